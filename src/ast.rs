@@ -1,3 +1,123 @@
+use std::fmt;
+
+use crate::span::Span;
+
+/// The type of a value, assigned to every expression by the type checker.
+///
+/// At run time every value occupies 64 bits. An integer narrower than that
+/// is kept sign-extended (signed types) or zero-extended (unsigned types)
+/// from its width, so that it can be compared, divided and printed as a
+/// 64-bit value. An `f64` is kept as its IEEE 754 bit pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    /// A double-precision floating-point number.
+    F64,
+    /// `true` or `false`, represented as 1 or 0 at run time.
+    Bool,
+}
+
+impl Type {
+    /// The type called `name` in a type annotation or cast.
+    pub fn from_name(name: &str) -> Option<Type> {
+        match name {
+            "i8" => Some(Type::I8),
+            "i16" => Some(Type::I16),
+            "i32" => Some(Type::I32),
+            "i64" => Some(Type::I64),
+            "u8" => Some(Type::U8),
+            "u16" => Some(Type::U16),
+            "u32" => Some(Type::U32),
+            "u64" => Some(Type::U64),
+            "f64" => Some(Type::F64),
+            "bool" => Some(Type::Bool),
+            _ => None,
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        !matches!(self, Type::F64 | Type::Bool)
+    }
+
+    /// Whether arithmetic applies: an integer type or `f64`.
+    pub fn is_numeric(self) -> bool {
+        self != Type::Bool
+    }
+
+    pub fn is_signed(self) -> bool {
+        matches!(self, Type::I8 | Type::I16 | Type::I32 | Type::I64)
+    }
+
+    /// Width of a numeric type in bits.
+    pub fn bits(self) -> u32 {
+        match self {
+            Type::I8 | Type::U8 => 8,
+            Type::I16 | Type::U16 => 16,
+            Type::I32 | Type::U32 => 32,
+            Type::I64 | Type::U64 | Type::F64 => 64,
+            Type::Bool => 1,
+        }
+    }
+
+    /// The smallest value of an integer type.
+    pub fn min(self) -> i128 {
+        if self.is_signed() {
+            -(1 << (self.bits() - 1))
+        } else {
+            0
+        }
+    }
+
+    /// The largest value of an integer type.
+    pub fn max(self) -> i128 {
+        if self.is_signed() {
+            (1 << (self.bits() - 1)) - 1
+        } else {
+            (1 << self.bits()) - 1
+        }
+    }
+
+    /// Wrap the 64-bit result of an operation on values of this integer
+    /// type into its range: keep the low `bits()` bits and sign- or
+    /// zero-extend them.
+    pub fn wrap(self, val: i64) -> i64 {
+        match self {
+            Type::I8 => val as i8 as i64,
+            Type::I16 => val as i16 as i64,
+            Type::I32 => val as i32 as i64,
+            Type::U8 => val as u8 as i64,
+            Type::U16 => val as u16 as i64,
+            Type::U32 => val as u32 as i64,
+            Type::I64 | Type::U64 | Type::F64 | Type::Bool => val,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Type::I8 => "i8",
+            Type::I16 => "i16",
+            Type::I32 => "i32",
+            Type::I64 => "i64",
+            Type::U8 => "u8",
+            Type::U16 => "u16",
+            Type::U32 => "u32",
+            Type::U64 => "u64",
+            Type::F64 => "f64",
+            Type::Bool => "bool",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinOp {
     Add,
@@ -5,23 +125,318 @@ pub enum BinOp {
     Mul,
     Div,
     Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitAnd,
+    BitOr,
+    BitXor,
+    /// Shift left. Only the low 6 bits of the shift count are used.
+    Shl,
+    /// Shift right: arithmetic (sign-propagating) for a signed left operand,
+    /// logical (zero-filling) for an unsigned one. Only the low 6 bits of
+    /// the shift count are used.
+    Shr,
+}
+
+impl BinOp {
+    /// The operator as written in source code.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::BitXor => "^",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+        }
+    }
+}
+
+/// Short-circuiting logical operators. The right operand is only evaluated
+/// when the left one does not already decide the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogicalOp {
+    And,
+    Or,
+}
+
+impl LogicalOp {
+    /// The operator as written in source code.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            LogicalOp::And => "&&",
+            LogicalOp::Or => "||",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Expr {
+    pub kind: ExprKind,
+    /// The source text of the expression, from its first token to its last.
+    /// Parentheses around the whole expression are not part of it.
+    pub span: Span,
+    /// Set by the type checker; `None` before it runs.
+    pub ty: Option<Type>,
+}
+
+impl Expr {
+    /// An expression spanning `span`, not yet type checked.
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Expr {
+            kind,
+            span,
+            ty: None,
+        }
+    }
 }
 
 #[derive(Debug)]
-pub enum Expr {
-    IntLit(i64),
+pub enum ExprKind {
+    /// An integer literal, of any integer type. A literal directly under a
+    /// unary minus may be one more than its type's maximum, so that every
+    /// type's minimum can be written.
+    IntLit(u64),
+    /// A float literal, of type `f64`.
+    FloatLit(f64),
+    BoolLit(bool),
     Var(String),
     UnaryMinus(Box<Expr>),
+    /// Logical not: the opposite `bool` for a `bool`, otherwise 1 if the
+    /// operand is 0 and 0 if not.
+    Not(Box<Expr>),
+    /// Bitwise not (`~`).
+    BitNot(Box<Expr>),
     BinOp {
         op: BinOp,
+        op_span: Span,
         left: Box<Expr>,
         right: Box<Expr>,
     },
+    Logical {
+        op: LogicalOp,
+        op_span: Span,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// `name[index]`, an element of an array. The out-of-bounds error points
+    /// at the start of the expression, `name`.
+    Index { name: String, index: Box<Expr> },
+    /// `len(name)`: the number of elements of an array.
+    Len(String),
+    /// `input()`: the next integer read from standard input.
+    Input,
+    /// `expr as ty`: a number or `bool` converted to a numeric type, or a
+    /// `bool` to `bool`. `op_span` is the span of `as`.
+    Cast {
+        expr: Box<Expr>,
+        ty: Type,
+        op_span: Span,
+    },
+}
+
+impl Expr {
+    /// The value of an integer literal, possibly negated, or `None` for any
+    /// other expression. Such a literal takes its type from its context, and
+    /// array indices with such a value are checked at compile time.
+    pub fn literal_value(&self) -> Option<i128> {
+        match &self.kind {
+            ExprKind::IntLit(val) => Some(*val as i128),
+            ExprKind::UnaryMinus(inner) => match inner.kind {
+                ExprKind::IntLit(val) => Some(-(val as i128)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Where errors about the expression point: the operator of a binary
+    /// operation or cast, and the whole expression otherwise.
+    pub fn anchor(&self) -> Span {
+        match &self.kind {
+            ExprKind::BinOp { op_span, .. }
+            | ExprKind::Logical { op_span, .. }
+            | ExprKind::Cast { op_span, .. } => *op_span,
+            _ => self.span,
+        }
+    }
+}
+
+/// A whole source file: function declarations (which may appear anywhere
+/// at the top level) and the top-level statements, which form the body of
+/// `main` in source order.
+#[derive(Debug)]
+pub struct Program {
+    pub functions: Vec<Function>,
+    pub stmts: Vec<Stmt>,
+    /// Names of variables, constants and functions whose declarations had
+    /// syntax errors and were left out. Any use of them is reported as the
+    /// syntax error, not as an undefined name.
+    pub lost_names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub name_span: Span,
+    pub params: Vec<String>,
+    pub body: Block,
+}
+
+#[derive(Debug)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    /// The source text of the statement, from its first token to its last.
+    /// Errors about the statement point at its start.
+    pub span: Span,
 }
 
 #[derive(Debug)]
-pub enum Stmt {
-    Let { name: String, expr: Expr },
+pub enum StmtKind {
+    /// `let name = expr;`, or `let name: ty = expr;` if `ty` is set.
+    Let {
+        name: String,
+        ty: Option<Type>,
+        expr: Expr,
+    },
+    /// `const name = expr;`, or `const name: ty = expr;` if `ty` is set: a
+    /// name for a value computed at compile time. `value`, the bits of that
+    /// value, is set by the type checker.
+    Const {
+        name: String,
+        ty: Option<Type>,
+        expr: Expr,
+        value: Option<i64>,
+    },
+    /// `let name = [init; len];`: an array of `len` elements, each
+    /// initialized to the value of `init`. With an annotation,
+    /// `let name: [ty; len] = ...`, the elements have type `ty`.
+    LetArray {
+        name: String,
+        ty: Option<Type>,
+        init: Expr,
+        len: usize,
+    },
     Assign { name: String, expr: Expr },
+    /// `name op= expr`, e.g. `x += 1`. `ty`, the type of `name`, is set by
+    /// the type checker.
+    CompoundAssign {
+        name: String,
+        op: BinOp,
+        expr: Expr,
+        ty: Option<Type>,
+    },
+    /// `name[index] = expr`, or `name[index] op= expr` if `op` is set. `ty`,
+    /// the element type, is set by the type checker.
+    AssignIndex {
+        name: String,
+        index: Expr,
+        op: Option<BinOp>,
+        expr: Expr,
+        ty: Option<Type>,
+    },
     Print { expr: Expr },
+    /// `print "format", args...;` or `write "format", args...;`, or
+    /// `write expr;`. Each `Arg` piece consumes the next argument.
+    PrintFormat {
+        pieces: Vec<FormatPiece>,
+        args: Vec<Expr>,
+        /// Whether a newline follows (`print`) or not (`write`).
+        newline: bool,
+    },
+    /// `if cond { ... } else { ... }`. An `else if` chain is represented
+    /// as an else block holding a single nested `If`.
+    If {
+        cond: Expr,
+        then_block: Block,
+        else_block: Option<Block>,
+    },
+    While { cond: Expr, body: Block },
+    /// A standalone `{ ... }` block, introducing a new scope.
+    Block(Block),
+    Break,
+    Continue,
+    Return { expr: Expr },
+    /// A function call evaluated for its side effects; its result is
+    /// discarded. The parser rejects any other expression here; `input()`
+    /// is allowed, and skips an integer of the input.
+    Expr { expr: Expr },
+}
+
+/// Part of a format string: literal text, or a `{...}` placeholder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatPiece {
+    Text(String),
+    Arg(FormatSpec),
+}
+
+/// How a placeholder formats its argument: `{:[0][width][x|X|b]}`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormatSpec {
+    pub radix: FormatRadix,
+    /// Minimum number of characters; shorter output is padded on the left.
+    pub width: usize,
+    /// Pad with zeros (after any sign) instead of spaces.
+    pub zero_pad: bool,
+}
+
+impl fmt::Display for FormatSpec {
+    /// Writes the placeholder back as it could have been written in source.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if *self == FormatSpec::default() {
+            return write!(f, "{{}}");
+        }
+        write!(f, "{{:")?;
+        if self.zero_pad {
+            write!(f, "0")?;
+        }
+        if self.width > 0 {
+            write!(f, "{}", self.width)?;
+        }
+        match self.radix {
+            FormatRadix::Decimal => {}
+            FormatRadix::LowerHex => write!(f, "x")?,
+            FormatRadix::UpperHex => write!(f, "X")?,
+            FormatRadix::Binary => write!(f, "b")?,
+        }
+        write!(f, "}}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FormatRadix {
+    /// Signed decimal (`{}`).
+    #[default]
+    Decimal,
+    /// Two's complement hexadecimal with lowercase digits (`{:x}`).
+    LowerHex,
+    /// Two's complement hexadecimal with uppercase digits (`{:X}`).
+    UpperHex,
+    /// Two's complement binary (`{:b}`).
+    Binary,
+}
+
+/// A braced sequence of statements. Variables declared inside a block are
+/// not visible after its closing brace.
+#[derive(Debug)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
 }
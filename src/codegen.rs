@@ -1,240 +1,1043 @@
 use std::collections::HashMap;
 use std::fmt::Write;
 
-use crate::ast::{BinOp, Expr, Stmt};
+use crate::ast::{
+    BinOp, Block, Expr, ExprKind, FormatPiece, FormatRadix, FormatSpec, LogicalOp, Program, Stmt,
+    StmtKind, Type,
+};
+use crate::diagnostic::{Diagnostic, Phase};
+use crate::span::{SourceMap, Span};
+use crate::target::Target;
 
-pub struct Codegen {
+pub struct Codegen<'t> {
+    target: &'t dyn Target,
+    /// The program's source, for the positions in runtime error messages.
+    source: &'t SourceMap,
     output: String,
-    /// Maps variable names to their offset from the frame pointer (x29).
-    /// Offsets are negative (variables are below the frame pointer).
-    variables: HashMap<String, i64>,
+    /// Maps variable names of the current function to their place in the
+    /// frame.
+    variables: HashMap<String, Slot>,
     /// Next available stack offset for a variable (grows downward).
     next_var_offset: i64,
-    /// Total number of variable slots allocated (used to size the stack frame).
-    var_count: usize,
+    /// Offset just above the next array to be allocated (grows downward).
+    /// Arrays live below all the variable slots.
+    next_array_offset: i64,
+    /// Number of local labels created so far (for unique label names).
+    label_count: usize,
+    /// `(continue, break)` jump targets of the enclosing loops, innermost last.
+    loops: Vec<(String, String)>,
+    /// Label of the current function's epilogue, the target of `return`.
+    return_label: String,
+    /// Distinct `printf` format strings, in order of first use. Each is
+    /// emitted once in the data section.
+    strings: Vec<String>,
+    /// Whether a `{:b}` placeholder was used, so the runtime helper that
+    /// prints binary must be generated.
+    uses_binary: bool,
+    /// Line of the statement being generated, which the interpreter's
+    /// division by zero errors name.
+    line: usize,
 }
 
-impl Codegen {
-    pub fn new() -> Self {
+/// Where a variable lives in the frame. Offsets are from the frame pointer
+/// and negative (variables are below the frame pointer).
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    /// An integer variable in the slot at this offset.
+    Scalar(i64),
+    /// A constant, which has no slot: uses load its value, these bits.
+    Const(i64),
+    /// An array of `len` elements, the first at `offset`.
+    Array { offset: i64, len: usize },
+}
+
+impl<'t> Codegen<'t> {
+    pub fn new(target: &'t dyn Target, source: &'t SourceMap) -> Self {
         Codegen {
+            target,
+            source,
             output: String::new(),
             variables: HashMap::new(),
-            next_var_offset: -8, // First variable at [x29, #-8]
-            var_count: 0,
+            next_var_offset: -8, // First variable at [fp, #-8]
+            next_array_offset: 0,
+            label_count: 0,
+            loops: Vec::new(),
+            return_label: String::new(),
+            strings: Vec::new(),
+            uses_binary: false,
+            line: 0,
         }
     }
 
-    /// Count the total number of variable slots needed by the program.
-    /// Each `let` statement allocates a new slot (even if shadowing).
+    /// Count the variable slots needed by a function body: the largest
+    /// number of variables live at once. Each `let` statement allocates a new
+    /// slot (even if shadowing) that stays allocated until the end of its
+    /// block, after which later declarations reuse it. Blocks are counted
+    /// whether or not they run; a `let` in a loop body has one slot, reused by
+    /// every iteration.
     fn count_variables(stmts: &[Stmt]) -> usize {
-        let mut count = 0;
+        Self::peak_live(stmts, Self::variable_slots)
+    }
+
+    /// Count the array elements needed by a function body: the largest
+    /// number live at once, with the same rules as `count_variables`.
+    fn count_array_elements(stmts: &[Stmt]) -> usize {
+        Self::peak_live(stmts, Self::array_elements)
+    }
+
+    /// The number of variable slots a statement declares.
+    fn variable_slots(kind: &StmtKind) -> usize {
+        matches!(kind, StmtKind::Let { .. }) as usize
+    }
+
+    /// The number of array elements a statement declares.
+    fn array_elements(kind: &StmtKind) -> usize {
+        match kind {
+            StmtKind::LetArray { len, .. } => *len,
+            _ => 0,
+        }
+    }
+
+    /// The largest total size of the declarations live at once in `stmts`,
+    /// where `size` gives the size declared by a statement. This recurses once
+    /// per nested block, which the parser's nesting limit keeps shallow.
+    fn peak_live(stmts: &[Stmt], size: fn(&StmtKind) -> usize) -> usize {
+        let mut live = 0;
+        let mut peak = 0;
         for stmt in stmts {
-            if matches!(stmt, Stmt::Let { .. }) {
-                count += 1;
+            let nested = match &stmt.kind {
+                StmtKind::If {
+                    then_block,
+                    else_block,
+                    ..
+                } => {
+                    let else_count = else_block
+                        .as_ref()
+                        .map_or(0, |else_block| Self::peak_live(&else_block.stmts, size));
+                    Self::peak_live(&then_block.stmts, size).max(else_count)
+                }
+                StmtKind::While { body, .. } => Self::peak_live(&body.stmts, size),
+                StmtKind::Block(block) => Self::peak_live(&block.stmts, size),
+                kind => {
+                    live += size(kind);
+                    0
+                }
+            };
+            peak = peak.max(live + nested);
+        }
+        peak
+    }
+
+    /// The first declaration in `stmts` that takes the total size of the
+    /// declarations live at once over `limit`, counted like `peak_live`
+    /// with `live` already taken, if there is one.
+    fn first_over_limit(
+        stmts: &[Stmt],
+        size: fn(&StmtKind) -> usize,
+        limit: usize,
+        mut live: usize,
+    ) -> Option<&Stmt> {
+        for stmt in stmts {
+            let over = match &stmt.kind {
+                StmtKind::If {
+                    then_block,
+                    else_block,
+                    ..
+                } => Self::first_over_limit(&then_block.stmts, size, limit, live).or_else(|| {
+                    let else_block = else_block.as_ref()?;
+                    Self::first_over_limit(&else_block.stmts, size, limit, live)
+                }),
+                StmtKind::While { body: block, .. } | StmtKind::Block(block) => {
+                    Self::first_over_limit(&block.stmts, size, limit, live)
+                }
+                kind => {
+                    live += size(kind);
+                    (live > limit).then_some(stmt)
+                }
+            };
+            if over.is_some() {
+                return over;
             }
         }
-        count
+        None
     }
 
-    /// Maximum number of `let` statements (including shadowing re-declarations).
+    /// Maximum number of variable slots per function, counting parameters and
+    /// shadowed variables that are still allocated (see `count_variables`).
     /// Limited by the ARM64 unscaled immediate offset range for `stur`/`ldur`
-    /// (offsets -8 to -256 from x29, giving 32 slots of 8 bytes each).
+    /// (offsets -8 to -256 from x29, giving 32 slots of 8 bytes each). The
+    /// limit applies to every target so programs are portable.
     const MAX_VARIABLES: usize = 32;
 
-    pub fn generate(mut self, stmts: &[Stmt]) -> Result<String, String> {
-        self.var_count = Self::count_variables(stmts);
-        if self.var_count > Self::MAX_VARIABLES {
-            return Err(format!(
-                "too many variables: {} declared, maximum is {}",
-                self.var_count,
-                Self::MAX_VARIABLES
-            ));
-        }
-
-        // Calculate stack frame size:
-        // - 16 bytes for saved x29 (frame pointer) and x30 (link register)
-        // - 8 bytes per variable
-        // - Round up to 16-byte alignment
-        let vars_size = (self.var_count as i64) * 8;
-        let frame_size = 16 + vars_size;
-        let frame_size = (frame_size + 15) & !15; // align to 16
-
-        // Data section
-        writeln!(self.output, ".section __DATA,__data").unwrap();
-        writeln!(self.output, "_fmt:").unwrap();
-        writeln!(self.output, "    .asciz \"%lld\\n\"").unwrap();
-        writeln!(self.output).unwrap();
-
-        // Text section
-        writeln!(self.output, ".section __TEXT,__text").unwrap();
-        writeln!(self.output, ".globl _main").unwrap();
-        writeln!(self.output, ".p2align 2").unwrap();
-        writeln!(self.output, "_main:").unwrap();
-
-        // Prologue: allocate stack frame, save frame pointer and link register
-        // Frame layout (high to low):
-        //   [x29+8]  = saved x30 (link register)
-        //   [x29]    = saved x29 (frame pointer)
-        //   [x29-8]  = variable 0
-        //   [x29-16] = variable 1
-        //   ...
-        //   [sp]     = bottom of frame
-        writeln!(self.output, "    sub sp, sp, #{frame_size}").unwrap();
-        writeln!(self.output, "    stp x29, x30, [sp, #{}]", frame_size - 16).unwrap();
-        writeln!(self.output, "    add x29, sp, #{}", frame_size - 16).unwrap();
+    /// Maximum number of array elements live at once per function, counted
+    /// like variables (see `count_array_elements`).
+    const MAX_ARRAY_ELEMENTS: usize = 4096;
+
+    /// Maximum number of arguments of a formatted print. They are all pushed
+    /// before anything is printed.
+    const MAX_FORMAT_ARGS: usize = 64;
+
+    /// Generate assembly for a program that passed the type checker, which
+    /// resolved every name; only the target's limits are checked here.
+    pub fn generate(mut self, program: &Program) -> Result<String, Diagnostic> {
+        let target = self.target;
+
+        // Text section: the top-level statements form `main`
+        writeln!(self.output, "{}", target.text_section()).unwrap();
+        self.gen_function(&target.symbol("main"), &[], &program.stmts, None)?;
+
+        for function in &program.functions {
+            writeln!(self.output).unwrap();
+            let symbol = Self::function_symbol(target, &function.name);
+            self.gen_function(
+                &symbol,
+                &function.params,
+                &function.body.stmts,
+                Some(&function.name),
+            )?;
+        }
+
+        if self.uses_binary {
+            writeln!(self.output).unwrap();
+            self.gen_print_binary();
+        }
+
+        target.emit_file_end(&mut self.output);
+
+        // Data section: emitted first, but generated last, once every format
+        // string has been seen
+        let mut data = String::new();
+        writeln!(data, "{}", target.data_section()).unwrap();
+        writeln!(data, "{}:", target.symbol("fmt")).unwrap();
+        writeln!(data, "    .asciz \"%lld\\n\"").unwrap();
+        for (index, text) in self.strings.iter().enumerate() {
+            writeln!(data, "{}:", Self::string_label(target, index)).unwrap();
+            writeln!(data, "    .asciz \"{}\"", escape_asm_string(text)).unwrap();
+        }
+        writeln!(data).unwrap();
+
+        Ok(data + &self.output)
+    }
+
+    /// Data label of the `index`th distinct format string.
+    fn string_label(target: &dyn Target, index: usize) -> String {
+        target.symbol(&format!("str{index}"))
+    }
+
+    /// Data label of a format string, adding it to the data section the
+    /// first time it is used.
+    fn intern_string(&mut self, text: &str) -> String {
+        let index = match self.strings.iter().position(|s| s == text) {
+            Some(index) => index,
+            None => {
+                self.strings.push(text.to_string());
+                self.strings.len() - 1
+            }
+        };
+        Self::string_label(self.target, index)
+    }
+
+    /// Generate the runtime helper behind `{:b}` placeholders, which
+    /// `printf` cannot format: `toyrt_print_binary(x, width, zero)` prints
+    /// the bits of `x`, as unsigned, in binary, padded to `width` characters
+    /// with zeros if `zero` is nonzero, otherwise with spaces. Its symbol
+    /// cannot collide with those of Toy functions (see `function_symbol`).
+    fn gen_print_binary(&mut self) {
+        // Slots of the parameters and of the number of digits
+        const X: i64 = -8;
+        const WIDTH: i64 = -16;
+        const ZERO: i64 = -24;
+        const DIGITS: i64 = -32;
+        const VARS_SIZE: i64 = 32;
+
+        let target = self.target;
+        let [count, pad, space, padded, digit, end] = std::array::from_fn(|_| self.new_label());
+        let zero_fmt = self.intern_string("0");
+        let space_fmt = self.intern_string(" ");
+        let digit_fmt = self.intern_string("%lld");
+        // Compute `<slot> op <value>` into the primary register, where
+        // `load` loads the value
+        let slot_op = |out: &mut String, slot: i64, op: BinOp, load: &dyn Fn(&mut String)| {
+            target.emit_load_local(out, slot);
+            target.emit_push(out);
+            load(out);
+            target.emit_pop_secondary(out);
+            target.emit_binop(out, op, true);
+        };
+        let digits = |out: &mut String| target.emit_load_local(out, DIGITS);
+        let one = |out: &mut String| target.emit_load_immediate(out, 1);
+        let out = &mut self.output;
+
+        target.emit_function_label(out, &target.symbol("toyrt_print_binary"));
+        target.emit_prologue(out, VARS_SIZE);
+        for (index, slot) in [X, WIDTH, ZERO].into_iter().enumerate() {
+            target.emit_store_param(out, index, slot);
+        }
+
+        // digits = 1; while digits < 64 && x >> digits != 0 { digits += 1; }
+        one(out);
+        target.emit_store_local(out, DIGITS);
+        target.emit_label(out, &count);
+        slot_op(out, DIGITS, BinOp::Lt, &|out| {
+            target.emit_load_immediate(out, 64)
+        });
+        target.emit_branch_if_zero(out, &pad);
+        slot_op(out, X, BinOp::Shr, &digits);
+        target.emit_branch_if_zero(out, &pad);
+        slot_op(out, DIGITS, BinOp::Add, &one);
+        target.emit_store_local(out, DIGITS);
+        target.emit_jump(out, &count);
+
+        // while width > digits { write zero != 0 ? "0" : " "; width -= 1; }
+        target.emit_label(out, &pad);
+        slot_op(out, WIDTH, BinOp::Gt, &digits);
+        target.emit_branch_if_zero(out, &digit);
+        target.emit_load_local(out, ZERO);
+        target.emit_branch_if_zero(out, &space);
+        target.emit_print(out, &zero_fmt);
+        target.emit_jump(out, &padded);
+        target.emit_label(out, &space);
+        target.emit_print(out, &space_fmt);
+        target.emit_label(out, &padded);
+        slot_op(out, WIDTH, BinOp::Sub, &one);
+        target.emit_store_local(out, WIDTH);
+        target.emit_jump(out, &pad);
+
+        // while digits > 0 { digits -= 1; write x >> digits & 1; }
+        target.emit_label(out, &digit);
+        digits(out);
+        target.emit_branch_if_zero(out, &end);
+        slot_op(out, DIGITS, BinOp::Sub, &one);
+        target.emit_store_local(out, DIGITS);
+        slot_op(out, X, BinOp::Shr, &digits);
+        target.emit_push(out);
+        one(out);
+        target.emit_pop_secondary(out);
+        target.emit_binop(out, BinOp::BitAnd, true);
+        target.emit_print(out, &digit_fmt);
+        target.emit_jump(out, &digit);
+
+        target.emit_label(out, &end);
+        target.emit_epilogue(out, VARS_SIZE);
+    }
+
+    /// Assembly symbol for a Toy function. The prefix keeps user functions
+    /// from colliding with `main` or with C library functions like `printf`.
+    fn function_symbol(target: &dyn Target, name: &str) -> String {
+        target.symbol(&format!("toy_{name}"))
+    }
+
+    /// Generate one function with its own frame. `name` is the Toy name, or
+    /// `None` for `main`. Parameters occupy the first variable slots.
+    fn gen_function(
+        &mut self,
+        symbol: &str,
+        params: &[String],
+        stmts: &[Stmt],
+        name: Option<&str>,
+    ) -> Result<(), Diagnostic> {
+        let var_count = params.len() + Self::count_variables(stmts);
+        if var_count > Self::MAX_VARIABLES {
+            let message = match name {
+                Some(name) => format!(
+                    "too many variables in function '{}': {} live at once, maximum is {}",
+                    name,
+                    var_count,
+                    Self::MAX_VARIABLES
+                ),
+                None => format!(
+                    "too many variables: {} live at once, maximum is {}",
+                    var_count,
+                    Self::MAX_VARIABLES
+                ),
+            };
+            // The parameters alone are under the limit, so a `let` is over
+            let over = Self::first_over_limit(
+                stmts,
+                Self::variable_slots,
+                Self::MAX_VARIABLES,
+                params.len(),
+            )
+            .unwrap();
+            return Err(Diagnostic::error(Phase::Codegen, "E0401", message)
+                .with_label(over.span, "first variable over the limit"));
+        }
+
+        let element_count = Self::count_array_elements(stmts);
+        if element_count > Self::MAX_ARRAY_ELEMENTS {
+            let message = match name {
+                Some(name) => format!(
+                    "arrays in function '{}' are too large: {} elements live at once, maximum is {}",
+                    name,
+                    element_count,
+                    Self::MAX_ARRAY_ELEMENTS
+                ),
+                None => format!(
+                    "arrays are too large: {} elements live at once, maximum is {}",
+                    element_count,
+                    Self::MAX_ARRAY_ELEMENTS
+                ),
+            };
+            let over =
+                Self::first_over_limit(stmts, Self::array_elements, Self::MAX_ARRAY_ELEMENTS, 0)
+                    .unwrap();
+            return Err(Diagnostic::error(Phase::Codegen, "E0402", message)
+                .with_label(over.span, "first array over the limit"));
+        }
+
+        // 8 bytes per variable and array element, rounded up to keep the
+        // stack 16-byte aligned
+        let vars_size = ((var_count + element_count) as i64) * 8;
+        let vars_size = (vars_size + 15) & !15; // align to 16
+
+        let target = self.target;
+        self.variables.clear();
+        self.next_var_offset = -8;
+        self.next_array_offset = -(var_count as i64) * 8;
+
+        target.emit_function_label(&mut self.output, symbol);
+        target.emit_prologue(&mut self.output, vars_size);
+
+        // Spill the parameters from their argument registers to their slots
+        for (index, param) in params.iter().enumerate() {
+            let offset = self.next_var_offset;
+            self.next_var_offset -= 8;
+            self.variables.insert(param.clone(), Slot::Scalar(offset));
+            target.emit_store_param(&mut self.output, index, offset);
+        }
+
+        // `return` is only allowed inside a function, so `main` needs no label
+        if name.is_some() {
+            self.return_label = self.new_label();
+        }
 
         // Generate code for each statement
         for stmt in stmts {
             self.gen_stmt(stmt)?;
         }
 
-        // Epilogue: return 0
-        writeln!(self.output, "    mov x0, #0").unwrap();
-        writeln!(self.output, "    ldp x29, x30, [sp, #{}]", frame_size - 16).unwrap();
-        writeln!(self.output, "    add sp, sp, #{frame_size}").unwrap();
-        writeln!(self.output, "    ret").unwrap();
-
-        Ok(self.output)
+        // Epilogue: falling off the end returns 0
+        target.emit_load_immediate(&mut self.output, 0);
+        if name.is_some() {
+            target.emit_label(&mut self.output, &self.return_label);
+        }
+        target.emit_epilogue(&mut self.output, vars_size);
+        Ok(())
     }
 
-    fn gen_stmt(&mut self, stmt: &Stmt) -> Result<(), String> {
-        match stmt {
-            Stmt::Let { name, expr } => {
-                // Evaluate the expression (result in x0).
+    fn gen_stmt(&mut self, stmt: &Stmt) -> Result<(), Diagnostic> {
+        self.line = self.source.line_col(stmt.span.start).0;
+        match &stmt.kind {
+            StmtKind::Let { name, expr, .. } => {
+                // Evaluate the expression (result in the primary register).
                 // Important: evaluate BEFORE allocating the new slot,
                 // so that `let x = x + 1;` reads the old x.
                 self.gen_expr(expr)?;
                 // Allocate a new variable slot
                 let offset = self.next_var_offset;
                 self.next_var_offset -= 8;
-                self.variables.insert(name.clone(), offset);
+                self.variables.insert(name.clone(), Slot::Scalar(offset));
                 // Store the value
-                writeln!(self.output, "    str x0, [x29, #{}]", offset).unwrap();
+                self.target.emit_store_local(&mut self.output, offset);
+                Ok(())
+            }
+            StmtKind::Const { name, value, .. } => {
+                self.variables
+                    .insert(name.clone(), Slot::Const(value.unwrap()));
+                Ok(())
+            }
+            StmtKind::LetArray {
+                name, init, len, ..
+            } => {
+                // As for `let`, the initializer cannot see the new array
+                self.gen_expr(init)?;
+                self.next_array_offset -= 8 * *len as i64;
+                let offset = self.next_array_offset;
+                self.variables
+                    .insert(name.clone(), Slot::Array { offset, len: *len });
+                self.target.emit_fill_array(&mut self.output, offset, *len);
                 Ok(())
             }
-            Stmt::Assign { name, expr } => {
-                let offset = *self.variables.get(name).ok_or_else(|| {
-                    format!("undefined variable '{}'", name)
-                })?;
+            StmtKind::Assign { name, expr } => {
+                let offset = self.scalar(name);
                 self.gen_expr(expr)?;
-                writeln!(self.output, "    str x0, [x29, #{}]", offset).unwrap();
+                self.target.emit_store_local(&mut self.output, offset);
                 Ok(())
             }
-            Stmt::Print { expr } => {
+            StmtKind::CompoundAssign { name, op, expr, ty } => {
+                // Like `name = name op expr`, but the slot is addressed once
+                // and `name` is loaded once.
+                let offset = self.scalar(name);
+                self.target.emit_load_local(&mut self.output, offset);
+                self.target.emit_push(&mut self.output);
                 self.gen_expr(expr)?;
-                // On ARM64 macOS, variadic arguments to printf are passed on
-                // the stack, not in registers. The format string (named param)
-                // goes in x0. The variadic i64 value goes at [sp].
-                // We need to allocate stack space for the variadic arg.
-                writeln!(self.output, "    str x0, [sp, #-16]!").unwrap();
-                // Load format string address into x0 (first arg).
-                self.gen_load_address("x0", "_fmt");
-                // Call printf
-                writeln!(self.output, "    bl _printf").unwrap();
-                // Restore stack
-                writeln!(self.output, "    add sp, sp, #16").unwrap();
+                self.gen_divisor_check(*op, ty.unwrap(), expr);
+                self.target.emit_pop_secondary(&mut self.output);
+                self.gen_binop(*op, ty.unwrap());
+                self.target.emit_store_local(&mut self.output, offset);
                 Ok(())
             }
+            StmtKind::Print { expr } if expr.ty != Some(Type::I64) => {
+                let pieces = [FormatPiece::Arg(FormatSpec::default())];
+                self.gen_print_format(&pieces, std::slice::from_ref(expr), true)
+            }
+            StmtKind::Print { expr } => {
+                self.gen_expr(expr)?;
+                let fmt = self.target.symbol("fmt");
+                self.target.emit_print(&mut self.output, &fmt);
+                Ok(())
+            }
+            StmtKind::AssignIndex {
+                name,
+                index,
+                op,
+                expr,
+                ty,
+            } => {
+                let offset = self.gen_index(name, index, stmt.span)?;
+                self.target.emit_push(&mut self.output);
+                if let Some(op) = op {
+                    // Like CompoundAssign: combine the old element with expr
+                    self.target.emit_load_stack(&mut self.output, 0);
+                    self.target.emit_load_element(&mut self.output, offset);
+                    self.target.emit_push(&mut self.output);
+                    self.gen_expr(expr)?;
+                    self.gen_divisor_check(*op, ty.unwrap(), expr);
+                    self.target.emit_pop_secondary(&mut self.output);
+                    self.gen_binop(*op, ty.unwrap());
+                } else {
+                    self.gen_expr(expr)?;
+                }
+                self.target.emit_pop_secondary(&mut self.output);
+                self.target.emit_store_element(&mut self.output, offset);
+                Ok(())
+            }
+            StmtKind::PrintFormat {
+                pieces,
+                args,
+                newline,
+            } => self.gen_print_format(pieces, args, *newline),
+            StmtKind::If {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                let else_label = self.new_label();
+                self.gen_expr(cond)?;
+                self.target.emit_branch_if_zero(&mut self.output, &else_label);
+                self.gen_block(then_block)?;
+                match else_block {
+                    Some(else_block) => {
+                        let end_label = self.new_label();
+                        self.target.emit_jump(&mut self.output, &end_label);
+                        self.target.emit_label(&mut self.output, &else_label);
+                        self.gen_block(else_block)?;
+                        self.target.emit_label(&mut self.output, &end_label);
+                    }
+                    None => self.target.emit_label(&mut self.output, &else_label),
+                }
+                Ok(())
+            }
+            StmtKind::While { cond, body } => {
+                let cond_label = self.new_label();
+                let end_label = self.new_label();
+                self.target.emit_label(&mut self.output, &cond_label);
+                self.gen_expr(cond)?;
+                self.target.emit_branch_if_zero(&mut self.output, &end_label);
+                self.loops.push((cond_label.clone(), end_label.clone()));
+                self.gen_block(body)?;
+                self.loops.pop();
+                self.target.emit_jump(&mut self.output, &cond_label);
+                self.target.emit_label(&mut self.output, &end_label);
+                Ok(())
+            }
+            StmtKind::Block(block) => self.gen_block(block),
+            StmtKind::Break | StmtKind::Continue => {
+                // The parser only accepts these inside a loop.
+                let (continue_label, break_label) = self.loops.last().unwrap();
+                let label = if matches!(stmt.kind, StmtKind::Break) {
+                    break_label
+                } else {
+                    continue_label
+                };
+                self.target.emit_jump(&mut self.output, label);
+                Ok(())
+            }
+            StmtKind::Return { expr } => {
+                // The primary register doubles as the return value register.
+                self.gen_expr(expr)?;
+                self.target.emit_jump(&mut self.output, &self.return_label);
+                Ok(())
+            }
+            StmtKind::Expr { expr } => self.gen_expr(expr),
+        }
+    }
+
+    /// Generate a formatted print. Every argument is evaluated and pushed
+    /// first. Then each decimal, hexadecimal or `f64` placeholder becomes a
+    /// `printf` call whose format is the text before it followed by the
+    /// conversion;
+    /// binary placeholders call the runtime helper, and a `bool` selects one
+    /// of two formats ending in `true` or `false`. Text after the last
+    /// placeholder is printed on its own.
+    fn gen_print_format(
+        &mut self,
+        pieces: &[FormatPiece],
+        args: &[Expr],
+        newline: bool,
+    ) -> Result<(), Diagnostic> {
+        if args.len() > Self::MAX_FORMAT_ARGS {
+            let message = format!(
+                "too many arguments to print: {}, maximum is {}",
+                args.len(),
+                Self::MAX_FORMAT_ARGS
+            );
+            return Err(
+                Diagnostic::error(Phase::Codegen, "E0403", message).with_label(
+                    args[Self::MAX_FORMAT_ARGS].span,
+                    "first argument over the limit",
+                ),
+            );
+        }
+        for arg in args {
+            self.gen_expr(arg)?;
+            self.target.emit_push(&mut self.output);
+        }
+
+        let target = self.target;
+        // Offset of the `index`th argument above the stack pointer
+        let arg_offset = |index: usize| 16 * (args.len() - 1 - index) as i64;
+        let mut fmt = String::new();
+        let mut newline = newline;
+        let mut index = 0;
+        for (position, piece) in pieces.iter().enumerate() {
+            match piece {
+                FormatPiece::Text(text) => fmt.push_str(&text.replace('%', "%%")),
+                FormatPiece::Arg(spec) if args[index].ty == Some(Type::Bool) => {
+                    let last = newline && position == pieces.len() - 1;
+                    let [true_fmt, false_fmt] = ["true", "false"].map(|word| {
+                        let mut fmt = format!("{fmt}{word:>width$}", width = spec.width);
+                        if last {
+                            fmt.push('\n');
+                        }
+                        fmt
+                    });
+                    fmt.clear();
+                    newline &= !last;
+                    let false_label = self.new_label();
+                    let end_label = self.new_label();
+                    target.emit_load_stack(&mut self.output, arg_offset(index));
+                    target.emit_branch_if_zero(&mut self.output, &false_label);
+                    let label = self.intern_string(&true_fmt);
+                    target.emit_print(&mut self.output, &label);
+                    target.emit_jump(&mut self.output, &end_label);
+                    target.emit_label(&mut self.output, &false_label);
+                    let label = self.intern_string(&false_fmt);
+                    target.emit_print(&mut self.output, &label);
+                    target.emit_label(&mut self.output, &end_label);
+                    index += 1;
+                }
+                FormatPiece::Arg(spec) if spec.radix == FormatRadix::Binary => {
+                    self.flush_format(&mut fmt);
+                    self.uses_binary = true;
+                    target.emit_load_stack(&mut self.output, arg_offset(index));
+                    // The helper prints the bits of `x`, as unsigned, so a
+                    // narrower signed value is cut to its own width first
+                    let ty = args[index].ty.unwrap();
+                    if ty.bits() < 64 && ty.is_signed() {
+                        target.emit_wrap(&mut self.output, ty.bits(), false);
+                    }
+                    target.emit_push(&mut self.output);
+                    target.emit_load_immediate(&mut self.output, spec.width as i64);
+                    target.emit_push(&mut self.output);
+                    target.emit_load_immediate(&mut self.output, spec.zero_pad as i64);
+                    target.emit_push(&mut self.output);
+                    for arg in (0..3).rev() {
+                        target.emit_pop_arg(&mut self.output, arg);
+                    }
+                    let symbol = target.symbol("toyrt_print_binary");
+                    target.emit_call(&mut self.output, &symbol);
+                    index += 1;
+                }
+                FormatPiece::Arg(spec) => {
+                    let ty = args[index].ty.unwrap();
+                    let flag = if spec.zero_pad { "0" } else { "" };
+                    let width = if spec.width > 0 {
+                        spec.width.to_string()
+                    } else {
+                        String::new()
+                    };
+                    // `printf` converts the argument to the type's width, so
+                    // hexadecimal shows only the type's bits
+                    let length = match (ty, ty.bits()) {
+                        (Type::F64, _) => "",
+                        (_, 8) => "hh",
+                        (_, 16) => "h",
+                        (_, 32) => "",
+                        _ => "ll",
+                    };
+                    let conversion = match spec.radix {
+                        FormatRadix::LowerHex => "x",
+                        FormatRadix::UpperHex => "X",
+                        _ if ty == Type::F64 => "g",
+                        _ if ty.is_signed() => "d",
+                        _ => "u",
+                    };
+                    write!(fmt, "%{flag}{width}{length}{conversion}").unwrap();
+                    if newline && position == pieces.len() - 1 {
+                        // Print the newline with the last conversion
+                        fmt.push('\n');
+                        newline = false;
+                    }
+                    target.emit_load_stack(&mut self.output, arg_offset(index));
+                    let label = self.intern_string(&std::mem::take(&mut fmt));
+                    if ty == Type::F64 {
+                        target.emit_print_float(&mut self.output, &label);
+                    } else {
+                        target.emit_print(&mut self.output, &label);
+                    }
+                    index += 1;
+                }
+            }
         }
+        if newline {
+            fmt.push('\n');
+        }
+        self.flush_format(&mut fmt);
+
+        if !args.is_empty() {
+            target.emit_stack_free(&mut self.output, 16 * args.len() as i64);
+        }
+        Ok(())
+    }
+
+    /// Print the text collected in `fmt`, a `printf` format without
+    /// conversions, if there is any, and clear it.
+    fn flush_format(&mut self, fmt: &mut String) {
+        if fmt.is_empty() {
+            return;
+        }
+        // `printf` ignores whatever is in the argument register
+        let label = self.intern_string(&std::mem::take(fmt));
+        self.target.emit_print(&mut self.output, &label);
     }
 
-    fn gen_load_address(&mut self, reg: &str, label: &str) {
-        // Use adrp + add to form a PC-relative address (required on macOS ARM64)
-        writeln!(self.output, "    adrp {reg}, {label}@PAGE").unwrap();
-        writeln!(self.output, "    add {reg}, {reg}, {label}@PAGEOFF").unwrap();
+    /// Generate a block. Variables it declares go out of scope at the end,
+    /// restoring any outer variables they shadowed, and their slots become
+    /// free for later declarations.
+    fn gen_block(&mut self, block: &Block) -> Result<(), Diagnostic> {
+        let outer = self.variables.clone();
+        let outer_offset = self.next_var_offset;
+        let outer_array_offset = self.next_array_offset;
+        for stmt in &block.stmts {
+            self.gen_stmt(stmt)?;
+        }
+        self.variables = outer;
+        self.next_var_offset = outer_offset;
+        self.next_array_offset = outer_array_offset;
+        Ok(())
+    }
+
+    /// Offset of the scalar variable `name`. The type checker resolved every
+    /// name, so it exists and is not an array.
+    fn scalar(&self, name: &str) -> i64 {
+        match self.variables[name] {
+            Slot::Scalar(offset) => offset,
+            Slot::Const(_) | Slot::Array { .. } => unreachable!(),
+        }
+    }
+
+    /// Offset and length of the array `name`.
+    fn array(&self, name: &str) -> (i64, usize) {
+        match self.variables[name] {
+            Slot::Array { offset, len } => (offset, len),
+            Slot::Scalar(_) | Slot::Const(_) => unreachable!(),
+        }
+    }
+
+    /// Evaluate an index into the array `name`, leaving it in the primary
+    /// register, and return the array's offset. Unless the index is a
+    /// literal, which the type checker checked against the array's length,
+    /// an out-of-bounds index ends the program with an error at run time.
+    /// `span` locates the indexing for that error.
+    fn gen_index(&mut self, name: &str, index: &Expr, span: Span) -> Result<i64, Diagnostic> {
+        let (offset, len) = self.array(name);
+        self.gen_expr(index)?;
+        if index.literal_value().is_some() {
+            return Ok(offset);
+        }
+        let ok_label = self.new_label();
+        self.target
+            .emit_branch_if_below(&mut self.output, len, &ok_label);
+        let (line, col) = self.source.line_col(span.start);
+        // Same message as the interpreter's runtime error
+        let fmt = format!(
+            "Runtime error: {}:{}: index %lld is out of bounds for array '{}' of length {}\n",
+            line, col, name, len
+        );
+        let fmt = self.intern_string(&fmt);
+        self.target.emit_runtime_error(&mut self.output, &fmt);
+        self.target.emit_label(&mut self.output, &ok_label);
+        Ok(offset)
+    }
+
+    /// Read an integer from standard input into the primary register, like
+    /// the interpreter: `scanf` reads it into a stack slot, and the program
+    /// ends with an error at the end of the input or if the input is not an
+    /// integer. `span` locates the `input()` for those errors.
+    fn gen_input(&mut self, span: Span) {
+        let fmt = self.intern_string("%lld");
+        let invalid_label = self.new_label();
+        let ok_label = self.new_label();
+        self.target.emit_push(&mut self.output);
+        self.target.emit_scan(&mut self.output, &fmt);
+        // scanf returns 1 if it read an integer, 0 if the input is not one
+        // and -1 at the end of the input; as unsigned, only 0 is below 1
+        // and only 1 is then below 2
+        self.target
+            .emit_branch_if_below(&mut self.output, 1, &invalid_label);
+        self.target
+            .emit_branch_if_below(&mut self.output, 2, &ok_label);
+        // Same messages as the interpreter's runtime errors
+        let (line, col) = self.source.line_col(span.start);
+        let eof = format!("Runtime error: {}:{}: unexpected end of input\n", line, col);
+        let eof = self.intern_string(&eof);
+        self.target.emit_runtime_error(&mut self.output, &eof);
+        self.target.emit_label(&mut self.output, &invalid_label);
+        let invalid = format!(
+            "Runtime error: {}:{}: expected an integer in the input\n",
+            line, col
+        );
+        let invalid = self.intern_string(&invalid);
+        self.target.emit_runtime_error(&mut self.output, &invalid);
+        self.target.emit_label(&mut self.output, &ok_label);
+        self.target.emit_load_stack(&mut self.output, 0);
+        self.target.emit_stack_free(&mut self.output, 16);
+    }
+
+    /// If `op` is an integer division or modulo, end the program with a
+    /// runtime error when the divisor, `divisor` in the primary register, is
+    /// zero, rather than trap or give a result that depends on the target.
+    fn gen_divisor_check(&mut self, op: BinOp, ty: Type, divisor: &Expr) {
+        let what = match op {
+            BinOp::Div => "division",
+            BinOp::Mod => "modulo",
+            _ => return,
+        };
+        if ty == Type::F64 || divisor.literal_value().is_some_and(|val| val != 0) {
+            return;
+        }
+        let ok_label = self.new_label();
+        self.target
+            .emit_branch_if_nonzero(&mut self.output, &ok_label);
+        // Same message as the interpreter's runtime error
+        let fmt = format!("Runtime error: line {}: {} by zero\n", self.line, what);
+        let fmt = self.intern_string(&fmt);
+        self.target.emit_runtime_error(&mut self.output, &fmt);
+        self.target.emit_label(&mut self.output, &ok_label);
+    }
+
+    /// Compute `secondary <op> primary`, where the left operand has type
+    /// `ty`, wrapping an integer result into the range of `ty`.
+    fn gen_binop(&mut self, op: BinOp, ty: Type) {
+        if ty == Type::F64 {
+            self.target.emit_float_binop(&mut self.output, op);
+            return;
+        }
+        self.target.emit_binop(&mut self.output, op, ty.is_signed());
+        // The other operators cannot leave the range of their operands
+        let overflows = match op {
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Shl => true,
+            // Only the minimum divided by -1, e.g. -128 / -1 for i8
+            BinOp::Div => ty.is_signed(),
+            _ => false,
+        };
+        if overflows {
+            self.gen_wrap(ty);
+        }
+    }
+
+    /// Wrap the primary register into the range of `ty` if it is an integer
+    /// type narrower than 64 bits.
+    fn gen_wrap(&mut self, ty: Type) {
+        if ty.is_integer() && ty.bits() < 64 {
+            self.target
+                .emit_wrap(&mut self.output, ty.bits(), ty.is_signed());
+        }
+    }
+
+    /// Jump to `label` if the primary register decides the result of a
+    /// logical operator on its own: zero for `&&`, nonzero for `||`.
+    fn emit_logical_branch(&mut self, op: LogicalOp, label: &str) {
+        match op {
+            LogicalOp::And => self.target.emit_branch_if_zero(&mut self.output, label),
+            LogicalOp::Or => self.target.emit_branch_if_nonzero(&mut self.output, label),
+        }
     }
 
-    fn gen_expr(&mut self, expr: &Expr) -> Result<(), String> {
-        match expr {
-            Expr::IntLit(val) => {
-                self.gen_load_immediate(*val);
+    fn new_label(&mut self) -> String {
+        let label = self.target.local_label(self.label_count);
+        self.label_count += 1;
+        label
+    }
+
+    fn gen_expr(&mut self, expr: &Expr) -> Result<(), Diagnostic> {
+        match &expr.kind {
+            ExprKind::IntLit(val) => {
+                // The type checker checked that it fits its type, so a
+                // literal above i64::MAX is a u64 with the same bits
+                self.target.emit_load_immediate(&mut self.output, *val as i64);
+                Ok(())
+            }
+            ExprKind::FloatLit(val) => {
+                self.target
+                    .emit_load_immediate(&mut self.output, val.to_bits() as i64);
+                Ok(())
+            }
+            ExprKind::BoolLit(val) => {
+                self.target.emit_load_immediate(&mut self.output, *val as i64);
+                Ok(())
+            }
+            ExprKind::Var(name) => {
+                match self.variables[name.as_str()] {
+                    Slot::Const(val) => self.target.emit_load_immediate(&mut self.output, val),
+                    _ => {
+                        let offset = self.scalar(name);
+                        self.target.emit_load_local(&mut self.output, offset);
+                    }
+                }
+                Ok(())
+            }
+            ExprKind::Index { name, index } => {
+                let offset = self.gen_index(name, index, expr.span)?;
+                self.target.emit_load_element(&mut self.output, offset);
+                Ok(())
+            }
+            ExprKind::Len(name) => {
+                let (_, len) = self.array(name);
+                self.target.emit_load_immediate(&mut self.output, len as i64);
+                Ok(())
+            }
+            ExprKind::Input => {
+                self.gen_input(expr.span);
+                Ok(())
+            }
+            ExprKind::UnaryMinus(inner) if expr.ty == Some(Type::F64) => {
+                self.gen_expr(inner)?;
+                self.target.emit_float_neg(&mut self.output);
+                Ok(())
+            }
+            ExprKind::UnaryMinus(inner) => {
+                self.gen_expr(inner)?;
+                self.target.emit_neg(&mut self.output);
+                self.gen_wrap(expr.ty.unwrap());
                 Ok(())
             }
-            Expr::Var(name) => {
-                let offset = *self.variables.get(name).ok_or_else(|| {
-                    format!("undefined variable '{}'", name)
-                })?;
-                writeln!(self.output, "    ldr x0, [x29, #{}]", offset).unwrap();
+            ExprKind::Not(inner) => {
+                self.gen_expr(inner)?;
+                self.target.emit_not(&mut self.output);
                 Ok(())
             }
-            Expr::UnaryMinus(inner) => {
+            ExprKind::BitNot(inner) => {
                 self.gen_expr(inner)?;
-                writeln!(self.output, "    neg x0, x0").unwrap();
+                self.target.emit_bit_not(&mut self.output);
+                self.gen_wrap(expr.ty.unwrap());
                 Ok(())
             }
-            Expr::BinOp { op, left, right } => {
-                // Evaluate left side, result in x0
+            ExprKind::Logical {
+                op, left, right, ..
+            } => {
+                // `a && b`: if a is 0 the result is 0 without evaluating b.
+                // `a || b`: if a is nonzero the result is 1 without evaluating b.
+                // Otherwise the result is b, normalized to 0/1.
+                let short_label = self.new_label();
+                let end_label = self.new_label();
                 self.gen_expr(left)?;
-                // Push x0 onto the stack (save left result)
-                writeln!(self.output, "    str x0, [sp, #-16]!").unwrap();
-                // Evaluate right side, result in x0
+                self.emit_logical_branch(*op, &short_label);
                 self.gen_expr(right)?;
-                // Pop left result into x1
-                writeln!(self.output, "    ldr x1, [sp], #16").unwrap();
-                // Now: x1 = left, x0 = right
-                // Compute result into x0
-                match op {
-                    BinOp::Add => {
-                        writeln!(self.output, "    add x0, x1, x0").unwrap();
-                    }
-                    BinOp::Sub => {
-                        writeln!(self.output, "    sub x0, x1, x0").unwrap();
-                    }
-                    BinOp::Mul => {
-                        writeln!(self.output, "    mul x0, x1, x0").unwrap();
-                    }
-                    BinOp::Div => {
-                        writeln!(self.output, "    sdiv x0, x1, x0").unwrap();
-                    }
-                    BinOp::Mod => {
-                        // ARM64 has no remainder instruction.
-                        // a % b = a - (a / b) * b
-                        // x1 = left (a), x0 = right (b)
-                        writeln!(self.output, "    sdiv x2, x1, x0").unwrap();
-                        writeln!(self.output, "    msub x0, x2, x0, x1").unwrap();
+                self.emit_logical_branch(*op, &short_label);
+                let (short_val, other_val) = match op {
+                    LogicalOp::And => (0, 1),
+                    LogicalOp::Or => (1, 0),
+                };
+                self.target.emit_load_immediate(&mut self.output, other_val);
+                self.target.emit_jump(&mut self.output, &end_label);
+                self.target.emit_label(&mut self.output, &short_label);
+                self.target.emit_load_immediate(&mut self.output, short_val);
+                self.target.emit_label(&mut self.output, &end_label);
+                Ok(())
+            }
+            ExprKind::BinOp {
+                op, left, right, ..
+            } => {
+                // Evaluate left side and save it on the stack
+                self.gen_expr(left)?;
+                self.target.emit_push(&mut self.output);
+                // Evaluate right side, then pop the left result into the
+                // secondary register and combine the two
+                self.gen_expr(right)?;
+                self.gen_divisor_check(*op, left.ty.unwrap(), right);
+                self.target.emit_pop_secondary(&mut self.output);
+                self.gen_binop(*op, left.ty.unwrap());
+                Ok(())
+            }
+            ExprKind::Cast {
+                expr: inner, ty, ..
+            } => {
+                self.gen_expr(inner)?;
+                let from = inner.ty.unwrap();
+                match (from, *ty) {
+                    (Type::F64, Type::F64) => {}
+                    // Narrower unsigned values are zero-extended, so only
+                    // u64 needs an unsigned conversion
+                    (_, Type::F64) => self
+                        .target
+                        .emit_int_to_float(&mut self.output, from != Type::U64),
+                    // Only u64 needs an unsigned conversion: narrower types
+                    // wrap the i64
+                    (Type::F64, _) => {
+                        self.target
+                            .emit_float_to_int(&mut self.output, *ty != Type::U64);
+                        self.gen_wrap(*ty);
                     }
+                    // Values are kept wrapped to their type, so converting
+                    // to a wider type of the same signedness, or to a 64-bit
+                    // type, keeps the bits; wrapping handles the rest
+                    _ => self.gen_wrap(*ty),
+                }
+                Ok(())
+            }
+            ExprKind::Call { name, args } => {
+                // Evaluate the arguments left to right onto the stack, then
+                // pop them into the argument registers, last one first
+                for arg in args {
+                    self.gen_expr(arg)?;
+                    self.target.emit_push(&mut self.output);
                 }
+                for index in (0..args.len()).rev() {
+                    self.target.emit_pop_arg(&mut self.output, index);
+                }
+                let symbol = Self::function_symbol(self.target, name);
+                self.target.emit_call(&mut self.output, &symbol);
                 Ok(())
             }
         }
     }
+}
 
-    fn gen_load_immediate(&mut self, val: i64) {
-        if val >= 0 && val < 65536 {
-            writeln!(self.output, "    mov x0, #{}", val).unwrap();
-        } else if val < 0 && val >= -65536 {
-            // movn loads the bitwise NOT of the shifted immediate.
-            // To load a negative value v, we use movn with the NOT of v.
-            let not_val = !val as u64;
-            writeln!(self.output, "    movn x0, #{}", not_val & 0xFFFF).unwrap();
-        } else {
-            // For arbitrary 64-bit values, use movz + movk sequence.
-            let uval = val as u64;
-            writeln!(self.output, "    movz x0, #{}", uval & 0xFFFF).unwrap();
-            if (uval >> 16) & 0xFFFF != 0 {
-                writeln!(
-                    self.output,
-                    "    movk x0, #{}, lsl #16",
-                    (uval >> 16) & 0xFFFF
-                )
-                .unwrap();
-            }
-            if (uval >> 32) & 0xFFFF != 0 {
-                writeln!(
-                    self.output,
-                    "    movk x0, #{}, lsl #32",
-                    (uval >> 32) & 0xFFFF
-                )
-                .unwrap();
-            }
-            if (uval >> 48) & 0xFFFF != 0 {
-                writeln!(
-                    self.output,
-                    "    movk x0, #{}, lsl #48",
-                    (uval >> 48) & 0xFFFF
-                )
-                .unwrap();
-            }
+/// Escape a string for an `.asciz` directive. Printable ASCII is written as
+/// is; other characters become escapes of their UTF-8 bytes.
+fn escape_asm_string(text: &str) -> String {
+    let mut escaped = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str("\\\\"),
+            b'\n' => escaped.push_str("\\n"),
+            b'\t' => escaped.push_str("\\t"),
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => write!(escaped, "\\{:03o}", byte).unwrap(),
         }
     }
+    escaped
 }
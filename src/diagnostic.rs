@@ -0,0 +1,357 @@
+use std::fmt::Write;
+
+use crate::span::{SourceMap, Span};
+
+/// How serious a diagnostic is. Every diagnostic is an error so far: each
+/// one stops the compilation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// The phase of the compiler that reported a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Lexer,
+    Parser,
+    Semantic,
+    Codegen,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Lexer => "lexer",
+            Phase::Parser => "parser",
+            Phase::Semantic => "semantic",
+            Phase::Codegen => "codegen",
+        }
+    }
+}
+
+/// A span of the source with a short message about it, which may be empty.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    /// Whether this is the span the diagnostic is about. Secondary labels
+    /// point at related code, like an earlier definition.
+    pub primary: bool,
+}
+
+/// A problem found in a program, reported by any phase of the compiler.
+/// Its code, listed in LANGUAGE.md, identifies the kind of problem and does
+/// not change between versions, so tools can rely on it rather than on the
+/// wording of the message.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub phase: Phase,
+    pub code: &'static str,
+    pub message: String,
+    /// The primary label, if any, comes first.
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(phase: Phase, code: &'static str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            phase,
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    /// Point the diagnostic at `span`.
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        let label = Label {
+            span,
+            message: message.into(),
+            primary: true,
+        };
+        self.labels.insert(0, label);
+        self
+    }
+
+    /// Add a label for code related to the problem.
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    /// Add a note: more information about the problem.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Add help: a suggestion for fixing the problem.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    /// The diagnostic as text for a person to read, in the style of rustc:
+    /// the message, the file and position of the primary label, each line
+    /// with a label underlined (`^` for the primary label, `-` for the
+    /// others), then the notes and help. With `color`, parts of it are
+    /// highlighted with ANSI escape codes; the text is the same either way.
+    pub fn render(&self, file: &str, source: &SourceMap, color: bool) -> String {
+        let paint = |text: &str, style: &str| {
+            if color && !text.is_empty() {
+                format!("{}{}{}", style, text, RESET)
+            } else {
+                text.to_string()
+            }
+        };
+        let mut out = paint(&format!("{}[{}]", self.severity.name(), self.code), ERROR);
+        out.push_str(&paint(&format!(": {}", self.message), BOLD));
+
+        let mut lines: Vec<usize> = self
+            .labels
+            .iter()
+            .map(|label| source.line_col(label.span.start).0)
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        let width = lines.last().map_or(0, |line| line.to_string().len());
+        let pad = " ".repeat(width);
+        let gutter = paint("|", GUTTER);
+
+        if let Some(label) = self.labels.iter().find(|label| label.primary) {
+            let (line, col) = source.line_col(label.span.start);
+            write!(
+                out,
+                "\n{}{} {}:{}:{}",
+                pad,
+                paint("-->", GUTTER),
+                file,
+                line,
+                col
+            )
+            .unwrap();
+        }
+        if !lines.is_empty() {
+            write!(out, "\n{} {}", pad, gutter).unwrap();
+        }
+        for (i, &line) in lines.iter().enumerate() {
+            if i > 0 && lines[i - 1] + 1 < line {
+                write!(out, "\n{}", paint("...", GUTTER)).unwrap();
+            }
+            let text = source.line_text(line);
+            let number = paint(&format!("{:>width$}", line), GUTTER);
+            write!(out, "\n{} {}", number, gutter).unwrap();
+            if !text.is_empty() {
+                write!(out, " {}", expand_tabs(text)).unwrap();
+            }
+            for row in self.label_rows(source, line) {
+                write!(out, "\n{} {}", pad, gutter).unwrap();
+                let row = render_row(&row, &paint);
+                if !row.is_empty() {
+                    write!(out, " {}", row).unwrap();
+                }
+            }
+        }
+
+        if !lines.is_empty() && (!self.notes.is_empty() || !self.help.is_empty()) {
+            write!(out, "\n{} {}", pad, gutter).unwrap();
+        }
+        let notes = self.notes.iter().map(|note| ("note", note));
+        for (kind, text) in notes.chain(self.help.iter().map(|help| ("help", help))) {
+            let equals = paint("=", GUTTER);
+            write!(out, "\n{} {} {}: {}", pad, equals, paint(kind, BOLD), text).unwrap();
+        }
+        out
+    }
+
+    /// The rows drawn under line `line` of the source for the labels that
+    /// start on it. The first row underlines every label and ends with the
+    /// message of the rightmost one; the messages of the others follow on
+    /// rows of their own, each hanging from the start of its label, the
+    /// rightmost first.
+    fn label_rows(&self, source: &SourceMap, line: usize) -> Vec<Vec<Cell>> {
+        let text = source.line_text(line);
+        let line_start = source.line_start(line);
+        let mut marks: Vec<(usize, usize, &Label)> = Vec::new();
+        for label in &self.labels {
+            if source.line_col(label.span.start).0 != line {
+                continue;
+            }
+            // A span may run past the end of the line, or start at its end
+            // (like the end of the file); underline what is on the line, and
+            // at least one column.
+            let start = label.span.start - line_start;
+            let end = (label.span.end.max(label.span.start) - line_start).min(text.len());
+            let col = display_width(&text[..start.min(text.len())]);
+            let len = display_width(text.get(start..end).unwrap_or("")).max(1);
+            marks.push((col, len, label));
+        }
+        marks.sort_by_key(|&(col, _, _)| col);
+
+        let style = |label: &Label| if label.primary { ERROR } else { GUTTER };
+        let mut rows = Vec::new();
+        let mut first = Vec::new();
+        for &(col, len, label) in &marks {
+            let marker = if label.primary { '^' } else { '-' };
+            put(
+                &mut first,
+                col,
+                &marker.to_string().repeat(len),
+                style(label),
+            );
+        }
+        let Some(&(col, len, last)) = marks.last() else {
+            return rows;
+        };
+        if !last.message.is_empty() {
+            put(&mut first, col + len + 1, &last.message, style(last));
+        }
+        rows.push(first);
+
+        let hanging: Vec<(usize, &Label)> = marks[..marks.len() - 1]
+            .iter()
+            .filter(|(_, _, label)| !label.message.is_empty())
+            .map(|&(col, _, label)| (col, label))
+            .collect();
+        if hanging.is_empty() {
+            return rows;
+        }
+        let connect = |row: &mut Vec<Cell>, hanging: &[(usize, &Label)]| {
+            for &(col, label) in hanging {
+                put(row, col, "|", style(label));
+            }
+        };
+        let mut row = Vec::new();
+        connect(&mut row, &hanging);
+        rows.push(row);
+        for (i, &(col, label)) in hanging.iter().enumerate().rev() {
+            let mut row = Vec::new();
+            connect(&mut row, &hanging[..i]);
+            put(&mut row, col, &label.message, style(label));
+            rows.push(row);
+        }
+        rows
+    }
+
+    /// The diagnostic as a single line of JSON, for tools. Labels have both
+    /// the byte offsets of their span and the line and column of its start.
+    pub fn to_json(&self, file: &str, source: &SourceMap) -> String {
+        let mut labels = Vec::new();
+        for label in &self.labels {
+            let (line, col) = source.line_col(label.span.start);
+            labels.push(format!(
+                "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"primary\":{},\"message\":{}}}",
+                label.span.start,
+                label.span.end,
+                line,
+                col,
+                label.primary,
+                json_string(&label.message)
+            ));
+        }
+        let strings = |items: &[String]| {
+            let items: Vec<String> = items.iter().map(|item| json_string(item)).collect();
+            format!("[{}]", items.join(","))
+        };
+        format!(
+            "{{\"severity\":\"{}\",\"phase\":\"{}\",\"code\":\"{}\",\"message\":{},\"file\":{},\"labels\":[{}],\"notes\":{},\"help\":{}}}",
+            self.severity.name(),
+            self.phase.name(),
+            self.code,
+            json_string(&self.message),
+            json_string(file),
+            labels.join(","),
+            strings(&self.notes),
+            strings(&self.help)
+        )
+    }
+}
+
+/// ANSI escape codes for the parts of a rendered diagnostic.
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const ERROR: &str = "\x1b[1;31m";
+const GUTTER: &str = "\x1b[1;34m";
+
+/// Tabs are shown as this many spaces, so underlines line up with them.
+const TAB_WIDTH: usize = 4;
+
+/// A character of a row drawn under a source line, with its style.
+type Cell = (char, &'static str);
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+/// The number of columns `text` takes up once its tabs are expanded.
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+/// Draw `text` in `row` from column `col`, over anything already there.
+fn put(row: &mut Vec<Cell>, col: usize, text: &str, style: &'static str) {
+    for (i, c) in text.chars().enumerate() {
+        if row.len() <= col + i {
+            row.resize(col + i + 1, (' ', ""));
+        }
+        row[col + i] = (c, style);
+    }
+}
+
+/// A row as text, each run of characters painted in its style.
+fn render_row(row: &[Cell], paint: &impl Fn(&str, &str) -> String) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < row.len() {
+        let style = row[i].1;
+        let run: String = row[i..]
+            .iter()
+            .take_while(|cell| cell.1 == style)
+            .map(|cell| cell.0)
+            .collect();
+        i += run.chars().count();
+        if style.is_empty() {
+            out.push_str(&run);
+        } else {
+            out.push_str(&paint(&run, style));
+        }
+    }
+    out
+}
+
+/// `text` as a JSON string literal.
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
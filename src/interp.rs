@@ -0,0 +1,576 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::ast::{
+    BinOp, Block, Expr, ExprKind, FormatPiece, FormatRadix, FormatSpec, Function, LogicalOp,
+    Program, Stmt, StmtKind, Type,
+};
+use crate::span::{SourceMap, Span};
+
+/// How a statement finished: normally, by jumping out of the innermost
+/// loop with `break` or `continue`, or by returning from the function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(i64),
+}
+
+/// The value of a variable.
+#[derive(Debug, Clone)]
+enum Value {
+    Int(i64),
+    Array(Vec<i64>),
+}
+
+/// Maximum depth of nested calls. Compiled programs crash when they run out
+/// of stack; the interpreter reports a runtime error instead.
+const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Tree-walking interpreter implementing the semantics in LANGUAGE.md,
+/// for running programs without an assembler and linker.
+pub struct Interpreter<'p, R: BufRead, W: Write> {
+    /// The program's source, for the positions in runtime error messages.
+    source: &'p SourceMap,
+    /// Every function of the program, by name.
+    functions: HashMap<&'p str, &'p Function>,
+    /// One map per enclosing block of the running function, innermost last,
+    /// holding the current value of every variable declared in that block. A
+    /// shadowing `let` in the same block simply replaces the entry, since the
+    /// old variable can no longer be named.
+    scopes: Vec<HashMap<String, Value>>,
+    /// Number of calls currently executing.
+    call_depth: usize,
+    /// Where `input()` reads from.
+    input: R,
+    out: W,
+}
+
+impl<'p, R: BufRead, W: Write> Interpreter<'p, R, W> {
+    pub fn new(source: &'p SourceMap, input: R, out: W) -> Self {
+        Interpreter {
+            source,
+            functions: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            call_depth: 0,
+            input,
+            out,
+        }
+    }
+
+    /// Run a program that passed the type checker.
+    pub fn run(mut self, program: &'p Program) -> Result<(), String> {
+        for function in &program.functions {
+            self.functions.insert(&function.name, function);
+        }
+
+        // The parser rejects `break` and `continue` outside a loop and
+        // `return` outside a function, so every top-level statement finishes
+        // normally.
+        let result = program
+            .stmts
+            .iter()
+            .try_for_each(|stmt| self.exec_stmt(stmt).map(|_| ()));
+        // Flush what was printed before a runtime error, too.
+        self.out
+            .flush()
+            .map_err(|e| format!("error writing output: {}", e))?;
+        result
+    }
+
+    fn exec_stmt(&mut self, stmt: &Stmt) -> Result<Flow, String> {
+        let (line, _) = self.source.line_col(stmt.span.start);
+        match &stmt.kind {
+            StmtKind::Let { name, expr, .. } => {
+                let val = self.eval(expr, line)?;
+                self.scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert(name.clone(), Value::Int(val));
+            }
+            StmtKind::Const { name, value, .. } => {
+                self.scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert(name.clone(), Value::Int(value.unwrap()));
+            }
+            StmtKind::LetArray {
+                name, init, len, ..
+            } => {
+                let val = self.eval(init, line)?;
+                self.scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert(name.clone(), Value::Array(vec![val; *len]));
+            }
+            StmtKind::Assign { name, expr } => {
+                let val = self.eval(expr, line)?;
+                *self.lookup_mut(name) = val;
+            }
+            StmtKind::CompoundAssign { name, op, expr, ty } => {
+                let old = self.lookup(name);
+                let r = self.eval(expr, line)?;
+                let val = binop(*op, ty.unwrap(), old, r, line)?;
+                *self.lookup_mut(name) = val;
+            }
+            StmtKind::AssignIndex {
+                name,
+                index,
+                op,
+                expr,
+                ty,
+            } => {
+                let index = self.eval_index(name, index, stmt.span)?;
+                let val = match op {
+                    Some(op) => {
+                        let old = self.array_mut(name)[index];
+                        let r = self.eval(expr, line)?;
+                        binop(*op, ty.unwrap(), old, r, line)?
+                    }
+                    None => self.eval(expr, line)?,
+                };
+                self.array_mut(name)[index] = val;
+            }
+            StmtKind::Print { expr } => {
+                let val = self.eval(expr, line)?;
+                let text = format_arg(FormatSpec::default(), expr.ty.unwrap(), val);
+                writeln!(self.out, "{}", text)
+                    .map_err(|e| format!("error writing output: {}", e))?;
+            }
+            StmtKind::PrintFormat {
+                pieces,
+                args,
+                newline,
+            } => {
+                // Every argument is evaluated before anything is printed.
+                let mut args = args
+                    .iter()
+                    .map(|arg| Ok((arg.ty.unwrap(), self.eval(arg, line)?)))
+                    .collect::<Result<Vec<_>, String>>()?
+                    .into_iter();
+                let mut text = String::new();
+                for piece in pieces {
+                    match piece {
+                        FormatPiece::Text(s) => text.push_str(s),
+                        FormatPiece::Arg(spec) => {
+                            let (ty, val) = args.next().unwrap();
+                            text.push_str(&format_arg(*spec, ty, val));
+                        }
+                    }
+                }
+                if *newline {
+                    text.push('\n');
+                }
+                write!(self.out, "{}", text)
+                    .map_err(|e| format!("error writing output: {}", e))?;
+            }
+            StmtKind::If {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                if self.eval(cond, line)? != 0 {
+                    return self.exec_block(then_block);
+                } else if let Some(else_block) = else_block {
+                    return self.exec_block(else_block);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                while self.eval(cond, line)? != 0 {
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        Flow::Return(val) => return Ok(Flow::Return(val)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                }
+            }
+            StmtKind::Block(block) => return self.exec_block(block),
+            StmtKind::Break => return Ok(Flow::Break),
+            StmtKind::Continue => return Ok(Flow::Continue),
+            StmtKind::Return { expr } => return Ok(Flow::Return(self.eval(expr, line)?)),
+            StmtKind::Expr { expr } => {
+                self.eval(expr, line)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Run a block in a new scope, stopping early at `break`, `continue` or
+    /// `return`.
+    fn exec_block(&mut self, block: &Block) -> Result<Flow, String> {
+        self.scopes.push(HashMap::new());
+        let mut result = Ok(Flow::Normal);
+        for stmt in &block.stmts {
+            result = self.exec_stmt(stmt);
+            if result != Ok(Flow::Normal) {
+                break;
+            }
+        }
+        self.scopes.pop();
+        result
+    }
+
+    /// Find the innermost variable called `name`. Names were resolved by
+    /// the type checker before running, so the variable always exists and is
+    /// an integer or an array as its use requires.
+    fn lookup(&self, name: &str) -> i64 {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(Value::Int(val)) => *val,
+            _ => unreachable!(),
+        }
+    }
+
+    fn lookup_mut(&mut self, name: &str) -> &mut i64 {
+        match self.variable_mut(name) {
+            Value::Int(val) => val,
+            Value::Array(_) => unreachable!(),
+        }
+    }
+
+    fn array_mut(&mut self, name: &str) -> &mut Vec<i64> {
+        match self.variable_mut(name) {
+            Value::Array(elements) => elements,
+            Value::Int(_) => unreachable!(),
+        }
+    }
+
+    fn variable_mut(&mut self, name: &str) -> &mut Value {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+            .unwrap()
+    }
+
+    /// Evaluate an index into the array `name` and check it against the
+    /// array's length. `span` locates the indexing expression.
+    fn eval_index(&mut self, name: &str, index: &Expr, span: Span) -> Result<usize, String> {
+        let (line, col) = self.source.line_col(span.start);
+        let index = self.eval(index, line)?;
+        let len = self.array_mut(name).len();
+        usize::try_from(index)
+            .ok()
+            .filter(|index| *index < len)
+            .ok_or_else(|| {
+                format!(
+                    "{}:{}: index {} is out of bounds for array '{}' of length {}",
+                    line, col, index, name, len
+                )
+            })
+    }
+
+    /// Evaluate an expression. `line` is the line of the enclosing
+    /// statement, used to locate runtime errors.
+    fn eval(&mut self, expr: &Expr, line: usize) -> Result<i64, String> {
+        match &expr.kind {
+            // The type checker checked that it fits its type, so a literal
+            // above i64::MAX is a u64 with the same bits
+            ExprKind::IntLit(val) => Ok(*val as i64),
+            ExprKind::FloatLit(val) => Ok(from_float(*val)),
+            ExprKind::BoolLit(val) => Ok(*val as i64),
+            ExprKind::Var(name) => Ok(self.lookup(name)),
+            ExprKind::Index { name, index } => {
+                let index = self.eval_index(name, index, expr.span)?;
+                Ok(self.array_mut(name)[index])
+            }
+            ExprKind::Len(name) => Ok(self.array_mut(name).len() as i64),
+            ExprKind::Input => self.read_int(expr.span),
+            ExprKind::UnaryMinus(inner) => Ok(negate(self.eval(inner, line)?, expr.ty.unwrap())),
+            ExprKind::Not(inner) => Ok((self.eval(inner, line)? == 0) as i64),
+            ExprKind::BitNot(inner) => Ok(expr.ty.unwrap().wrap(!self.eval(inner, line)?)),
+            ExprKind::Cast {
+                expr: inner, ty, ..
+            } => Ok(cast(self.eval(inner, line)?, inner.ty.unwrap(), *ty)),
+            ExprKind::Logical {
+                op, left, right, ..
+            } => {
+                let l = self.eval(left, line)? != 0;
+                let result = match op {
+                    LogicalOp::And => l && self.eval(right, line)? != 0,
+                    LogicalOp::Or => l || self.eval(right, line)? != 0,
+                };
+                Ok(result as i64)
+            }
+            ExprKind::BinOp {
+                op, left, right, ..
+            } => {
+                let l = self.eval(left, line)?;
+                let r = self.eval(right, line)?;
+                binop(*op, left.ty.unwrap(), l, r, line)
+            }
+            ExprKind::Call { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg, line))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(name, args, line)
+            }
+        }
+    }
+
+    /// Read an integer for `input()` at `span` like the compiled
+    /// code's `scanf("%lld")`: skip whitespace, then read an optional sign
+    /// and decimal digits, leaving what follows them unread. A value out of
+    /// the range of `i64` saturates to its minimum or maximum.
+    fn read_int(&mut self, span: Span) -> Result<i64, String> {
+        let (line, col) = self.source.line_col(span.start);
+        // Show any prompt before waiting for input
+        self.out
+            .flush()
+            .map_err(|e| format!("error writing output: {}", e))?;
+        self.skip_bytes(|byte| matches!(byte, b' ' | b'\t'..=b'\r'))?;
+        let negative = match self.peek_byte()? {
+            None => return Err(format!("{}:{}: unexpected end of input", line, col)),
+            Some(sign @ (b'+' | b'-')) => {
+                self.input.consume(1);
+                sign == b'-'
+            }
+            Some(_) => false,
+        };
+        let mut magnitude: i128 = 0;
+        let digits = self.skip_bytes(|byte| {
+            if !byte.is_ascii_digit() {
+                return false;
+            }
+            // Past i64::MAX + 1 the value saturates either way
+            magnitude = (magnitude * 10 + (byte - b'0') as i128).min(1 << 63);
+            true
+        })?;
+        if digits == 0 {
+            return Err(format!(
+                "{}:{}: expected an integer in the input",
+                line, col
+            ));
+        }
+        let val = if negative { -magnitude } else { magnitude };
+        Ok(val.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+
+    /// The next byte of the input, without consuming it, or `None` at its
+    /// end.
+    fn peek_byte(&mut self) -> Result<Option<u8>, String> {
+        let buf = self
+            .input
+            .fill_buf()
+            .map_err(|e| format!("error reading input: {}", e))?;
+        Ok(buf.first().copied())
+    }
+
+    /// Consume input bytes while `accept` returns true for them, returning
+    /// how many were consumed.
+    fn skip_bytes(&mut self, mut accept: impl FnMut(u8) -> bool) -> Result<usize, String> {
+        let mut count = 0;
+        while let Some(byte) = self.peek_byte()? {
+            if !accept(byte) {
+                break;
+            }
+            self.input.consume(1);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Call a function with its arguments already evaluated. The callee gets
+    /// a fresh scope stack: functions cannot see their caller's variables.
+    fn call(&mut self, name: &str, args: Vec<i64>, line: usize) -> Result<i64, String> {
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err(format!(
+                "line {}: stack overflow (more than {} nested calls)",
+                line, MAX_CALL_DEPTH
+            ));
+        }
+        let function = self.functions[name];
+        let params = function
+            .params
+            .iter()
+            .cloned()
+            .zip(args.into_iter().map(Value::Int))
+            .collect();
+        let caller_scopes = std::mem::replace(&mut self.scopes, vec![params]);
+        self.call_depth += 1;
+        let mut result = Ok(0);
+        for stmt in &function.body.stmts {
+            match self.exec_stmt(stmt) {
+                Ok(Flow::Return(val)) => {
+                    result = Ok(val);
+                    break;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        self.call_depth -= 1;
+        self.scopes = caller_scopes;
+        result
+    }
+}
+
+/// Negate a value of type `ty`, as the compiled code does.
+pub(crate) fn negate(val: i64, ty: Type) -> i64 {
+    if ty == Type::F64 {
+        from_float(-to_float(val))
+    } else {
+        ty.wrap(val.wrapping_neg())
+    }
+}
+
+/// Convert a value of type `from` to type `to`, as the compiled code does.
+pub(crate) fn cast(val: i64, from: Type, to: Type) -> i64 {
+    match (from, to) {
+        (Type::F64, Type::F64) => val,
+        (Type::U64, Type::F64) => from_float(val as u64 as f64),
+        (_, Type::F64) => from_float(val as f64),
+        // `as` rounds toward zero, saturates at the range of i64 (or u64)
+        // and converts NaN to 0, like the compiled code
+        (Type::F64, Type::U64) => to_float(val) as u64 as i64,
+        (Type::F64, to) => to.wrap(to_float(val) as i64),
+        (_, to) => to.wrap(val),
+    }
+}
+
+/// Apply a binary operator to two evaluated operands, as the compiled code
+/// does. `ty` is the type of the left operand: it selects signed or unsigned
+/// division, right shift and ordering, and the result is wrapped into its
+/// range. `line` locates division by zero.
+pub(crate) fn binop(op: BinOp, ty: Type, l: i64, r: i64, line: usize) -> Result<i64, String> {
+    if ty == Type::F64 {
+        return Ok(float_binop(op, to_float(l), to_float(r)));
+    }
+    let signed = ty.is_signed();
+    // Unsigned values are zero-extended, so these are their values
+    let (ul, ur) = (l as u64, r as u64);
+    let val = match op {
+        BinOp::Add => l.wrapping_add(r),
+        BinOp::Sub => l.wrapping_sub(r),
+        BinOp::Mul => l.wrapping_mul(r),
+        BinOp::Div if r == 0 => return Err(format!("line {}: division by zero", line)),
+        BinOp::Mod if r == 0 => return Err(format!("line {}: modulo by zero", line)),
+        // wrapping_div and wrapping_rem give i64::MIN / -1 = i64::MIN
+        // and i64::MIN % -1 = 0, matching the compiled code.
+        BinOp::Div if signed => l.wrapping_div(r),
+        BinOp::Mod if signed => l.wrapping_rem(r),
+        BinOp::Div => (ul / ur) as i64,
+        BinOp::Mod => (ul % ur) as i64,
+        BinOp::Eq => (l == r) as i64,
+        BinOp::Ne => (l != r) as i64,
+        BinOp::Lt if signed => (l < r) as i64,
+        BinOp::Le if signed => (l <= r) as i64,
+        BinOp::Gt if signed => (l > r) as i64,
+        BinOp::Ge if signed => (l >= r) as i64,
+        BinOp::Lt => (ul < ur) as i64,
+        BinOp::Le => (ul <= ur) as i64,
+        BinOp::Gt => (ul > ur) as i64,
+        BinOp::Ge => (ul >= ur) as i64,
+        BinOp::BitAnd => l & r,
+        BinOp::BitOr => l | r,
+        BinOp::BitXor => l ^ r,
+        // wrapping_shl and wrapping_shr use the low 6 bits of the
+        // count, like the shift instructions of both targets.
+        BinOp::Shl => l.wrapping_shl(r as u32),
+        BinOp::Shr if signed => l.wrapping_shr(r as u32),
+        BinOp::Shr => ul.wrapping_shr(r as u32) as i64,
+    };
+    Ok(ty.wrap(val))
+}
+
+/// Apply an arithmetic or comparison operator to two `f64`s. Division by
+/// zero gives an infinity or NaN, and comparisons with NaN are false except
+/// `!=`.
+fn float_binop(op: BinOp, l: f64, r: f64) -> i64 {
+    match op {
+        BinOp::Add => from_float(l + r),
+        BinOp::Sub => from_float(l - r),
+        BinOp::Mul => from_float(l * r),
+        BinOp::Div => from_float(l / r),
+        BinOp::Eq => (l == r) as i64,
+        BinOp::Ne => (l != r) as i64,
+        BinOp::Lt => (l < r) as i64,
+        BinOp::Le => (l <= r) as i64,
+        BinOp::Gt => (l > r) as i64,
+        BinOp::Ge => (l >= r) as i64,
+        // The type checker rejects the other operators on f64
+        _ => unreachable!(),
+    }
+}
+
+/// The `f64` whose bits a value of type `f64` holds.
+fn to_float(val: i64) -> f64 {
+    f64::from_bits(val as u64)
+}
+
+/// The value of type `f64` holding `val`.
+pub(crate) fn from_float(val: f64) -> i64 {
+    val.to_bits() as i64
+}
+
+/// Format one placeholder's argument of type `ty` like the `printf`
+/// conversion the compiler uses for it. Unsigned types print their unsigned
+/// value; hexadecimal and binary show the two's complement bits of the
+/// type's width. A `bool` prints as `true` or `false`; the type checker only
+/// allows a width for it.
+fn format_arg(spec: FormatSpec, ty: Type, val: i64) -> String {
+    let width = spec.width;
+    if ty == Type::Bool {
+        return format!("{:>width$}", val != 0);
+    }
+    if ty == Type::F64 {
+        return format_float(to_float(val), width, spec.zero_pad);
+    }
+    let bits = val as u64 & (u64::MAX >> (64 - ty.bits()));
+    match (spec.radix, spec.zero_pad) {
+        (FormatRadix::Decimal, false) if ty.is_signed() => format!("{val:width$}"),
+        (FormatRadix::Decimal, true) if ty.is_signed() => format!("{val:0width$}"),
+        (FormatRadix::Decimal, false) => format!("{bits:width$}"),
+        (FormatRadix::Decimal, true) => format!("{bits:0width$}"),
+        (FormatRadix::LowerHex, false) => format!("{bits:width$x}"),
+        (FormatRadix::LowerHex, true) => format!("{bits:0width$x}"),
+        (FormatRadix::UpperHex, false) => format!("{bits:width$X}"),
+        (FormatRadix::UpperHex, true) => format!("{bits:0width$X}"),
+        (FormatRadix::Binary, false) => format!("{bits:width$b}"),
+        (FormatRadix::Binary, true) => format!("{bits:0width$b}"),
+    }
+}
+
+/// Format an `f64` like `printf`'s `%g`: rounded to six significant digits,
+/// in exponent form if the exponent is below -4 or at least 6, and without
+/// trailing zeros. Infinities and NaN print as `inf` and `nan`, signed as
+/// the C library does, and are never zero padded.
+fn format_float(val: f64, width: usize, zero_pad: bool) -> String {
+    let sign = if val.is_sign_negative() { "-" } else { "" };
+    let digits = if val.is_nan() {
+        "nan".to_string()
+    } else if val.is_infinite() {
+        "inf".to_string()
+    } else {
+        // The exponent of the value rounded to six significant digits
+        let sci = format!("{:.5e}", val.abs());
+        let (mantissa, exp) = sci.split_once('e').unwrap();
+        let exp: i32 = exp.parse().unwrap();
+        if (-4..6).contains(&exp) {
+            trim_fraction(&format!("{:.*}", (5 - exp) as usize, val.abs())).to_string()
+        } else {
+            let exp_sign = if exp < 0 { '-' } else { '+' };
+            format!("{}e{}{:02}", trim_fraction(mantissa), exp_sign, exp.abs())
+        }
+    };
+    if zero_pad && val.is_finite() {
+        let width = width.saturating_sub(sign.len());
+        format!("{sign}{digits:0>width$}")
+    } else {
+        format!("{:>width$}", format!("{sign}{digits}"))
+    }
+}
+
+/// Remove trailing zeros after a decimal point, and the point if nothing
+/// is left after it.
+fn trim_fraction(text: &str) -> &str {
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        text
+    }
+}
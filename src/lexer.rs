@@ -1,33 +1,152 @@
+use std::fmt;
+
+use crate::diagnostic::{Diagnostic, Phase};
+use crate::span::Span;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
     Let,
+    Const,
     Print,
+    Write,
+    If,
+    Else,
+    While,
+    Break,
+    Continue,
+    Fn,
+    Return,
+    True,
+    False,
+    As,
     Ident(String),
-    IntLit(String), // Store as string to defer parsing to later stage
+    IntLit(String), // Source text, including any prefix and `_`; see `int_literal_value`
+    FloatLit(String), // Source text, including any `_`; see `float_literal_value`
+    StrLit(String), // Source text between the quotes; see `decode_string_char`
     Plus,
     Minus,
     Star,
     Slash,
     Percent,
     Eq,
+    PlusEq,
+    MinusEq,
+    StarEq,
+    SlashEq,
+    PercentEq,
+    AmpEq,
+    PipeEq,
+    CaretEq,
+    ShlEq,
+    ShrEq,
+    EqEq,
+    NotEq,
+    Bang,
+    Tilde,
+    Amp,
+    AndAnd,
+    Pipe,
+    OrOr,
+    Caret,
+    Shl,
+    Shr,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
     Semi,
+    Colon,
+    Comma,
     LParen,
     RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
     Eof,
 }
 
+impl fmt::Display for Token {
+    /// How messages name the token: keywords and punctuation as written, in
+    /// quotes, and other tokens by their kind.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Token::Ident(name) => return write!(f, "identifier '{}'", name),
+            Token::IntLit(text) => return write!(f, "integer literal '{}'", text),
+            Token::FloatLit(text) => return write!(f, "float literal '{}'", text),
+            Token::StrLit(_) => return write!(f, "string literal"),
+            Token::Eof => return write!(f, "end of file"),
+            Token::Let => "let",
+            Token::Const => "const",
+            Token::Print => "print",
+            Token::Write => "write",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::While => "while",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Fn => "fn",
+            Token::Return => "return",
+            Token::True => "true",
+            Token::False => "false",
+            Token::As => "as",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::Eq => "=",
+            Token::PlusEq => "+=",
+            Token::MinusEq => "-=",
+            Token::StarEq => "*=",
+            Token::SlashEq => "/=",
+            Token::PercentEq => "%=",
+            Token::AmpEq => "&=",
+            Token::PipeEq => "|=",
+            Token::CaretEq => "^=",
+            Token::ShlEq => "<<=",
+            Token::ShrEq => ">>=",
+            Token::EqEq => "==",
+            Token::NotEq => "!=",
+            Token::Bang => "!",
+            Token::Tilde => "~",
+            Token::Amp => "&",
+            Token::AndAnd => "&&",
+            Token::Pipe => "|",
+            Token::OrOr => "||",
+            Token::Caret => "^",
+            Token::Shl => "<<",
+            Token::Shr => ">>",
+            Token::Lt => "<",
+            Token::LtEq => "<=",
+            Token::Gt => ">",
+            Token::GtEq => ">=",
+            Token::Semi => ";",
+            Token::Colon => ":",
+            Token::Comma => ",",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+        };
+        write!(f, "'{}'", text)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SpannedToken {
     pub token: Token,
-    pub line: usize,
-    pub col: usize,
+    /// The token's source text.
+    pub span: Span,
 }
 
 pub struct Lexer {
     input: Vec<char>,
     pos: usize,
-    line: usize,
-    col: usize,
+    /// Byte offset of `input[pos]` in the source text.
+    offset: usize,
 }
 
 impl Lexer {
@@ -35,8 +154,7 @@ impl Lexer {
         Lexer {
             input: input.chars().collect(),
             pos: 0,
-            line: 1,
-            col: 1,
+            offset: 0,
         }
     }
 
@@ -44,16 +162,182 @@ impl Lexer {
         self.input.get(self.pos).copied()
     }
 
+    fn peek_next(&self) -> Option<char> {
+        self.input.get(self.pos + 1).copied()
+    }
+
+    /// Consume the rest of a word (letters, digits and `_`), appending it to
+    /// `text`.
+    fn lex_word(&mut self, text: &mut String) {
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                text.push(c);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+    }
+
     fn advance(&mut self) -> Option<char> {
         let ch = self.input.get(self.pos).copied()?;
         self.pos += 1;
-        if ch == '\n' {
-            self.line += 1;
-            self.col = 1;
+        self.offset += ch.len_utf8();
+        Some(ch)
+    }
+
+    /// Lex an integer literal: an optional `0x`, `0o` or `0b` prefix, then
+    /// digits of that radix, optionally separated by `_`. The literal runs to
+    /// the end of the word, so a letter directly after the digits (as in
+    /// `3x`) is an error rather than the start of an identifier. A decimal
+    /// literal followed by a fraction or with an exponent is a float literal.
+    /// `start` is the offset of the literal.
+    fn lex_number(&mut self, start: usize) -> Result<Token, Diagnostic> {
+        let mut text = String::new();
+        self.lex_word(&mut text);
+        let (radix, prefix_len) = literal_radix(&text);
+        if radix == 10 {
+            // `1.` is not a float literal, so that a `.` can follow an integer
+            if self.peek() == Some('.') && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+                text.push('.');
+                self.advance();
+                self.lex_word(&mut text);
+            }
+            if text.ends_with(['e', 'E'])
+                && matches!(self.peek(), Some('+' | '-'))
+                && self.peek_next().is_some_and(|c| c.is_ascii_digit())
+            {
+                text.push(self.advance().unwrap());
+                self.lex_word(&mut text);
+            }
+            if text.contains(['.', 'e', 'E']) {
+                check_float_literal(&text, start)?;
+                return Ok(Token::FloatLit(text));
+            }
+        }
+        let kind = match radix {
+            16 => "hexadecimal",
+            8 => "octal",
+            2 => "binary",
+            _ => "decimal",
+        };
+        let digits = &text[prefix_len..];
+        if let Some(i) = digits.chars().position(|c| c != '_' && !c.is_digit(radix)) {
+            let digit = start + prefix_len + i;
+            return Err(error(
+                "E0101",
+                Span::new(digit, digit + 1),
+                format!(
+                    "invalid digit '{}' in {} literal '{}'",
+                    &digits[i..i + 1],
+                    kind,
+                    text
+                ),
+            ));
+        }
+        if !digits.chars().any(|c| c != '_') {
+            return Err(error(
+                "E0102",
+                Span::new(start, self.offset),
+                format!("{} literal '{}' has no digits", kind, text),
+            ));
+        }
+        Ok(Token::IntLit(text))
+    }
+
+    /// Lex a string literal, checking its escape sequences: `\n`, `\t`,
+    /// `\"`, `\\` and `\xHH` for a character from `\x01` to `\x7f`. Strings
+    /// cannot contain NUL, which terminates them in the generated code, or
+    /// span lines. The token keeps the source text between the quotes, so
+    /// that format strings can locate errors; see `decode_string_char`.
+    /// `start` is the offset of the opening quote.
+    fn lex_string(&mut self, start: usize) -> Result<Token, Diagnostic> {
+        self.advance(); // consume opening '"'
+        let mut raw = String::new();
+        loop {
+            let char_start = self.offset;
+            let unterminated = || {
+                error(
+                    "E0103",
+                    Span::new(start, char_start),
+                    "unterminated string literal".to_string(),
+                )
+            };
+            match self.advance() {
+                Some('"') => return Ok(Token::StrLit(raw)),
+                None | Some('\n') => return Err(unterminated()),
+                Some('\0') => {
+                    return Err(error(
+                        "E0104",
+                        Span::new(char_start, self.offset),
+                        "NUL character in string literal".to_string(),
+                    ));
+                }
+                Some('\\') => {
+                    raw.push('\\');
+                    match self.advance() {
+                        Some(c @ ('n' | 't' | '"' | '\\')) => raw.push(c),
+                        Some('x') => {
+                            raw.push('x');
+                            raw.push_str(&self.lex_hex_escape(char_start)?);
+                        }
+                        Some(c) if c != '\n' => {
+                            return Err(error(
+                                "E0105",
+                                Span::new(char_start, self.offset),
+                                format!("unknown escape sequence '\\{}'", c),
+                            ));
+                        }
+                        _ => return Err(unterminated()),
+                    }
+                }
+                Some(c) => raw.push(c),
+            }
+        }
+    }
+
+    /// Check and consume the two hex digits of a `\xHH` escape, after the
+    /// `x`. `start` is the offset of the backslash.
+    fn lex_hex_escape(&mut self, start: usize) -> Result<String, Diagnostic> {
+        let digits: String = self.input[self.pos..]
+            .iter()
+            .take(2)
+            .take_while(|c| c.is_ascii_hexdigit())
+            .collect();
+        if digits.len() != 2 {
+            return Err(error(
+                "E0106",
+                Span::new(start, self.offset),
+                "'\\x' must be followed by two hexadecimal digits".to_string(),
+            ));
+        }
+        self.advance();
+        self.advance();
+        let span = Span::new(start, self.offset);
+        match u8::from_str_radix(&digits, 16).unwrap() {
+            0 => Err(error(
+                "E0104",
+                span,
+                "NUL character in string literal".to_string(),
+            )),
+            0x01..=0x7f => Ok(digits),
+            _ => Err(error(
+                "E0106",
+                span,
+                format!("'\\x{}' is out of range (must be \\x01 to \\x7f)", digits),
+            )),
+        }
+    }
+
+    /// Finish an operator that has a compound assignment form: if the next
+    /// character is `=`, consume it and return `compound`, else `plain`.
+    fn with_eq(&mut self, plain: Token, compound: Token) -> Token {
+        if self.peek() == Some('=') {
+            self.advance();
+            compound
         } else {
-            self.col += 1;
+            plain
         }
-        Some(ch)
     }
 
     fn skip_whitespace_and_comments(&mut self) {
@@ -83,19 +367,17 @@ impl Lexer {
         }
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<SpannedToken>, String> {
+    pub fn tokenize(&mut self) -> Result<Vec<SpannedToken>, Diagnostic> {
         let mut tokens = Vec::new();
         loop {
             self.skip_whitespace_and_comments();
-            let line = self.line;
-            let col = self.col;
+            let start = self.offset;
             let ch = match self.peek() {
                 Some(ch) => ch,
                 None => {
                     tokens.push(SpannedToken {
                         token: Token::Eof,
-                        line,
-                        col,
+                        span: Span::new(start, start),
                     });
                     return Ok(tokens);
                 }
@@ -104,32 +386,109 @@ impl Lexer {
             let token = match ch {
                 '+' => {
                     self.advance();
-                    Token::Plus
+                    self.with_eq(Token::Plus, Token::PlusEq)
                 }
                 '-' => {
                     self.advance();
-                    Token::Minus
+                    self.with_eq(Token::Minus, Token::MinusEq)
                 }
                 '*' => {
                     self.advance();
-                    Token::Star
+                    self.with_eq(Token::Star, Token::StarEq)
                 }
                 '/' => {
+                    // `//` comments were already skipped
                     self.advance();
-                    Token::Slash
+                    self.with_eq(Token::Slash, Token::SlashEq)
                 }
                 '%' => {
                     self.advance();
-                    Token::Percent
+                    self.with_eq(Token::Percent, Token::PercentEq)
                 }
                 '=' => {
                     self.advance();
-                    Token::Eq
+                    if self.peek() == Some('=') {
+                        self.advance();
+                        Token::EqEq
+                    } else {
+                        Token::Eq
+                    }
+                }
+                '!' => {
+                    self.advance();
+                    if self.peek() == Some('=') {
+                        self.advance();
+                        Token::NotEq
+                    } else {
+                        Token::Bang
+                    }
+                }
+                '~' => {
+                    self.advance();
+                    Token::Tilde
+                }
+                '&' => {
+                    self.advance();
+                    if self.peek() == Some('&') {
+                        self.advance();
+                        Token::AndAnd
+                    } else {
+                        self.with_eq(Token::Amp, Token::AmpEq)
+                    }
+                }
+                '|' => {
+                    self.advance();
+                    if self.peek() == Some('|') {
+                        self.advance();
+                        Token::OrOr
+                    } else {
+                        self.with_eq(Token::Pipe, Token::PipeEq)
+                    }
+                }
+                '^' => {
+                    self.advance();
+                    self.with_eq(Token::Caret, Token::CaretEq)
+                }
+                '<' => {
+                    self.advance();
+                    match self.peek() {
+                        Some('=') => {
+                            self.advance();
+                            Token::LtEq
+                        }
+                        Some('<') => {
+                            self.advance();
+                            self.with_eq(Token::Shl, Token::ShlEq)
+                        }
+                        _ => Token::Lt,
+                    }
+                }
+                '>' => {
+                    self.advance();
+                    match self.peek() {
+                        Some('=') => {
+                            self.advance();
+                            Token::GtEq
+                        }
+                        Some('>') => {
+                            self.advance();
+                            self.with_eq(Token::Shr, Token::ShrEq)
+                        }
+                        _ => Token::Gt,
+                    }
                 }
                 ';' => {
                     self.advance();
                     Token::Semi
                 }
+                ':' => {
+                    self.advance();
+                    Token::Colon
+                }
+                ',' => {
+                    self.advance();
+                    Token::Comma
+                }
                 '(' => {
                     self.advance();
                     Token::LParen
@@ -138,18 +497,24 @@ impl Lexer {
                     self.advance();
                     Token::RParen
                 }
-                c if c.is_ascii_digit() => {
-                    let mut num = String::new();
-                    while let Some(c) = self.peek() {
-                        if c.is_ascii_digit() {
-                            num.push(c);
-                            self.advance();
-                        } else {
-                            break;
-                        }
-                    }
-                    Token::IntLit(num)
+                '{' => {
+                    self.advance();
+                    Token::LBrace
+                }
+                '}' => {
+                    self.advance();
+                    Token::RBrace
+                }
+                '[' => {
+                    self.advance();
+                    Token::LBracket
+                }
+                ']' => {
+                    self.advance();
+                    Token::RBracket
                 }
+                c if c.is_ascii_digit() => self.lex_number(start)?,
+                '"' => self.lex_string(start)?,
                 c if c.is_ascii_alphabetic() || c == '_' => {
                     let mut ident = String::new();
                     while let Some(c) = self.peek() {
@@ -162,19 +527,122 @@ impl Lexer {
                     }
                     match ident.as_str() {
                         "let" => Token::Let,
+                        "const" => Token::Const,
                         "print" => Token::Print,
+                        "write" => Token::Write,
+                        "if" => Token::If,
+                        "else" => Token::Else,
+                        "while" => Token::While,
+                        "break" => Token::Break,
+                        "continue" => Token::Continue,
+                        "fn" => Token::Fn,
+                        "return" => Token::Return,
+                        "true" => Token::True,
+                        "false" => Token::False,
+                        "as" => Token::As,
                         _ => Token::Ident(ident),
                     }
                 }
                 _ => {
-                    return Err(format!(
-                        "{}:{}: unexpected character '{}'",
-                        line, col, ch
+                    return Err(error(
+                        "E0107",
+                        Span::new(start, start + ch.len_utf8()),
+                        format!("unexpected character '{}'", ch),
                     ));
                 }
             };
 
-            tokens.push(SpannedToken { token, line, col });
+            tokens.push(SpannedToken {
+                token,
+                span: Span::new(start, self.offset),
+            });
+        }
+    }
+}
+
+/// An error of the lexer at `span`.
+fn error(code: &'static str, span: Span, message: String) -> Diagnostic {
+    Diagnostic::error(Phase::Lexer, code, message).with_label(span, "")
+}
+
+/// The radix of an integer literal's text and the length of its prefix.
+fn literal_radix(text: &str) -> (u32, usize) {
+    match text.get(..2) {
+        Some("0x") => (16, 2),
+        Some("0o") => (8, 2),
+        Some("0b") => (2, 2),
+        _ => (10, 0),
+    }
+}
+
+/// Check the text of a float literal: decimal digits, an optional fraction
+/// `.digits` and an optional exponent `e[+-]digits`, with `_` separators.
+/// `start` is the offset of the literal.
+fn check_float_literal(text: &str, start: usize) -> Result<(), Diagnostic> {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(i) => (&text[..i], Some(i + 1)),
+        None => (text, None),
+    };
+    let invalid = |i: usize| {
+        error(
+            "E0101",
+            Span::new(start + i, start + i + 1),
+            format!(
+                "invalid digit '{}' in float literal '{}'",
+                &text[i..i + 1],
+                text
+            ),
+        )
+    };
+    // The lexer only includes a `.` followed by a digit
+    if let Some(i) = mantissa.find(|c: char| c != '_' && c != '.' && !c.is_ascii_digit()) {
+        return Err(invalid(i));
+    }
+    if let Some(exponent) = exponent {
+        let exponent = exponent + text[exponent..].starts_with(['+', '-']) as usize;
+        let digits = &text[exponent..];
+        if let Some(i) = digits.find(|c: char| c != '_' && !c.is_ascii_digit()) {
+            return Err(invalid(exponent + i));
+        }
+        if !digits.contains(|c: char| c.is_ascii_digit()) {
+            return Err(error(
+                "E0102",
+                Span::new(start, start + text.len()),
+                format!("float literal '{}' has no digits in its exponent", text),
+            ));
         }
     }
+    Ok(())
+}
+
+/// The value of a float literal token's text, or `None` if it is too large
+/// for an `f64`. The lexer has already checked the text.
+pub fn float_literal_value(text: &str) -> Option<f64> {
+    let text: String = text.chars().filter(|&c| c != '_').collect();
+    text.parse::<f64>().ok().filter(|val| val.is_finite())
 }
+
+/// The value of an integer literal token's text, or `None` if it does not
+/// fit in 64 bits. The lexer has already checked the digits.
+pub fn int_literal_value(text: &str) -> Option<u64> {
+    let (radix, prefix_len) = literal_radix(text);
+    let digits: String = text[prefix_len..].chars().filter(|&c| c != '_').collect();
+    u64::from_str_radix(&digits, radix).ok()
+}
+
+/// Decode the first character of `raw`, the source text of a string literal
+/// (already checked by the lexer). Returns the character and the number of
+/// bytes of `raw` it spans.
+pub fn decode_string_char(raw: &str) -> (char, usize) {
+    let mut chars = raw.chars();
+    match chars.next().unwrap() {
+        '\\' => match chars.next().unwrap() {
+            'n' => ('\n', 2),
+            't' => ('\t', 2),
+            'x' => (u8::from_str_radix(&raw[2..4], 16).unwrap() as char, 4),
+            c => (c, 2),
+        },
+        c => (c, c.len_utf8()),
+    }
+}
+
@@ -1,70 +1,238 @@
 mod ast;
 mod codegen;
+mod diagnostic;
+mod interp;
 mod lexer;
 mod parser;
+mod span;
+mod target;
+mod typeck;
 
 use std::env;
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::{self, Command};
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: toy-compiler <input.toy> [-o output]");
-        process::exit(1);
+use diagnostic::Diagnostic;
+use span::SourceMap;
+
+const USAGE: &str = "\
+Usage: toy-compiler <input.toy> [-o output] [--target <triple>] [-S]
+                    [--error-format <human|json>] [--color <auto|always|never>]
+                    [--max-errors <n>]
+       toy-compiler run <input.toy> [--error-format <human|json>]
+                        [--color <auto|always|never>] [--max-errors <n>]";
+
+/// How many errors are reported by default before they are only counted.
+const DEFAULT_MAX_ERRORS: usize = 20;
+
+/// Stack size of the thread that parses, type checks and compiles the
+/// program. These phases recurse once per level of nested blocks and
+/// expressions, which the parser limits so that the deepest program fits,
+/// even in a debug build.
+const COMPILER_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Stack size of the thread that runs `toy-compiler run`.
+const INTERPRETER_STACK_SIZE: usize = 512 * 1024 * 1024;
+
+/// Command-line options for a compilation.
+struct Options {
+    /// `toy-compiler run`: interpret the program instead of compiling it.
+    run: bool,
+    input_path: String,
+    output_path: Option<PathBuf>,
+    /// Target triple; defaults to the host.
+    triple: String,
+    /// Stop after code generation and write assembly instead of an executable.
+    emit_asm: bool,
+    error_format: ErrorFormat,
+    color: ColorChoice,
+    /// How many errors to report before only counting them.
+    max_errors: usize,
+}
+
+/// How compile errors are printed: as text, or as one line of JSON each
+/// for tools (see `Diagnostic::to_json`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
+/// Whether human-readable errors are highlighted with ANSI colours. By
+/// default they are when standard error is a terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut input_path = None;
+    let mut output_path = None;
+    let mut triple = target::host_triple().to_string();
+    let mut emit_asm = false;
+    let mut error_format = ErrorFormat::Human;
+    let mut color = ColorChoice::Auto;
+    let mut max_errors = DEFAULT_MAX_ERRORS;
+
+    let run = args.first().is_some_and(|arg| arg == "run");
+    let mut iter = args.iter().skip(if run { 1 } else { 0 });
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-o" => {
+                let path = iter.next().ok_or("missing path after '-o'")?;
+                output_path = Some(PathBuf::from(path));
+            }
+            "--target" => {
+                triple = iter.next().ok_or("missing triple after '--target'")?.clone();
+            }
+            "-S" => emit_asm = true,
+            "--error-format" => {
+                error_format = match iter.next().map(String::as_str) {
+                    Some("human") => ErrorFormat::Human,
+                    Some("json") => ErrorFormat::Json,
+                    Some(format) => return Err(format!("unknown error format '{}'", format)),
+                    None => return Err("missing format after '--error-format'".to_string()),
+                };
+            }
+            "--color" => {
+                color = match iter.next().map(String::as_str) {
+                    Some("auto") => ColorChoice::Auto,
+                    Some("always") => ColorChoice::Always,
+                    Some("never") => ColorChoice::Never,
+                    Some(when) => return Err(format!("unknown color choice '{}'", when)),
+                    None => return Err("missing choice after '--color'".to_string()),
+                };
+            }
+            "--max-errors" => {
+                let n = iter.next().ok_or("missing number after '--max-errors'")?;
+                max_errors = n
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("invalid number of errors '{}'", n))?;
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option '{}'", arg));
+            }
+            _ if input_path.is_none() => input_path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
     }
 
-    let input_path = &args[1];
-    let output_path = if args.len() >= 4 && args[2] == "-o" {
-        PathBuf::from(&args[3])
-    } else {
-        // Default output name: input stem without extension
-        let stem = std::path::Path::new(input_path)
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("a.out");
-        PathBuf::from(stem)
-    };
+    if run && (output_path.is_some() || emit_asm) {
+        return Err("'-o' and '-S' cannot be used with 'run'".to_string());
+    }
+
+    Ok(Options {
+        run,
+        input_path: input_path.ok_or("no input file")?,
+        output_path,
+        triple,
+        emit_asm,
+        error_format,
+        color,
+        max_errors,
+    })
+}
 
-    let source = match fs::read_to_string(input_path) {
-        Ok(s) => s,
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let opts = match parse_args(&args) {
+        Ok(opts) => opts,
         Err(e) => {
-            eprintln!("Error reading '{}': {}", input_path, e);
+            eprintln!("Error: {}", e);
+            eprintln!("{}", USAGE);
             process::exit(1);
         }
     };
 
-    // Lex
-    let mut lexer = lexer::Lexer::new(&source);
-    let tokens = match lexer.tokenize() {
-        Ok(t) => t,
+    let compiler = std::thread::Builder::new()
+        .stack_size(COMPILER_STACK_SIZE)
+        .spawn(move || compile(opts));
+    match compiler.map(|thread| thread.join()) {
+        Ok(Ok(())) => {}
+        // The panic message has been printed
+        Ok(Err(_)) => process::exit(101),
         Err(e) => {
-            eprintln!("Lexer error: {}", e);
+            eprintln!("Error starting compiler: {}", e);
             process::exit(1);
         }
-    };
+    }
+}
+
+/// Compile or run the input file as `opts` say, exiting on any error.
+fn compile(opts: Options) {
+    let (source, program) = parse_file(&opts);
+
+    if opts.run {
+        // The interpreter recurses once per Toy call, so give it a stack big
+        // enough for its own call depth limit.
+        let result = std::thread::Builder::new()
+            .stack_size(INTERPRETER_STACK_SIZE)
+            .spawn(move || {
+                let stdout = io::stdout();
+                let interpreter = interp::Interpreter::new(
+                    &source,
+                    io::stdin().lock(),
+                    BufWriter::new(stdout.lock()),
+                );
+                interpreter.run(&program)
+            })
+            .map(|thread| thread.join().unwrap());
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                eprintln!("Runtime error: {}", e);
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error starting interpreter: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
-    // Parse
-    let mut parser = parser::Parser::new(tokens);
-    let stmts = match parser.parse_program() {
-        Ok(s) => s,
+    let target = match target::from_triple(&opts.triple) {
+        Ok(t) => t,
         Err(e) => {
-            eprintln!("Parse error: {}", e);
+            eprintln!("Error: {}", e);
             process::exit(1);
         }
     };
 
+    let input_path = &opts.input_path;
+    let output_path = opts.output_path.clone().unwrap_or_else(|| {
+        // Default output name: input stem without extension (plus `.s` for -S)
+        let stem = Path::new(input_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("a.out");
+        if opts.emit_asm {
+            PathBuf::from(format!("{stem}.s"))
+        } else {
+            PathBuf::from(stem)
+        }
+    });
+
     // Codegen
-    let codegen = codegen::Codegen::new();
-    let asm = match codegen.generate(&stmts) {
+    let codegen = codegen::Codegen::new(target.as_ref(), &source);
+    let asm = match codegen.generate(&program) {
         Ok(a) => a,
-        Err(e) => {
-            eprintln!("Codegen error: {}", e);
+        Err(diagnostic) => report(&[diagnostic], &opts, &source),
+    };
+
+    if opts.emit_asm {
+        if let Err(e) = fs::write(&output_path, &asm) {
+            eprintln!("Error writing '{}': {}", output_path.display(), e);
             process::exit(1);
         }
-    };
+        return;
+    }
 
     // Write assembly to a temp file (use PID to avoid collisions)
     let tmp_dir = env::temp_dir();
@@ -78,32 +246,89 @@ fn main() {
     }
 
     // Assemble
-    let as_status = Command::new("as")
-        .args(["-o", obj_path.to_str().unwrap(), asm_path.to_str().unwrap()])
-        .status()
-        .expect("failed to run assembler");
+    run_tool(target.assemble_command(&asm_path, &obj_path), "Assembly");
+
+    // Link (the C compiler driver finds the right SDK and libraries)
+    run_tool(target.link_command(&obj_path, &output_path), "Linking");
 
-    if !as_status.success() {
-        eprintln!("Assembly failed");
+    // Clean up temp files
+    let _ = fs::remove_file(&asm_path);
+    let _ = fs::remove_file(&obj_path);
+}
+
+/// Run an external toolchain command, exiting if it fails.
+fn run_tool(mut cmd: Command, step: &str) {
+    let status = match cmd.status() {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!(
+                "{} failed: could not run '{}': {}",
+                step,
+                cmd.get_program().to_string_lossy(),
+                e
+            );
+            process::exit(1);
+        }
+    };
+    if !status.success() {
+        eprintln!("{} failed", step);
         process::exit(1);
     }
+}
 
-    // Link using cc (handles finding the right SDK and libraries)
-    let cc_status = Command::new("cc")
-        .args([
-            "-o",
-            output_path.to_str().unwrap(),
-            obj_path.to_str().unwrap(),
-        ])
-        .status()
-        .expect("failed to run linker");
-
-    if !cc_status.success() {
-        eprintln!("Linking failed");
-        process::exit(1);
+/// Read, lex, parse and type check the input file, exiting with a message
+/// on any error. Returns the program with its source, which locates the
+/// program's spans.
+///
+/// The parser recovers from syntax errors, so that they are all reported
+/// at once, along with every error the type checker finds in the
+/// statements that did parse. The lexer does not recover: a lexical error
+/// is reported on its own, and hides every other error in the file.
+fn parse_file(opts: &Options) -> (SourceMap, ast::Program) {
+    let source = match fs::read_to_string(&opts.input_path) {
+        Ok(s) => SourceMap::new(&s),
+        Err(e) => {
+            eprintln!("Error reading '{}': {}", opts.input_path, e);
+            process::exit(1);
+        }
+    };
+
+    let tokens = match lexer::Lexer::new(source.text()).tokenize() {
+        Ok(tokens) => tokens,
+        Err(diagnostic) => report(&[diagnostic], opts, &source),
+    };
+    let (mut program, mut diagnostics) = parser::Parser::new(tokens).parse_program();
+    diagnostics.extend(typeck::check(&mut program));
+    if diagnostics.len() > opts.max_errors {
+        let dropped = diagnostics.len() - opts.max_errors;
+        diagnostics.truncate(opts.max_errors);
+        let last = diagnostics.pop().unwrap();
+        diagnostics.push(last.with_note(format!("{} more error(s) not shown", dropped)));
     }
+    if !diagnostics.is_empty() {
+        report(&diagnostics, opts, &source);
+    }
+    (source, program)
+}
 
-    // Clean up temp files
-    let _ = fs::remove_file(&asm_path);
-    let _ = fs::remove_file(&obj_path);
+/// Print diagnostics about the input file in the chosen format and exit.
+/// As text, the diagnostics are separated by blank lines.
+fn report(diagnostics: &[Diagnostic], opts: &Options, source: &SourceMap) -> ! {
+    let color = match opts.color {
+        ColorChoice::Auto => io::stderr().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        match opts.error_format {
+            ErrorFormat::Human => {
+                if i > 0 {
+                    eprintln!();
+                }
+                eprintln!("{}", diagnostic.render(&opts.input_path, source, color));
+            }
+            ErrorFormat::Json => eprintln!("{}", diagnostic.to_json(&opts.input_path, source)),
+        }
+    }
+    process::exit(1);
 }
@@ -1,11 +1,33 @@
-use crate::ast::{BinOp, Expr, Stmt};
-use crate::lexer::{SpannedToken, Token};
+use crate::ast::{
+    BinOp, Block, Expr, ExprKind, FormatPiece, FormatRadix, FormatSpec, Function, LogicalOp,
+    Program, Stmt, StmtKind, Type,
+};
+use crate::diagnostic::{Diagnostic, Phase};
+use crate::lexer::{
+    SpannedToken, Token, decode_string_char, float_literal_value, int_literal_value,
+};
+use crate::span::Span;
 
 pub struct Parser {
     tokens: Vec<SpannedToken>,
     pos: usize,
     /// Current expression nesting depth (parentheses and unary operators).
     depth: usize,
+    /// Current statement nesting depth (blocks, and the `if` of each
+    /// `else if`).
+    block_depth: usize,
+    /// Number of `while` loops enclosing the current statement, so that
+    /// `break` and `continue` outside a loop can be rejected.
+    loop_depth: usize,
+    /// Whether we are inside a function body, where `return` is allowed.
+    in_function: bool,
+    /// The errors found so far.
+    errors: Vec<Diagnostic>,
+    /// The name declared by the statement or function being parsed, once
+    /// it is known.
+    declaring: Option<String>,
+    /// Names declared by statements and functions that failed to parse.
+    lost_names: Vec<String>,
 }
 
 /// Maximum expression nesting depth. Prevents stack overflow in the
@@ -13,18 +35,50 @@ pub struct Parser {
 /// parentheses or long chains of unary minus.
 const MAX_DEPTH: usize = 256;
 
+/// Maximum statement nesting depth. Like `MAX_DEPTH` for expressions, it
+/// prevents stack overflow in the parser, and in the type checker and code
+/// generator, which recurse into nested blocks the same way.
+const MAX_BLOCK_DEPTH: usize = 128;
+
+/// Maximum number of elements of an array. Arrays live in the stack frame,
+/// which also limits how many can be live at once (see `Codegen`).
+pub const MAX_ARRAY_LEN: usize = 4096;
+
+/// Functions provided by the language, whose names user functions cannot
+/// take.
+const BUILTIN_FUNCTIONS: &[&str] = &["len", "input"];
+
 impl Parser {
     pub fn new(tokens: Vec<SpannedToken>) -> Self {
-        Parser { tokens, pos: 0, depth: 0 }
+        Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+            block_depth: 0,
+            loop_depth: 0,
+            in_function: false,
+            errors: Vec::new(),
+            declaring: None,
+            lost_names: Vec::new(),
+        }
     }
 
     fn peek(&self) -> &Token {
         &self.tokens[self.pos].token
     }
 
-    fn current_span(&self) -> (usize, usize) {
-        let t = &self.tokens[self.pos];
-        (t.line, t.col)
+    fn peek_next(&self) -> &Token {
+        let next = (self.pos + 1).min(self.tokens.len() - 1);
+        &self.tokens[next].token
+    }
+
+    fn current_span(&self) -> Span {
+        self.tokens[self.pos].span
+    }
+
+    /// The span from byte `start` to the end of the last token consumed.
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.tokens[self.pos.saturating_sub(1)].span.end)
     }
 
     fn advance(&mut self) -> &SpannedToken {
@@ -35,69 +89,378 @@ impl Parser {
         t
     }
 
-    fn expect(&mut self, expected: &Token) -> Result<(), String> {
-        let (line, col) = self.current_span();
+    fn expect(&mut self, expected: &Token) -> Result<(), Diagnostic> {
         if self.peek() == expected {
             self.advance();
             Ok(())
         } else {
-            Err(format!(
-                "{}:{}: expected {:?}, found {:?}",
-                line,
-                col,
-                expected,
-                self.peek()
+            Err(error(
+                "E0201",
+                self.current_span(),
+                format!("expected {}, found {}", expected, self.peek()),
             ))
         }
     }
 
-    pub fn parse_program(&mut self) -> Result<Vec<Stmt>, String> {
+    /// Consume the `;` that ends a statement. When it is missing but the
+    /// next token clearly starts another statement, or ends the block or
+    /// the file, the error is recorded and parsing goes on as if it were
+    /// there, keeping the statement. The error then points just after the
+    /// statement, where the `;` belongs.
+    fn expect_semi(&mut self) -> Result<(), Diagnostic> {
+        match self.expect(&Token::Semi) {
+            Err(e)
+                if starts_stmt(self.peek())
+                    || matches!(self.peek(), Token::RBrace | Token::Eof) =>
+            {
+                let end = self.tokens[self.pos - 1].span.end;
+                self.record(error("E0201", Span::new(end, end), e.message));
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Record an error and go on parsing.
+    fn record(&mut self, diagnostic: Diagnostic) {
+        self.errors.push(diagnostic);
+    }
+
+    /// Parse the whole program. Parsing does not stop at a syntax error: the
+    /// statement (or function) with the error is left out, and parsing
+    /// resumes after it (see `synchronize`). Returns the statements and
+    /// functions that parsed, with the errors, if any.
+    pub fn parse_program(mut self) -> (Program, Vec<Diagnostic>) {
+        let mut functions = Vec::new();
         let mut stmts = Vec::new();
         while *self.peek() != Token::Eof {
-            stmts.push(self.parse_stmt()?);
+            let start = self.pos;
+            let result = if *self.peek() == Token::Fn {
+                self.parse_function()
+                    .map(|function| functions.push(function))
+            } else {
+                self.parse_stmt().map(|stmt| stmts.push(stmt))
+            };
+            if let Err(e) = result {
+                self.recover(e, start);
+            }
         }
-        Ok(stmts)
+        let program = Program {
+            functions,
+            stmts,
+            lost_names: self.lost_names,
+        };
+        (program, self.errors)
     }
 
-    fn parse_stmt(&mut self) -> Result<Stmt, String> {
-        match self.peek().clone() {
-            Token::Let => self.parse_let(),
-            Token::Print => self.parse_print(),
-            Token::Ident(_) => self.parse_assign(),
-            _ => {
-                let (line, col) = self.current_span();
-                Err(format!(
-                    "{}:{}: expected statement, found {:?}",
-                    line,
-                    col,
-                    self.peek()
-                ))
+    /// Record the error `e` in the statement or function that started at
+    /// token `start`, and skip the rest of it.
+    fn recover(&mut self, e: Diagnostic, start: usize) {
+        self.record(e);
+        self.lost_names.extend(self.declaring.take());
+        self.synchronize(start);
+    }
+
+    /// Skip the rest of a statement that failed to parse, which started at
+    /// token `start`: up to and including the next `;`, or up to the next
+    /// keyword that starts a statement, or the `}` that closes the enclosing
+    /// block. Braced blocks on the way are skipped whole, with any `else` or
+    /// `else if` after them. At least one token is skipped if the statement consumed
+    /// none, so that parsing always moves on.
+    fn synchronize(&mut self, start: usize) {
+        // Nesting depth is counted afresh for each statement
+        self.depth = 0;
+        let mut braces = 0;
+        if self.pos == start {
+            // A block statement that failed before its `{` is skipped whole
+            if *self.peek() == Token::LBrace {
+                braces += 1;
+            }
+            self.advance();
+        }
+        loop {
+            match self.peek() {
+                Token::Eof => return,
+                Token::Semi if braces == 0 => {
+                    self.advance();
+                    return;
+                }
+                Token::RBrace if braces == 0 => return,
+                Token::RBrace => {
+                    braces -= 1;
+                    self.advance();
+                    if braces == 0 && *self.peek() != Token::Else {
+                        return;
+                    }
+                    continue;
+                }
+                Token::LBrace => braces += 1,
+                // The `if` of an `else if` is part of the skipped statement
+                Token::If if self.tokens[self.pos - 1].token == Token::Else => {}
+                token if braces == 0 && starts_stmt(token) => return,
+                _ => {}
             }
+            self.advance();
         }
     }
 
-    fn parse_let(&mut self) -> Result<Stmt, String> {
-        self.advance(); // consume 'let'
-        let (line, col) = self.current_span();
-        let name = match self.peek().clone() {
+    fn parse_function(&mut self) -> Result<Function, Diagnostic> {
+        self.declaring = None;
+        self.advance(); // consume 'fn'
+        let name_span = self.current_span();
+        let name = self.expect_ident("'fn'")?;
+        self.declaring = Some(name.clone());
+        if BUILTIN_FUNCTIONS.contains(&name.as_str()) {
+            self.record(error(
+                "E0202",
+                name_span,
+                format!("'{}' is a built-in function and cannot be redefined", name),
+            ));
+        }
+        self.expect(&Token::LParen)?;
+        let mut params: Vec<String> = Vec::new();
+        let mut param_spans = Vec::new();
+        if *self.peek() != Token::RParen {
+            loop {
+                let span = self.current_span();
+                let param = self.expect_ident("'(' or ','")?;
+                if let Some(first) = params.iter().position(|other| *other == param) {
+                    self.record(
+                        error("E0203", span, format!("duplicate parameter '{}'", param))
+                            .with_secondary_label(param_spans[first], "first declared here"),
+                    );
+                }
+                params.push(param);
+                param_spans.push(span);
+                if *self.peek() != Token::Comma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        self.expect(&Token::RParen)?;
+        self.in_function = true;
+        let body = self.parse_block();
+        self.in_function = false;
+        let body = body?;
+        Ok(Function {
+            name,
+            name_span,
+            params,
+            body,
+        })
+    }
+
+    /// Consume an identifier, or report that one was expected after `what`.
+    fn expect_ident(&mut self, what: &str) -> Result<String, Diagnostic> {
+        match self.peek().clone() {
             Token::Ident(name) => {
                 self.advance();
-                name
+                Ok(name)
+            }
+            _ => Err(error(
+                "E0201",
+                self.current_span(),
+                format!("expected identifier after {}", what),
+            )),
+        }
+    }
+
+    fn parse_stmt(&mut self) -> Result<Stmt, Diagnostic> {
+        self.declaring = None;
+        let start = self.current_span().start;
+        let kind = match self.peek().clone() {
+            Token::Let => self.parse_let()?,
+            Token::Const => self.parse_const()?,
+            Token::Print | Token::Write => self.parse_print()?,
+            Token::Ident(_) if *self.peek_next() == Token::LParen => {
+                let expr = self.parse_expr()?;
+                // Like `f(1) + 2`: the call is only part of the expression,
+                // whose value would be discarded
+                if !matches!(expr.kind, ExprKind::Call { .. } | ExprKind::Input) {
+                    let mut diagnostic = error(
+                        "E0213",
+                        expr.span,
+                        "only a call can be used as a statement".to_string(),
+                    );
+                    // `len(a)` looks like a call, but has no effect
+                    if matches!(expr.kind, ExprKind::Len(_)) {
+                        diagnostic =
+                            diagnostic.with_note("'len' is built in and only computes the length");
+                    }
+                    self.record(
+                        diagnostic
+                            .with_help("to use the value, assign it to a variable with 'let'"),
+                    );
+                }
+                self.expect_semi()?;
+                StmtKind::Expr { expr }
+            }
+            Token::Ident(_) => self.parse_assign()?,
+            Token::If => self.parse_if()?,
+            Token::While => self.parse_while()?,
+            Token::Break | Token::Continue => self.parse_loop_jump()?,
+            Token::LBrace => StmtKind::Block(self.nested(Self::parse_block)?),
+            Token::Return => self.parse_return()?,
+            Token::Fn => {
+                return Err(error(
+                    "E0204",
+                    self.current_span(),
+                    "functions can only be declared at the top level".to_string(),
+                ));
             }
             _ => {
-                return Err(format!(
-                    "{}:{}: expected identifier after 'let'",
-                    line, col
+                return Err(error(
+                    "E0201",
+                    self.current_span(),
+                    format!("expected statement, found {}", self.peek()),
                 ));
             }
         };
+        // Statements nested in this one may have set it
+        self.declaring = None;
+        Ok(Stmt {
+            kind,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_let(&mut self) -> Result<StmtKind, Diagnostic> {
+        self.advance(); // consume 'let'
+        let name = self.expect_ident("'let'")?;
+        self.declaring = Some(name.clone());
+        // An optional annotation: `: ty`, or `: [ty; len]` for an array
+        let mut ty = None;
+        let mut array_len = None;
+        if *self.peek() == Token::Colon {
+            self.advance();
+            if *self.peek() == Token::LBracket {
+                self.advance();
+                ty = Some(self.parse_type("'['")?);
+                self.expect(&Token::Semi)?;
+                array_len = Some(self.parse_array_len()?);
+                self.expect(&Token::RBracket)?;
+            } else {
+                ty = Some(self.parse_type("':'")?);
+            }
+        }
+        self.expect(&Token::Eq)?;
+        let span = self.current_span();
+        match (*self.peek() == Token::LBracket, ty, array_len) {
+            (true, Some(ty), None) => Err(error(
+                "E0205",
+                span,
+                format!("mismatched types: expected {}, found an array", ty),
+            )),
+            (true, _, _) => self.parse_array(name, ty, array_len),
+            (false, _, Some(_)) => Err(error(
+                "E0205",
+                span,
+                "expected an array '[init; len]'".to_string(),
+            )),
+            (false, _, None) => {
+                let expr = self.parse_expr()?;
+                self.expect_semi()?;
+                Ok(StmtKind::Let { name, ty, expr })
+            }
+        }
+    }
+
+    fn parse_const(&mut self) -> Result<StmtKind, Diagnostic> {
+        self.advance(); // consume 'const'
+        let name = self.expect_ident("'const'")?;
+        self.declaring = Some(name.clone());
+        let ty = if *self.peek() == Token::Colon {
+            self.advance();
+            Some(self.parse_type("':'")?)
+        } else {
+            None
+        };
         self.expect(&Token::Eq)?;
         let expr = self.parse_expr()?;
+        self.expect_semi()?;
+        Ok(StmtKind::Const {
+            name,
+            ty,
+            expr,
+            value: None,
+        })
+    }
+
+    /// Parse the name of a type, which was expected after `what`.
+    fn parse_type(&mut self, what: &str) -> Result<Type, Diagnostic> {
+        let span = self.current_span();
+        let name = self.expect_ident(what)?;
+        Type::from_name(&name)
+            .ok_or_else(|| error("E0206", span, format!("unknown type '{}'", name)))
+    }
+
+    /// Parse the `[init; len];` rest of an array declaration, whose
+    /// annotation, if any, gave the element type `ty` and the length
+    /// `declared_len`.
+    fn parse_array(
+        &mut self,
+        name: String,
+        ty: Option<Type>,
+        declared_len: Option<usize>,
+    ) -> Result<StmtKind, Diagnostic> {
+        self.advance(); // consume '['
+        let init = self.parse_expr()?;
         self.expect(&Token::Semi)?;
-        Ok(Stmt::Let { name, expr })
+        let len_span = self.current_span();
+        let len = self.parse_array_len()?;
+        if let Some(declared_len) = declared_len
+            && len != declared_len
+        {
+            return Err(error(
+                "E0205",
+                len_span,
+                format!(
+                    "array length {} does not match the declared length {}",
+                    len, declared_len
+                ),
+            ));
+        }
+        self.expect(&Token::RBracket)?;
+        self.expect_semi()?;
+        Ok(StmtKind::LetArray {
+            name,
+            ty,
+            init,
+            len,
+        })
     }
 
-    fn parse_assign(&mut self) -> Result<Stmt, String> {
+    /// Parse the length of an array, which must be an integer literal, since
+    /// arrays live in the stack frame.
+    fn parse_array_len(&mut self) -> Result<usize, Diagnostic> {
+        let span = self.current_span();
+        match self.peek().clone() {
+            Token::IntLit(s) => {
+                self.advance();
+                int_literal_value(&s)
+                    .and_then(|len| usize::try_from(len).ok())
+                    .filter(|len| *len <= MAX_ARRAY_LEN)
+                    .ok_or_else(|| {
+                        error(
+                            "E0207",
+                            span,
+                            format!(
+                                "array length '{}' is too large (maximum is {})",
+                                s, MAX_ARRAY_LEN
+                            ),
+                        )
+                    })
+            }
+            _ => Err(error(
+                "E0207",
+                span,
+                "array length must be an integer literal".to_string(),
+            )),
+        }
+    }
+
+    fn parse_assign(&mut self) -> Result<StmtKind, Diagnostic> {
         let name = match self.peek().clone() {
             Token::Ident(name) => {
                 self.advance();
@@ -105,113 +468,606 @@ impl Parser {
             }
             _ => unreachable!(),
         };
-        self.expect(&Token::Eq)?;
+        let index = if *self.peek() == Token::LBracket {
+            self.advance();
+            let index = self.parse_expr()?;
+            self.expect(&Token::RBracket)?;
+            Some(index)
+        } else {
+            None
+        };
+        let op = match self.peek() {
+            Token::PlusEq => Some(BinOp::Add),
+            Token::MinusEq => Some(BinOp::Sub),
+            Token::StarEq => Some(BinOp::Mul),
+            Token::SlashEq => Some(BinOp::Div),
+            Token::PercentEq => Some(BinOp::Mod),
+            Token::AmpEq => Some(BinOp::BitAnd),
+            Token::PipeEq => Some(BinOp::BitOr),
+            Token::CaretEq => Some(BinOp::BitXor),
+            Token::ShlEq => Some(BinOp::Shl),
+            Token::ShrEq => Some(BinOp::Shr),
+            _ => None,
+        };
+        if op.is_some() {
+            self.advance();
+        } else {
+            self.expect(&Token::Eq)?;
+        }
         let expr = self.parse_expr()?;
-        self.expect(&Token::Semi)?;
-        Ok(Stmt::Assign { name, expr })
+        self.expect_semi()?;
+        Ok(match (index, op) {
+            (Some(index), op) => StmtKind::AssignIndex {
+                name,
+                index,
+                op,
+                expr,
+                ty: None,
+            },
+            (None, Some(op)) => StmtKind::CompoundAssign {
+                name,
+                op,
+                expr,
+                ty: None,
+            },
+            (None, None) => StmtKind::Assign { name, expr },
+        })
     }
 
-    fn parse_print(&mut self) -> Result<Stmt, String> {
-        self.advance(); // consume 'print'
+    /// Parse a `print` or `write` statement. They are the same, except that
+    /// `print` ends the output with a newline.
+    fn parse_print(&mut self) -> Result<StmtKind, Diagnostic> {
+        let newline = *self.peek() == Token::Print;
+        self.advance(); // consume 'print' or 'write'
+        let span = self.current_span();
+        if let Token::StrLit(raw) = self.peek().clone() {
+            self.advance();
+            // The text of the literal starts after the opening quote
+            let pieces = parse_format(&raw, span.start + 1)?;
+            let mut args = Vec::new();
+            while *self.peek() == Token::Comma {
+                self.advance();
+                args.push(self.parse_expr()?);
+            }
+            let placeholders = pieces
+                .iter()
+                .filter(|piece| matches!(piece, FormatPiece::Arg(_)))
+                .count();
+            if placeholders != args.len() {
+                return Err(error(
+                    "E0208",
+                    span,
+                    format!(
+                        "format string has {} placeholder(s) but {} argument(s) were supplied",
+                        placeholders,
+                        args.len()
+                    ),
+                ));
+            }
+            self.expect_semi()?;
+            return Ok(StmtKind::PrintFormat {
+                pieces,
+                args,
+                newline,
+            });
+        }
         let expr = self.parse_expr()?;
-        self.expect(&Token::Semi)?;
-        Ok(Stmt::Print { expr })
+        self.expect_semi()?;
+        if newline {
+            Ok(StmtKind::Print { expr })
+        } else {
+            Ok(StmtKind::PrintFormat {
+                pieces: vec![FormatPiece::Arg(FormatSpec::default())],
+                args: vec![expr],
+                newline,
+            })
+        }
     }
 
-    fn parse_expr(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_term()?;
-        loop {
-            let op = match self.peek() {
-                Token::Plus => BinOp::Add,
-                Token::Minus => BinOp::Sub,
-                _ => break,
-            };
+    fn parse_if(&mut self) -> Result<StmtKind, Diagnostic> {
+        self.advance(); // consume 'if'
+        let cond = self.parse_expr()?;
+        let then_block = self.nested(Self::parse_block)?;
+        let else_block = if *self.peek() == Token::Else {
+            self.advance();
+            if *self.peek() == Token::If {
+                // `else if ...` is sugar for `else { if ... }`
+                let start = self.current_span().start;
+                let kind = self.nested(Self::parse_if)?;
+                let span = self.span_from(start);
+                Some(Block {
+                    stmts: vec![Stmt { kind, span }],
+                })
+            } else {
+                Some(self.nested(Self::parse_block)?)
+            }
+        } else {
+            None
+        };
+        Ok(StmtKind::If {
+            cond,
+            then_block,
+            else_block,
+        })
+    }
+
+    fn parse_while(&mut self) -> Result<StmtKind, Diagnostic> {
+        self.advance(); // consume 'while'
+        let cond = self.parse_expr()?;
+        self.loop_depth += 1;
+        let body = self.nested(Self::parse_block);
+        self.loop_depth -= 1;
+        Ok(StmtKind::While { cond, body: body? })
+    }
+
+    /// Parse `break;` or `continue;`.
+    fn parse_loop_jump(&mut self) -> Result<StmtKind, Diagnostic> {
+        let span = self.current_span();
+        let (kind, keyword) = match self.advance().token {
+            Token::Break => (StmtKind::Break, "break"),
+            _ => (StmtKind::Continue, "continue"),
+        };
+        if self.loop_depth == 0 {
+            return Err(error(
+                "E0209",
+                span,
+                format!("'{}' outside of a loop", keyword),
+            ));
+        }
+        self.expect_semi()?;
+        Ok(kind)
+    }
+
+    fn parse_return(&mut self) -> Result<StmtKind, Diagnostic> {
+        let span = self.current_span();
+        self.advance(); // consume 'return'
+        if !self.in_function {
+            return Err(error(
+                "E0210",
+                span,
+                "'return' outside of a function".to_string(),
+            ));
+        }
+        let expr = self.parse_expr()?;
+        self.expect_semi()?;
+        Ok(StmtKind::Return { expr })
+    }
+
+    fn parse_block(&mut self) -> Result<Block, Diagnostic> {
+        self.expect(&Token::LBrace)?;
+        let mut stmts = Vec::new();
+        while *self.peek() != Token::RBrace {
+            if *self.peek() == Token::Eof {
+                // Keep the statements of the block
+                self.record(error(
+                    "E0201",
+                    self.current_span(),
+                    "expected '}', found end of file".to_string(),
+                ));
+                return Ok(Block { stmts });
+            }
+            let start = self.pos;
+            match self.parse_stmt() {
+                Ok(stmt) => stmts.push(stmt),
+                Err(e) => self.recover(e, start),
+            }
+        }
+        self.advance(); // consume '}'
+        Ok(Block { stmts })
+    }
+
+    /// Parse a nested block, or the `if` of an `else if`, with `parse`, one
+    /// level deeper into nested statements.
+    fn nested<T>(
+        &mut self,
+        parse: fn(&mut Self) -> Result<T, Diagnostic>,
+    ) -> Result<T, Diagnostic> {
+        if self.block_depth == MAX_BLOCK_DEPTH {
+            return Err(error(
+                "E0214",
+                self.current_span(),
+                format!("blocks are nested too deeply (limit is {})", MAX_BLOCK_DEPTH),
+            ));
+        }
+        self.block_depth += 1;
+        let result = parse(self);
+        self.block_depth -= 1;
+        result
+    }
+
+    /// Go one level deeper into nested expressions, at the current token.
+    fn enter_nesting(&mut self) -> Result<(), Diagnostic> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(error(
+                "E0211",
+                self.current_span(),
+                format!("expression is too deeply nested (limit is {})", MAX_DEPTH),
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_or()
+    }
+
+    /// Parse a left-associative chain of operands separated by binary
+    /// operators of one precedence level. `op_for` maps a token to the
+    /// operator it stands for at this level, and `operand` parses the next
+    /// level.
+    fn parse_binary(
+        &mut self,
+        op_for: fn(&Token) -> Option<BinOp>,
+        operand: fn(&mut Self) -> Result<Expr, Diagnostic>,
+    ) -> Result<Expr, Diagnostic> {
+        let start = self.current_span().start;
+        let mut left = operand(self)?;
+        while let Some(op) = op_for(self.peek()) {
+            let op_span = self.current_span();
             self.advance();
-            let right = self.parse_term()?;
-            left = Expr::BinOp {
+            let right = operand(self)?;
+            let kind = ExprKind::BinOp {
                 op,
+                op_span,
                 left: Box::new(left),
                 right: Box::new(right),
             };
+            left = Expr::new(kind, self.span_from(start));
         }
         Ok(left)
     }
 
-    fn parse_term(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_unary()?;
-        loop {
-            let op = match self.peek() {
-                Token::Star => BinOp::Mul,
-                Token::Slash => BinOp::Div,
-                Token::Percent => BinOp::Mod,
-                _ => break,
-            };
+    fn parse_or(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_logical(Token::OrOr, LogicalOp::Or, Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_logical(Token::AndAnd, LogicalOp::And, Self::parse_comparison)
+    }
+
+    /// Like `parse_binary`, for the short-circuiting operators.
+    fn parse_logical(
+        &mut self,
+        token: Token,
+        op: LogicalOp,
+        operand: fn(&mut Self) -> Result<Expr, Diagnostic>,
+    ) -> Result<Expr, Diagnostic> {
+        let start = self.current_span().start;
+        let mut left = operand(self)?;
+        while *self.peek() == token {
+            let op_span = self.current_span();
             self.advance();
-            let right = self.parse_unary()?;
-            left = Expr::BinOp {
+            let right = operand(self)?;
+            let kind = ExprKind::Logical {
                 op,
+                op_span,
                 left: Box::new(left),
                 right: Box::new(right),
             };
+            left = Expr::new(kind, self.span_from(start));
         }
         Ok(left)
     }
 
-    fn parse_unary(&mut self) -> Result<Expr, String> {
-        if *self.peek() == Token::Minus {
-            let (line, col) = self.current_span();
-            self.depth += 1;
-            if self.depth > MAX_DEPTH {
-                return Err(format!(
-                    "{}:{}: expression is too deeply nested (limit is {})",
-                    line, col, MAX_DEPTH
-                ));
-            }
+    fn parse_comparison(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_binary(
+            |token| match token {
+                Token::EqEq => Some(BinOp::Eq),
+                Token::NotEq => Some(BinOp::Ne),
+                Token::Lt => Some(BinOp::Lt),
+                Token::LtEq => Some(BinOp::Le),
+                Token::Gt => Some(BinOp::Gt),
+                Token::GtEq => Some(BinOp::Ge),
+                _ => None,
+            },
+            Self::parse_bit_or,
+        )
+    }
+
+    fn parse_bit_or(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_binary(
+            |token| (*token == Token::Pipe).then_some(BinOp::BitOr),
+            Self::parse_bit_xor,
+        )
+    }
+
+    fn parse_bit_xor(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_binary(
+            |token| (*token == Token::Caret).then_some(BinOp::BitXor),
+            Self::parse_bit_and,
+        )
+    }
+
+    fn parse_bit_and(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_binary(
+            |token| (*token == Token::Amp).then_some(BinOp::BitAnd),
+            Self::parse_shift,
+        )
+    }
+
+    fn parse_shift(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_binary(
+            |token| match token {
+                Token::Shl => Some(BinOp::Shl),
+                Token::Shr => Some(BinOp::Shr),
+                _ => None,
+            },
+            Self::parse_additive,
+        )
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_binary(
+            |token| match token {
+                Token::Plus => Some(BinOp::Add),
+                Token::Minus => Some(BinOp::Sub),
+                _ => None,
+            },
+            Self::parse_term,
+        )
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_binary(
+            |token| match token {
+                Token::Star => Some(BinOp::Mul),
+                Token::Slash => Some(BinOp::Div),
+                Token::Percent => Some(BinOp::Mod),
+                _ => None,
+            },
+            Self::parse_cast,
+        )
+    }
+
+    /// Parse `unary ("as" type)*`. A cast binds tighter than any binary
+    /// operator but looser than the unary ones: `-x as u8` is `(-x) as u8`.
+    fn parse_cast(&mut self) -> Result<Expr, Diagnostic> {
+        let start = self.current_span().start;
+        let mut expr = self.parse_unary()?;
+        while *self.peek() == Token::As {
+            let op_span = self.current_span();
             self.advance();
-            let expr = self.parse_unary()?;
+            let ty = self.parse_type("'as'")?;
+            let kind = ExprKind::Cast {
+                expr: Box::new(expr),
+                ty,
+                op_span,
+            };
+            expr = Expr::new(kind, self.span_from(start));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, Diagnostic> {
+        let op = self.peek().clone();
+        let start = self.current_span().start;
+        if matches!(op, Token::Minus | Token::Bang | Token::Tilde) {
+            self.enter_nesting()?;
+            self.advance();
+            let expr = Box::new(self.parse_unary()?);
             self.depth -= 1;
-            Ok(Expr::UnaryMinus(Box::new(expr)))
+            let kind = match op {
+                Token::Minus => ExprKind::UnaryMinus(expr),
+                Token::Bang => ExprKind::Not(expr),
+                _ => ExprKind::BitNot(expr),
+            };
+            Ok(Expr::new(kind, self.span_from(start)))
         } else {
             self.parse_atom()
         }
     }
 
-    fn parse_atom(&mut self) -> Result<Expr, String> {
-        let (line, col) = self.current_span();
-        match self.peek().clone() {
+    fn parse_atom(&mut self) -> Result<Expr, Diagnostic> {
+        let span = self.current_span();
+        let start = span.start;
+        let kind = match self.peek().clone() {
             Token::IntLit(s) => {
                 self.advance();
-                // Parse as u64 first to handle the full range of i64 values
-                // (the value 9223372036854775808 can appear as the operand of unary minus)
-                let val: i64 = s.parse().map_err(|e| {
-                    format!("{}:{}: invalid integer literal '{}': {}", line, col, s, e)
+                // The type checker checks the value against the literal's type
+                let val = int_literal_value(&s).ok_or_else(|| {
+                    error(
+                        "E0212",
+                        span,
+                        format!(
+                            "integer literal '{}' is out of range (maximum is {})",
+                            s,
+                            u64::MAX
+                        ),
+                    )
+                })?;
+                ExprKind::IntLit(val)
+            }
+            Token::FloatLit(s) => {
+                self.advance();
+                let val = float_literal_value(&s).ok_or_else(|| {
+                    error(
+                        "E0212",
+                        span,
+                        format!(
+                            "float literal '{}' is out of range (maximum is {:e})",
+                            s,
+                            f64::MAX
+                        ),
+                    )
                 })?;
-                Ok(Expr::IntLit(val))
+                ExprKind::FloatLit(val)
+            }
+            Token::True | Token::False => {
+                let val = *self.peek() == Token::True;
+                self.advance();
+                ExprKind::BoolLit(val)
+            }
+            Token::Ident(name) if name == "len" && *self.peek_next() == Token::LParen => {
+                self.advance(); // consume 'len'
+                self.advance(); // consume '('
+                let name = self.expect_ident("'len('")?;
+                self.expect(&Token::RParen)?;
+                ExprKind::Len(name)
+            }
+            Token::Ident(name) if name == "input" && *self.peek_next() == Token::LParen => {
+                self.advance(); // consume 'input'
+                self.advance(); // consume '('
+                self.expect(&Token::RParen)?;
+                ExprKind::Input
+            }
+            Token::Ident(name) if *self.peek_next() == Token::LParen => {
+                self.enter_nesting()?;
+                self.advance(); // consume name
+                self.advance(); // consume '('
+                let mut args = Vec::new();
+                if *self.peek() != Token::RParen {
+                    loop {
+                        args.push(self.parse_expr()?);
+                        if *self.peek() != Token::Comma {
+                            break;
+                        }
+                        self.advance();
+                    }
+                }
+                self.expect(&Token::RParen)?;
+                self.depth -= 1;
+                ExprKind::Call { name, args }
+            }
+            Token::Ident(name) if *self.peek_next() == Token::LBracket => {
+                self.enter_nesting()?;
+                self.advance(); // consume name
+                self.advance(); // consume '['
+                let index = self.parse_expr()?;
+                self.expect(&Token::RBracket)?;
+                self.depth -= 1;
+                ExprKind::Index {
+                    name,
+                    index: Box::new(index),
+                }
             }
             Token::Ident(name) => {
                 self.advance();
-                Ok(Expr::Var(name))
+                ExprKind::Var(name)
             }
             Token::LParen => {
-                self.depth += 1;
-                if self.depth > MAX_DEPTH {
-                    return Err(format!(
-                        "{}:{}: expression is too deeply nested (limit is {})",
-                        line, col, MAX_DEPTH
-                    ));
-                }
+                self.enter_nesting()?;
                 self.advance();
                 let expr = self.parse_expr()?;
                 self.expect(&Token::RParen)?;
                 self.depth -= 1;
-                Ok(expr)
+                return Ok(expr);
             }
-            _ => Err(format!(
-                "{}:{}: expected expression, found {:?}",
-                line,
-                col,
-                self.peek()
-            )),
+            _ => {
+                return Err(error(
+                    "E0201",
+                    span,
+                    format!("expected expression, found {}", self.peek()),
+                ));
+            }
+        };
+        Ok(Expr::new(kind, self.span_from(start)))
+    }
+}
+
+/// Whether `token` is a keyword that starts a statement (or a function),
+/// where parsing can resume after an error.
+fn starts_stmt(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Let
+            | Token::Const
+            | Token::Print
+            | Token::Write
+            | Token::If
+            | Token::While
+            | Token::Break
+            | Token::Continue
+            | Token::Return
+            | Token::Fn
+    )
+}
+
+/// An error of the parser at `span`.
+fn error(code: &'static str, span: Span, message: String) -> Diagnostic {
+    Diagnostic::error(Phase::Parser, code, message).with_label(span, "")
+}
+
+/// Split a format string, given as the source text of a string literal, into
+/// literal text and `{...}` placeholders. `{{` and `}}` stand for literal
+/// braces. `start` is the offset of `raw` in the source, for error messages.
+fn parse_format(raw: &str, start: usize) -> Result<Vec<FormatPiece>, Diagnostic> {
+    // The bytes `pos` to `end` of `raw`
+    let span = |pos: usize, end: usize| Span::new(start + pos, start + end);
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut pos = 0;
+    while pos < raw.len() {
+        let rest = &raw[pos..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            text.push_str(&rest[..1]);
+            pos += 2;
+        } else if rest.starts_with('{') {
+            let end = rest.find('}').ok_or_else(|| {
+                error(
+                    "E0208",
+                    span(pos, pos + 1),
+                    "unmatched '{' in format string".to_string(),
+                )
+            })?;
+            let spec = parse_format_spec(&rest[1..end]).ok_or_else(|| {
+                error(
+                    "E0208",
+                    span(pos, pos + end + 1),
+                    format!("unknown format specifier '{}'", &rest[..=end]),
+                )
+            })?;
+            if !text.is_empty() {
+                pieces.push(FormatPiece::Text(std::mem::take(&mut text)));
+            }
+            pieces.push(FormatPiece::Arg(spec));
+            pos += end + 1;
+        } else if rest.starts_with('}') {
+            return Err(error(
+                "E0208",
+                span(pos, pos + 1),
+                "unmatched '}' in format string".to_string(),
+            ));
+        } else {
+            let (c, len) = decode_string_char(rest);
+            text.push(c);
+            pos += len;
         }
     }
+    if !text.is_empty() || pieces.is_empty() {
+        pieces.push(FormatPiece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// Parse what is between the braces of a placeholder: nothing, or `:`
+/// followed by an optional `0` flag, an optional width and an optional
+/// radix (`x`, `X` or `b`). Widths are limited to 65535.
+fn parse_format_spec(spec: &str) -> Option<FormatSpec> {
+    if spec.is_empty() {
+        return Some(FormatSpec::default());
+    }
+    let spec = spec.strip_prefix(':')?;
+    let (spec, radix) = match spec.chars().last() {
+        Some('x') => (&spec[..spec.len() - 1], FormatRadix::LowerHex),
+        Some('X') => (&spec[..spec.len() - 1], FormatRadix::UpperHex),
+        Some('b') => (&spec[..spec.len() - 1], FormatRadix::Binary),
+        _ => (spec, FormatRadix::Decimal),
+    };
+    let (zero_pad, width) = match spec.strip_prefix('0') {
+        Some(width) => (true, width),
+        None => (false, spec),
+    };
+    let width = if width.is_empty() {
+        0
+    } else if width.bytes().all(|b| b.is_ascii_digit()) {
+        width.parse::<u16>().ok()? as usize
+    } else {
+        return None;
+    };
+    Some(FormatSpec {
+        radix,
+        width,
+        zero_pad,
+    })
 }
@@ -0,0 +1,61 @@
+/// A range of bytes of the source file, from `start` up to but not
+/// including `end`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+}
+
+/// The text of a source file, indexed by line, for turning the byte offsets
+/// of spans into the line and column numbers that messages show.
+pub struct SourceMap {
+    text: String,
+    /// Byte offset of the first character of each line.
+    line_starts: Vec<usize>,
+}
+
+impl SourceMap {
+    pub fn new(text: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(pos, _)| pos + 1))
+            .collect();
+        SourceMap {
+            text: text.to_string(),
+            line_starts,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The 1-based line and column of the byte at `offset`. Like the
+    /// lexer's, columns count characters, not bytes.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let col = self.text[line_start..offset].chars().count() + 1;
+        (line, col)
+    }
+
+    /// The text of the 1-based line `line`, without its line ending.
+    pub fn line_text(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.text.len(), |next| next - 1);
+        self.text[start..end].trim_end_matches('\r')
+    }
+
+    /// Byte offset of the start of the 1-based line `line`.
+    pub fn line_start(&self, line: usize) -> usize {
+        self.line_starts[line - 1]
+    }
+}
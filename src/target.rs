@@ -0,0 +1,214 @@
+mod aarch64;
+mod x86_64;
+
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+use crate::ast::BinOp;
+
+pub use aarch64::{AArch64, Os};
+pub use x86_64::X86_64;
+
+/// A code generation target: instruction set, object format and toolchain.
+///
+/// `Codegen` walks the AST and asks the target to emit each operation. All
+/// targets share one evaluation model: an expression leaves its result in
+/// the *primary* register (x0 / %rax), binary operators push the left
+/// operand and pop it into the *secondary* register (x1 / %rcx), and
+/// variables live in 8-byte slots at negative offsets from the frame pointer.
+/// Element `i` of an array whose first element is at `offset` lives at
+/// `offset + 8 * i`.
+pub trait Target {
+    /// The target triple, e.g. `aarch64-apple-darwin`.
+    fn triple(&self) -> &'static str;
+
+    /// Mangle a symbol name for the object format (`main` -> `_main` on Mach-O).
+    fn symbol(&self, name: &str) -> String;
+
+    /// Name of the `n`th assembler-local label, which does not appear in the
+    /// object file's symbol table.
+    fn local_label(&self, n: usize) -> String;
+
+    /// Directive that switches to the writable data section.
+    fn data_section(&self) -> &'static str;
+
+    /// Directive that switches to the code section.
+    fn text_section(&self) -> &'static str;
+
+    /// Directives emitted at the very end of the file.
+    fn emit_file_end(&self, _out: &mut String) {}
+
+    /// Emit the label and visibility directives that start a global function.
+    fn emit_function_label(&self, out: &mut String, name: &str);
+
+    /// Set up the frame pointer and reserve `vars_size` bytes of variable
+    /// slots below it. `vars_size` is a multiple of 16.
+    fn emit_prologue(&self, out: &mut String, vars_size: i64);
+
+    /// Tear down the frame set up by `emit_prologue` and return.
+    fn emit_epilogue(&self, out: &mut String, vars_size: i64);
+
+    /// Load a 64-bit constant into the primary register.
+    fn emit_load_immediate(&self, out: &mut String, val: i64);
+
+    /// Load the variable slot at `offset` from the frame pointer.
+    fn emit_load_local(&self, out: &mut String, offset: i64);
+
+    /// Store the primary register into the slot at `offset`.
+    fn emit_store_local(&self, out: &mut String, offset: i64);
+
+    /// Store argument register `index` (0-based) into the slot at `offset`.
+    fn emit_store_param(&self, out: &mut String, index: usize, offset: i64);
+
+    /// Store the primary register into each of the `len` elements of the
+    /// array at `offset`.
+    fn emit_fill_array(&self, out: &mut String, offset: i64, len: usize);
+
+    /// Load the element of the array at `offset` whose index is in the
+    /// primary register.
+    fn emit_load_element(&self, out: &mut String, offset: i64);
+
+    /// Store the primary register into the element of the array at `offset`
+    /// whose index is in the secondary register.
+    fn emit_store_element(&self, out: &mut String, offset: i64);
+
+    /// Define a local label at the current position.
+    fn emit_label(&self, out: &mut String, label: &str) {
+        writeln!(out, "{label}:").unwrap();
+    }
+
+    /// Jump unconditionally to `label`.
+    fn emit_jump(&self, out: &mut String, label: &str);
+
+    /// Jump to `label` if the primary register is zero.
+    fn emit_branch_if_zero(&self, out: &mut String, label: &str);
+
+    /// Jump to `label` if the primary register is not zero.
+    fn emit_branch_if_nonzero(&self, out: &mut String, label: &str);
+
+    /// Jump to `label` if the primary register, taken as unsigned, is less
+    /// than `bound`. A negative value is never below the bound.
+    fn emit_branch_if_below(&self, out: &mut String, bound: usize, label: &str);
+
+    /// Push the primary register onto the stack (16-byte slot).
+    fn emit_push(&self, out: &mut String);
+
+    /// Load the stack word `offset` bytes above the stack pointer into the
+    /// primary register, without popping it.
+    fn emit_load_stack(&self, out: &mut String, offset: i64);
+
+    /// Discard `bytes` bytes of pushed values (a multiple of 16).
+    fn emit_stack_free(&self, out: &mut String, bytes: i64);
+
+    /// Pop the top of the stack into the secondary register.
+    fn emit_pop_secondary(&self, out: &mut String);
+
+    /// Pop the top of the stack into argument register `index` (0-based).
+    fn emit_pop_arg(&self, out: &mut String, index: usize);
+
+    /// Call the function `symbol`; its result is left in the primary register.
+    fn emit_call(&self, out: &mut String, symbol: &str);
+
+    /// Negate the primary register (wrapping).
+    fn emit_neg(&self, out: &mut String);
+
+    /// Replace the primary register with 1 if it is zero, otherwise 0.
+    fn emit_not(&self, out: &mut String);
+
+    /// Invert every bit of the primary register.
+    fn emit_bit_not(&self, out: &mut String);
+
+    /// Compute `secondary <op> primary` into the primary register. `signed`
+    /// selects signed or unsigned division, right shift and ordering.
+    fn emit_binop(&self, out: &mut String, op: BinOp, signed: bool);
+
+    /// Wrap the primary register to a `bits`-bit integer: keep its low
+    /// `bits` bits and sign-extend (`signed`) or zero-extend them. `bits`
+    /// is 8, 16 or 32.
+    fn emit_wrap(&self, out: &mut String, bits: u32, signed: bool);
+
+    /// Negate the `f64` in the primary register.
+    fn emit_float_neg(&self, out: &mut String);
+
+    /// Compute `secondary <op> primary` on `f64`s into the primary register:
+    /// `+`, `-`, `*`, `/` or a comparison, which is false when either
+    /// operand is NaN (except `!=`, which is true).
+    fn emit_float_binop(&self, out: &mut String, op: BinOp);
+
+    /// Convert the integer in the primary register to the nearest `f64`,
+    /// treating it as signed or unsigned.
+    fn emit_int_to_float(&self, out: &mut String, signed: bool);
+
+    /// Convert the `f64` in the primary register to an `i64`, or to a `u64`
+    /// if not `signed`, rounding toward zero. Values beyond the range of the
+    /// type saturate to its minimum or maximum, and NaN converts to 0.
+    fn emit_float_to_int(&self, out: &mut String, signed: bool);
+
+    /// Call `printf` with the format string at `fmt` and the primary register
+    /// (an integer or a string address) as its single variadic argument.
+    fn emit_print(&self, out: &mut String, fmt: &str);
+
+    /// Call `printf` with the format string at `fmt` and the `f64` in the
+    /// primary register as its single variadic argument.
+    fn emit_print_float(&self, out: &mut String, fmt: &str);
+
+    /// Call `scanf` with the format string at `fmt` and, as its single
+    /// variadic argument, the address of the stack slot on top of the stack
+    /// (pushed with `emit_push`). Its return value, the number of values
+    /// read or -1 at the end of the input, is left in the primary register.
+    fn emit_scan(&self, out: &mut String, fmt: &str);
+
+    /// Print the format string at `fmt`, with the primary register as its
+    /// single argument, to stderr, then exit with status 1.
+    fn emit_runtime_error(&self, out: &mut String, fmt: &str);
+
+    /// The command that assembles `asm` into the object file `obj`.
+    fn assemble_command(&self, asm: &Path, obj: &Path) -> Command;
+
+    /// The command that links `obj` against the C runtime into `exe`.
+    fn link_command(&self, obj: &Path, exe: &Path) -> Command;
+}
+
+/// Triples accepted by `--target`.
+pub const SUPPORTED_TRIPLES: &[&str] = &[
+    "aarch64-apple-darwin",
+    "aarch64-unknown-linux-gnu",
+    "x86_64-unknown-linux-gnu",
+];
+
+/// Look up a target by its triple.
+pub fn from_triple(triple: &str) -> Result<Box<dyn Target>, String> {
+    match triple {
+        "aarch64-apple-darwin" => Ok(Box::new(AArch64 { os: Os::Darwin })),
+        "aarch64-unknown-linux-gnu" => Ok(Box::new(AArch64 { os: Os::Linux })),
+        "x86_64-unknown-linux-gnu" => Ok(Box::new(X86_64)),
+        _ => Err(format!(
+            "unsupported target '{}' (supported: {})",
+            triple,
+            SUPPORTED_TRIPLES.join(", ")
+        )),
+    }
+}
+
+/// The triple of the machine the compiler is running on. Hosts we have no
+/// backend for fall back to macOS on Apple Silicon, the original target.
+pub fn host_triple() -> &'static str {
+    if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        "x86_64-unknown-linux-gnu"
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        "aarch64-unknown-linux-gnu"
+    } else {
+        "aarch64-apple-darwin"
+    }
+}
+
+/// Name a cross tool: the plain system tool when compiling for the host,
+/// otherwise the conventional `<prefix>-<tool>` binary of a cross toolchain.
+fn tool(triple: &str, cross_prefix: &str, native: &str, cross: &str) -> Command {
+    if triple == host_triple() {
+        Command::new(native)
+    } else {
+        Command::new(format!("{cross_prefix}-{cross}"))
+    }
+}
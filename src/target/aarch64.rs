@@ -0,0 +1,455 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+use super::{Target, host_triple, tool};
+use crate::ast::BinOp;
+
+/// Operating systems supported by the AArch64 backend. They share the
+/// instruction set but differ in object format and variadic calling convention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Os {
+    /// macOS: Mach-O, `_`-prefixed symbols, variadic arguments on the stack.
+    Darwin,
+    /// Linux: ELF, plain symbols, variadic arguments in registers (AAPCS64).
+    Linux,
+}
+
+/// AArch64 (ARM64) code generation. Primary register x0, secondary x1.
+pub struct AArch64 {
+    pub os: Os,
+}
+
+impl AArch64 {
+    /// Emit `dst = src - val` for `val` below 2^24. `sub` takes a 12-bit
+    /// immediate, optionally shifted left by 12, so larger values need two.
+    fn gen_sub_immediate(&self, out: &mut String, dst: &str, src: &str, val: i64) {
+        if val < 4096 {
+            writeln!(out, "    sub {dst}, {src}, #{val}").unwrap();
+            return;
+        }
+        writeln!(out, "    sub {dst}, {src}, #{}, lsl #12", val >> 12).unwrap();
+        if val & 0xFFF != 0 {
+            writeln!(out, "    sub {dst}, {dst}, #{}", val & 0xFFF).unwrap();
+        }
+    }
+
+    fn gen_load_address(&self, out: &mut String, reg: &str, label: &str) {
+        // Use adrp + add to form a PC-relative address
+        match self.os {
+            Os::Darwin => {
+                writeln!(out, "    adrp {reg}, {label}@PAGE").unwrap();
+                writeln!(out, "    add {reg}, {reg}, {label}@PAGEOFF").unwrap();
+            }
+            Os::Linux => {
+                writeln!(out, "    adrp {reg}, {label}").unwrap();
+                writeln!(out, "    add {reg}, {reg}, :lo12:{label}").unwrap();
+            }
+        }
+    }
+}
+
+impl Target for AArch64 {
+    fn triple(&self) -> &'static str {
+        match self.os {
+            Os::Darwin => "aarch64-apple-darwin",
+            Os::Linux => "aarch64-unknown-linux-gnu",
+        }
+    }
+
+    fn symbol(&self, name: &str) -> String {
+        match self.os {
+            Os::Darwin => format!("_{name}"),
+            Os::Linux => name.to_string(),
+        }
+    }
+
+    fn local_label(&self, n: usize) -> String {
+        match self.os {
+            Os::Darwin => format!("L{n}"),
+            Os::Linux => format!(".L{n}"),
+        }
+    }
+
+    fn data_section(&self) -> &'static str {
+        match self.os {
+            Os::Darwin => ".section __DATA,__data",
+            Os::Linux => ".section .data",
+        }
+    }
+
+    fn text_section(&self) -> &'static str {
+        match self.os {
+            Os::Darwin => ".section __TEXT,__text",
+            Os::Linux => ".section .text",
+        }
+    }
+
+    fn emit_file_end(&self, out: &mut String) {
+        if self.os == Os::Linux {
+            // Mark the stack as non-executable so the linker does not warn.
+            writeln!(out).unwrap();
+            writeln!(out, ".section .note.GNU-stack,\"\",@progbits").unwrap();
+        }
+    }
+
+    fn emit_function_label(&self, out: &mut String, name: &str) {
+        writeln!(out, ".globl {name}").unwrap();
+        writeln!(out, ".p2align 2").unwrap();
+        writeln!(out, "{name}:").unwrap();
+    }
+
+    fn emit_prologue(&self, out: &mut String, vars_size: i64) {
+        // Save frame pointer and link register, then allocate the variables.
+        // Frame layout (high to low):
+        //   [x29+8]  = saved x30 (link register)
+        //   [x29]    = saved x29 (frame pointer)
+        //   [x29-8]  = variable 0
+        //   [x29-16] = variable 1
+        //   ...
+        //   [sp]     = bottom of frame
+        writeln!(out, "    stp x29, x30, [sp, #-16]!").unwrap();
+        writeln!(out, "    mov x29, sp").unwrap();
+        if vars_size > 0 {
+            self.gen_sub_immediate(out, "sp", "sp", vars_size);
+        }
+    }
+
+    fn emit_epilogue(&self, out: &mut String, _vars_size: i64) {
+        writeln!(out, "    mov sp, x29").unwrap();
+        writeln!(out, "    ldp x29, x30, [sp], #16").unwrap();
+        writeln!(out, "    ret").unwrap();
+    }
+
+    fn emit_load_immediate(&self, out: &mut String, val: i64) {
+        if (0..65536).contains(&val) {
+            writeln!(out, "    mov x0, #{}", val).unwrap();
+        } else if (-65536..0).contains(&val) {
+            // movn loads the bitwise NOT of the shifted immediate.
+            // To load a negative value v, we use movn with the NOT of v.
+            let not_val = !val as u64;
+            writeln!(out, "    movn x0, #{}", not_val & 0xFFFF).unwrap();
+        } else {
+            // For arbitrary 64-bit values, use movz + movk sequence.
+            let uval = val as u64;
+            writeln!(out, "    movz x0, #{}", uval & 0xFFFF).unwrap();
+            if (uval >> 16) & 0xFFFF != 0 {
+                writeln!(out, "    movk x0, #{}, lsl #16", (uval >> 16) & 0xFFFF).unwrap();
+            }
+            if (uval >> 32) & 0xFFFF != 0 {
+                writeln!(out, "    movk x0, #{}, lsl #32", (uval >> 32) & 0xFFFF).unwrap();
+            }
+            if (uval >> 48) & 0xFFFF != 0 {
+                writeln!(out, "    movk x0, #{}, lsl #48", (uval >> 48) & 0xFFFF).unwrap();
+            }
+        }
+    }
+
+    fn emit_load_local(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    ldr x0, [x29, #{}]", offset).unwrap();
+    }
+
+    fn emit_store_local(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    str x0, [x29, #{}]", offset).unwrap();
+    }
+
+    fn emit_store_param(&self, out: &mut String, index: usize, offset: i64) {
+        // AAPCS64 passes the first eight integer arguments in x0-x7.
+        writeln!(out, "    str x{index}, [x29, #{}]", offset).unwrap();
+    }
+
+    fn emit_fill_array(&self, out: &mut String, offset: i64, len: usize) {
+        // x1 walks the elements, x2 counts down the ones left
+        self.gen_sub_immediate(out, "x1", "x29", -offset);
+        writeln!(out, "    mov x2, #{len}").unwrap();
+        writeln!(out, "1:").unwrap();
+        writeln!(out, "    cbz x2, 2f").unwrap();
+        writeln!(out, "    str x0, [x1], #8").unwrap();
+        writeln!(out, "    sub x2, x2, #1").unwrap();
+        writeln!(out, "    b 1b").unwrap();
+        writeln!(out, "2:").unwrap();
+    }
+
+    fn emit_load_element(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    add x0, x29, x0, lsl #3").unwrap();
+        self.gen_sub_immediate(out, "x0", "x0", -offset);
+        writeln!(out, "    ldr x0, [x0]").unwrap();
+    }
+
+    fn emit_store_element(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    add x1, x29, x1, lsl #3").unwrap();
+        self.gen_sub_immediate(out, "x1", "x1", -offset);
+        writeln!(out, "    str x0, [x1]").unwrap();
+    }
+
+    fn emit_jump(&self, out: &mut String, label: &str) {
+        writeln!(out, "    b {label}").unwrap();
+    }
+
+    fn emit_branch_if_zero(&self, out: &mut String, label: &str) {
+        writeln!(out, "    cbz x0, {label}").unwrap();
+    }
+
+    fn emit_branch_if_nonzero(&self, out: &mut String, label: &str) {
+        writeln!(out, "    cbnz x0, {label}").unwrap();
+    }
+
+    fn emit_branch_if_below(&self, out: &mut String, bound: usize, label: &str) {
+        writeln!(out, "    mov x1, #{bound}").unwrap();
+        writeln!(out, "    cmp x0, x1").unwrap();
+        writeln!(out, "    b.lo {label}").unwrap();
+    }
+
+    fn emit_push(&self, out: &mut String) {
+        writeln!(out, "    str x0, [sp, #-16]!").unwrap();
+    }
+
+    fn emit_load_stack(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    ldr x0, [sp, #{offset}]").unwrap();
+    }
+
+    fn emit_stack_free(&self, out: &mut String, bytes: i64) {
+        writeln!(out, "    add sp, sp, #{bytes}").unwrap();
+    }
+
+    fn emit_pop_secondary(&self, out: &mut String) {
+        writeln!(out, "    ldr x1, [sp], #16").unwrap();
+    }
+
+    fn emit_pop_arg(&self, out: &mut String, index: usize) {
+        writeln!(out, "    ldr x{index}, [sp], #16").unwrap();
+    }
+
+    fn emit_call(&self, out: &mut String, symbol: &str) {
+        writeln!(out, "    bl {symbol}").unwrap();
+    }
+
+    fn emit_neg(&self, out: &mut String) {
+        writeln!(out, "    neg x0, x0").unwrap();
+    }
+
+    fn emit_not(&self, out: &mut String) {
+        writeln!(out, "    cmp x0, #0").unwrap();
+        writeln!(out, "    cset x0, eq").unwrap();
+    }
+
+    fn emit_bit_not(&self, out: &mut String) {
+        writeln!(out, "    mvn x0, x0").unwrap();
+    }
+
+    fn emit_binop(&self, out: &mut String, op: BinOp, signed: bool) {
+        // x1 = left, x0 = right
+        match op {
+            BinOp::Add => {
+                writeln!(out, "    add x0, x1, x0").unwrap();
+            }
+            BinOp::Sub => {
+                writeln!(out, "    sub x0, x1, x0").unwrap();
+            }
+            BinOp::Mul => {
+                writeln!(out, "    mul x0, x1, x0").unwrap();
+            }
+            BinOp::Div => {
+                let div = if signed { "sdiv" } else { "udiv" };
+                writeln!(out, "    {div} x0, x1, x0").unwrap();
+            }
+            BinOp::Mod => {
+                // ARM64 has no remainder instruction.
+                // a % b = a - (a / b) * b
+                let div = if signed { "sdiv" } else { "udiv" };
+                writeln!(out, "    {div} x2, x1, x0").unwrap();
+                writeln!(out, "    msub x0, x2, x0, x1").unwrap();
+            }
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                let cond = match (op, signed) {
+                    (BinOp::Eq, _) => "eq",
+                    (BinOp::Ne, _) => "ne",
+                    (BinOp::Lt, true) => "lt",
+                    (BinOp::Le, true) => "le",
+                    (BinOp::Gt, true) => "gt",
+                    (_, true) => "ge",
+                    (BinOp::Lt, false) => "lo",
+                    (BinOp::Le, false) => "ls",
+                    (BinOp::Gt, false) => "hi",
+                    (_, false) => "hs",
+                };
+                writeln!(out, "    cmp x1, x0").unwrap();
+                writeln!(out, "    cset x0, {cond}").unwrap();
+            }
+            BinOp::BitAnd => {
+                writeln!(out, "    and x0, x1, x0").unwrap();
+            }
+            BinOp::BitOr => {
+                writeln!(out, "    orr x0, x1, x0").unwrap();
+            }
+            BinOp::BitXor => {
+                writeln!(out, "    eor x0, x1, x0").unwrap();
+            }
+            // The register forms of the shifts use the count modulo 64.
+            BinOp::Shl => {
+                writeln!(out, "    lsl x0, x1, x0").unwrap();
+            }
+            BinOp::Shr => {
+                let shift = if signed { "asr" } else { "lsr" };
+                writeln!(out, "    {shift} x0, x1, x0").unwrap();
+            }
+        }
+    }
+
+    fn emit_wrap(&self, out: &mut String, bits: u32, signed: bool) {
+        let insn = match (bits, signed) {
+            (8, true) => "sxtb x0, w0",
+            (16, true) => "sxth x0, w0",
+            (32, true) => "sxtw x0, w0",
+            (8, false) => "uxtb w0, w0",
+            (16, false) => "uxth w0, w0",
+            // Writing a w register clears the upper half.
+            _ => "mov w0, w0",
+        };
+        writeln!(out, "    {insn}").unwrap();
+    }
+
+    fn emit_float_neg(&self, out: &mut String) {
+        writeln!(out, "    fmov d0, x0").unwrap();
+        writeln!(out, "    fneg d0, d0").unwrap();
+        writeln!(out, "    fmov x0, d0").unwrap();
+    }
+
+    fn emit_float_binop(&self, out: &mut String, op: BinOp) {
+        // d1 = left, d0 = right
+        writeln!(out, "    fmov d1, x1").unwrap();
+        writeln!(out, "    fmov d0, x0").unwrap();
+        let insn = match op {
+            BinOp::Add => "fadd",
+            BinOp::Sub => "fsub",
+            BinOp::Mul => "fmul",
+            BinOp::Div => "fdiv",
+            _ => {
+                // An unordered comparison (a NaN operand) sets C and V, so
+                // `<` and `<=` use conditions that are false for it.
+                let cond = match op {
+                    BinOp::Eq => "eq",
+                    BinOp::Ne => "ne",
+                    BinOp::Lt => "mi",
+                    BinOp::Le => "ls",
+                    BinOp::Gt => "gt",
+                    _ => "ge",
+                };
+                writeln!(out, "    fcmp d1, d0").unwrap();
+                writeln!(out, "    cset x0, {cond}").unwrap();
+                return;
+            }
+        };
+        writeln!(out, "    {insn} d0, d1, d0").unwrap();
+        writeln!(out, "    fmov x0, d0").unwrap();
+    }
+
+    fn emit_int_to_float(&self, out: &mut String, signed: bool) {
+        let insn = if signed { "scvtf" } else { "ucvtf" };
+        writeln!(out, "    {insn} d0, x0").unwrap();
+        writeln!(out, "    fmov x0, d0").unwrap();
+    }
+
+    fn emit_float_to_int(&self, out: &mut String, signed: bool) {
+        // fcvtzs and fcvtzu saturate and convert NaN to 0
+        let insn = if signed { "fcvtzs" } else { "fcvtzu" };
+        writeln!(out, "    fmov d0, x0").unwrap();
+        writeln!(out, "    {insn} x0, d0").unwrap();
+    }
+
+    fn emit_print(&self, out: &mut String, fmt: &str) {
+        match self.os {
+            Os::Darwin => {
+                // On ARM64 macOS, variadic arguments to printf are passed on
+                // the stack, not in registers. The format string (named param)
+                // goes in x0. The variadic i64 value goes at [sp].
+                writeln!(out, "    str x0, [sp, #-16]!").unwrap();
+                self.gen_load_address(out, "x0", fmt);
+                writeln!(out, "    bl {}", self.symbol("printf")).unwrap();
+                writeln!(out, "    add sp, sp, #16").unwrap();
+            }
+            Os::Linux => {
+                // Standard AAPCS64 passes variadic arguments in registers
+                // like any other: format string in x0, value in x1.
+                writeln!(out, "    mov x1, x0").unwrap();
+                self.gen_load_address(out, "x0", fmt);
+                writeln!(out, "    bl {}", self.symbol("printf")).unwrap();
+            }
+        }
+    }
+
+    fn emit_print_float(&self, out: &mut String, fmt: &str) {
+        match self.os {
+            // Variadic arguments go on the stack whatever their type
+            Os::Darwin => self.emit_print(out, fmt),
+            Os::Linux => {
+                // A variadic double goes in d0, like a named one.
+                writeln!(out, "    fmov d0, x0").unwrap();
+                self.gen_load_address(out, "x0", fmt);
+                writeln!(out, "    bl {}", self.symbol("printf")).unwrap();
+            }
+        }
+    }
+
+    fn emit_scan(&self, out: &mut String, fmt: &str) {
+        writeln!(out, "    mov x1, sp").unwrap();
+        match self.os {
+            // The address is variadic, so it goes on the stack, below the
+            // slot it points to (see emit_print)
+            Os::Darwin => {
+                writeln!(out, "    str x1, [sp, #-16]!").unwrap();
+                self.gen_load_address(out, "x0", fmt);
+                writeln!(out, "    bl {}", self.symbol("scanf")).unwrap();
+                writeln!(out, "    add sp, sp, #16").unwrap();
+            }
+            Os::Linux => {
+                self.gen_load_address(out, "x0", fmt);
+                writeln!(out, "    bl {}", self.symbol("scanf")).unwrap();
+            }
+        }
+        // scanf returns an int
+        writeln!(out, "    sxtw x0, w0").unwrap();
+    }
+
+    fn emit_runtime_error(&self, out: &mut String, fmt: &str) {
+        // dprintf(2, fmt, value), with the same variadic conventions as
+        // printf (see emit_print), then exit(1), which flushes stdout.
+        match self.os {
+            Os::Darwin => writeln!(out, "    str x0, [sp, #-16]!").unwrap(),
+            Os::Linux => writeln!(out, "    mov x2, x0").unwrap(),
+        }
+        writeln!(out, "    mov x0, #2").unwrap();
+        self.gen_load_address(out, "x1", fmt);
+        writeln!(out, "    bl {}", self.symbol("dprintf")).unwrap();
+        writeln!(out, "    mov x0, #1").unwrap();
+        writeln!(out, "    bl {}", self.symbol("exit")).unwrap();
+    }
+
+    fn assemble_command(&self, asm: &Path, obj: &Path) -> Command {
+        let mut cmd = match self.os {
+            Os::Darwin if self.triple() != host_triple() => {
+                let mut cmd = Command::new("clang");
+                cmd.args(["--target=arm64-apple-darwin", "-c"]);
+                cmd
+            }
+            Os::Darwin => Command::new("as"),
+            Os::Linux => tool(self.triple(), "aarch64-linux-gnu", "as", "as"),
+        };
+        cmd.arg("-o").arg(obj).arg(asm);
+        cmd
+    }
+
+    fn link_command(&self, obj: &Path, exe: &Path) -> Command {
+        let mut cmd = match self.os {
+            Os::Darwin if self.triple() != host_triple() => {
+                let mut cmd = Command::new("clang");
+                cmd.arg("--target=arm64-apple-darwin");
+                cmd
+            }
+            Os::Darwin => Command::new("cc"),
+            Os::Linux => tool(self.triple(), "aarch64-linux-gnu", "cc", "gcc"),
+        };
+        cmd.arg("-o").arg(exe).arg(obj);
+        cmd
+    }
+}
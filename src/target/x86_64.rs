@@ -0,0 +1,442 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+use super::{Target, tool};
+use crate::ast::BinOp;
+
+/// x86-64 Linux code generation (System V ABI, ELF, AT&T syntax).
+/// Primary register %rax, secondary %rcx.
+pub struct X86_64;
+
+/// Integer argument registers of the System V calling convention, in order.
+const ARG_REGS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+
+impl X86_64 {
+    /// Emit a signed division or remainder of %rcx (left) by %rax (right).
+    ///
+    /// `idivq` raises #DE for i64::MIN / -1, but the language defines that
+    /// case to wrap, so a divisor of -1 is handled without dividing:
+    /// `a / -1` is `-a` (wrapping) and `a % -1` is 0.
+    fn gen_divide(&self, out: &mut String, op: BinOp) {
+        writeln!(out, "    xchgq %rax, %rcx").unwrap();
+        // Now: %rax = left (dividend), %rcx = right (divisor)
+        writeln!(out, "    cmpq $-1, %rcx").unwrap();
+        writeln!(out, "    jne 1f").unwrap();
+        if op == BinOp::Div {
+            writeln!(out, "    negq %rax").unwrap();
+        } else {
+            writeln!(out, "    xorl %eax, %eax").unwrap();
+        }
+        writeln!(out, "    jmp 2f").unwrap();
+        writeln!(out, "1:").unwrap();
+        writeln!(out, "    cqto").unwrap();
+        writeln!(out, "    idivq %rcx").unwrap();
+        if op == BinOp::Mod {
+            writeln!(out, "    movq %rdx, %rax").unwrap();
+        }
+        writeln!(out, "2:").unwrap();
+    }
+
+    /// Emit an unsigned division or remainder of %rcx (left) by %rax (right).
+    fn gen_unsigned_divide(&self, out: &mut String, op: BinOp) {
+        writeln!(out, "    xchgq %rax, %rcx").unwrap();
+        writeln!(out, "    xorl %edx, %edx").unwrap();
+        writeln!(out, "    divq %rcx").unwrap();
+        if op == BinOp::Mod {
+            writeln!(out, "    movq %rdx, %rax").unwrap();
+        }
+    }
+
+    /// Convert the `f64` in %rax to a `u64`, saturating like `fcvtzu`.
+    ///
+    /// There is no unsigned conversion: values from 2^63 up are reduced by
+    /// 2^63 before the signed one, and get their top bit back afterwards.
+    fn gen_float_to_unsigned(&self, out: &mut String) {
+        writeln!(out, "    movq %rax, %xmm0").unwrap();
+        // 2^63
+        writeln!(out, "    movabsq $0x43e0000000000000, %rcx").unwrap();
+        writeln!(out, "    movq %rcx, %xmm1").unwrap();
+        writeln!(out, "    ucomisd %xmm1, %xmm0").unwrap();
+        writeln!(out, "    jae 1f").unwrap();
+        // Below 2^63, or NaN: a negative result (from a negative value, an
+        // out-of-range one or NaN) becomes 0
+        writeln!(out, "    cvttsd2siq %xmm0, %rax").unwrap();
+        writeln!(out, "    movq %rax, %rcx").unwrap();
+        writeln!(out, "    sarq $63, %rcx").unwrap();
+        writeln!(out, "    notq %rcx").unwrap();
+        writeln!(out, "    andq %rcx, %rax").unwrap();
+        writeln!(out, "    jmp 2f").unwrap();
+        writeln!(out, "1:").unwrap();
+        // From 2^63 up: cvttsd2si gives i64::MIN if the value was 2^64 or
+        // more, which becomes u64::MAX
+        writeln!(out, "    subsd %xmm1, %xmm0").unwrap();
+        writeln!(out, "    cvttsd2siq %xmm0, %rax").unwrap();
+        writeln!(out, "    movq %rax, %rcx").unwrap();
+        writeln!(out, "    sarq $63, %rcx").unwrap();
+        writeln!(out, "    btcq $63, %rax").unwrap();
+        writeln!(out, "    orq %rcx, %rax").unwrap();
+        writeln!(out, "2:").unwrap();
+    }
+}
+
+impl Target for X86_64 {
+    fn triple(&self) -> &'static str {
+        "x86_64-unknown-linux-gnu"
+    }
+
+    fn symbol(&self, name: &str) -> String {
+        name.to_string()
+    }
+
+    fn local_label(&self, n: usize) -> String {
+        format!(".L{n}")
+    }
+
+    fn data_section(&self) -> &'static str {
+        ".section .data"
+    }
+
+    fn text_section(&self) -> &'static str {
+        ".section .text"
+    }
+
+    fn emit_file_end(&self, out: &mut String) {
+        // Mark the stack as non-executable so the linker does not warn.
+        writeln!(out).unwrap();
+        writeln!(out, ".section .note.GNU-stack,\"\",@progbits").unwrap();
+    }
+
+    fn emit_function_label(&self, out: &mut String, name: &str) {
+        writeln!(out, ".globl {name}").unwrap();
+        writeln!(out, "{name}:").unwrap();
+    }
+
+    fn emit_prologue(&self, out: &mut String, vars_size: i64) {
+        // Save the caller's frame pointer and allocate the frame.
+        // On entry %rsp is 8 mod 16; pushing %rbp realigns it to 16, so the
+        // variable area only needs to be a multiple of 16 to keep calls aligned.
+        // Frame layout (high to low):
+        //   [rbp+8]  = return address
+        //   [rbp]    = saved rbp
+        //   [rbp-8]  = variable 0
+        //   [rbp-16] = variable 1
+        //   ...
+        //   [rsp]    = bottom of frame
+        writeln!(out, "    pushq %rbp").unwrap();
+        writeln!(out, "    movq %rsp, %rbp").unwrap();
+        if vars_size > 0 {
+            writeln!(out, "    subq ${vars_size}, %rsp").unwrap();
+        }
+    }
+
+    fn emit_epilogue(&self, out: &mut String, _vars_size: i64) {
+        writeln!(out, "    movq %rbp, %rsp").unwrap();
+        writeln!(out, "    popq %rbp").unwrap();
+        writeln!(out, "    ret").unwrap();
+    }
+
+    fn emit_load_immediate(&self, out: &mut String, val: i64) {
+        if i32::try_from(val).is_ok() {
+            // movq sign-extends a 32-bit immediate.
+            writeln!(out, "    movq ${}, %rax", val).unwrap();
+        } else {
+            writeln!(out, "    movabsq ${}, %rax", val).unwrap();
+        }
+    }
+
+    fn emit_load_local(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    movq {}(%rbp), %rax", offset).unwrap();
+    }
+
+    fn emit_store_local(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    movq %rax, {}(%rbp)", offset).unwrap();
+    }
+
+    fn emit_store_param(&self, out: &mut String, index: usize, offset: i64) {
+        writeln!(out, "    movq {}, {}(%rbp)", ARG_REGS[index], offset).unwrap();
+    }
+
+    fn emit_fill_array(&self, out: &mut String, offset: i64, len: usize) {
+        // rep stosq stores %rax at (%rdi), %rcx times, moving %rdi forward
+        writeln!(out, "    leaq {offset}(%rbp), %rdi").unwrap();
+        writeln!(out, "    movq ${len}, %rcx").unwrap();
+        writeln!(out, "    rep stosq").unwrap();
+    }
+
+    fn emit_load_element(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    movq {offset}(%rbp,%rax,8), %rax").unwrap();
+    }
+
+    fn emit_store_element(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    movq %rax, {offset}(%rbp,%rcx,8)").unwrap();
+    }
+
+    fn emit_jump(&self, out: &mut String, label: &str) {
+        writeln!(out, "    jmp {label}").unwrap();
+    }
+
+    fn emit_branch_if_zero(&self, out: &mut String, label: &str) {
+        writeln!(out, "    testq %rax, %rax").unwrap();
+        writeln!(out, "    jz {label}").unwrap();
+    }
+
+    fn emit_branch_if_nonzero(&self, out: &mut String, label: &str) {
+        writeln!(out, "    testq %rax, %rax").unwrap();
+        writeln!(out, "    jnz {label}").unwrap();
+    }
+
+    fn emit_branch_if_below(&self, out: &mut String, bound: usize, label: &str) {
+        writeln!(out, "    cmpq ${bound}, %rax").unwrap();
+        writeln!(out, "    jb {label}").unwrap();
+    }
+
+    fn emit_push(&self, out: &mut String) {
+        // 16-byte slots keep %rsp aligned for calls, as on AArch64.
+        writeln!(out, "    subq $16, %rsp").unwrap();
+        writeln!(out, "    movq %rax, (%rsp)").unwrap();
+    }
+
+    fn emit_load_stack(&self, out: &mut String, offset: i64) {
+        writeln!(out, "    movq {offset}(%rsp), %rax").unwrap();
+    }
+
+    fn emit_stack_free(&self, out: &mut String, bytes: i64) {
+        writeln!(out, "    addq ${bytes}, %rsp").unwrap();
+    }
+
+    fn emit_pop_secondary(&self, out: &mut String) {
+        writeln!(out, "    movq (%rsp), %rcx").unwrap();
+        writeln!(out, "    addq $16, %rsp").unwrap();
+    }
+
+    fn emit_pop_arg(&self, out: &mut String, index: usize) {
+        writeln!(out, "    movq (%rsp), {}", ARG_REGS[index]).unwrap();
+        writeln!(out, "    addq $16, %rsp").unwrap();
+    }
+
+    fn emit_call(&self, out: &mut String, symbol: &str) {
+        writeln!(out, "    call {symbol}").unwrap();
+    }
+
+    fn emit_neg(&self, out: &mut String) {
+        writeln!(out, "    negq %rax").unwrap();
+    }
+
+    fn emit_not(&self, out: &mut String) {
+        writeln!(out, "    testq %rax, %rax").unwrap();
+        writeln!(out, "    sete %al").unwrap();
+        writeln!(out, "    movzbq %al, %rax").unwrap();
+    }
+
+    fn emit_bit_not(&self, out: &mut String) {
+        writeln!(out, "    notq %rax").unwrap();
+    }
+
+    fn emit_binop(&self, out: &mut String, op: BinOp, signed: bool) {
+        // %rcx = left, %rax = right
+        match op {
+            BinOp::Add => {
+                writeln!(out, "    addq %rcx, %rax").unwrap();
+            }
+            BinOp::Sub => {
+                writeln!(out, "    subq %rax, %rcx").unwrap();
+                writeln!(out, "    movq %rcx, %rax").unwrap();
+            }
+            BinOp::Mul => {
+                writeln!(out, "    imulq %rcx, %rax").unwrap();
+            }
+            BinOp::Div | BinOp::Mod if signed => self.gen_divide(out, op),
+            BinOp::Div | BinOp::Mod => self.gen_unsigned_divide(out, op),
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                let cond = match (op, signed) {
+                    (BinOp::Eq, _) => "e",
+                    (BinOp::Ne, _) => "ne",
+                    (BinOp::Lt, true) => "l",
+                    (BinOp::Le, true) => "le",
+                    (BinOp::Gt, true) => "g",
+                    (_, true) => "ge",
+                    (BinOp::Lt, false) => "b",
+                    (BinOp::Le, false) => "be",
+                    (BinOp::Gt, false) => "a",
+                    (_, false) => "ae",
+                };
+                // AT&T operand order: this sets flags for %rcx - %rax.
+                writeln!(out, "    cmpq %rax, %rcx").unwrap();
+                writeln!(out, "    set{cond} %al").unwrap();
+                writeln!(out, "    movzbq %al, %rax").unwrap();
+            }
+            BinOp::BitAnd => {
+                writeln!(out, "    andq %rcx, %rax").unwrap();
+            }
+            BinOp::BitOr => {
+                writeln!(out, "    orq %rcx, %rax").unwrap();
+            }
+            BinOp::BitXor => {
+                writeln!(out, "    xorq %rcx, %rax").unwrap();
+            }
+            BinOp::Shl | BinOp::Shr => {
+                // The count must be in %cl; the shifts use it modulo 64.
+                let insn = match op {
+                    BinOp::Shl => "shlq",
+                    _ if signed => "sarq",
+                    _ => "shrq",
+                };
+                writeln!(out, "    xchgq %rax, %rcx").unwrap();
+                writeln!(out, "    {insn} %cl, %rax").unwrap();
+            }
+        }
+    }
+
+    fn emit_wrap(&self, out: &mut String, bits: u32, signed: bool) {
+        let insn = match (bits, signed) {
+            (8, true) => "movsbq %al, %rax",
+            (16, true) => "movswq %ax, %rax",
+            (32, true) => "movslq %eax, %rax",
+            (8, false) => "movzbq %al, %rax",
+            (16, false) => "movzwq %ax, %rax",
+            // Writing a 32-bit register clears the upper half.
+            _ => "movl %eax, %eax",
+        };
+        writeln!(out, "    {insn}").unwrap();
+    }
+
+    fn emit_float_neg(&self, out: &mut String) {
+        writeln!(out, "    btcq $63, %rax").unwrap();
+    }
+
+    fn emit_float_binop(&self, out: &mut String, op: BinOp) {
+        // %xmm1 = left, %xmm0 = right
+        writeln!(out, "    movq %rcx, %xmm1").unwrap();
+        writeln!(out, "    movq %rax, %xmm0").unwrap();
+        let insn = match op {
+            BinOp::Add => "addsd",
+            BinOp::Sub => "subsd",
+            BinOp::Mul => "mulsd",
+            BinOp::Div => "divsd",
+            _ => {
+                // ucomisd sets ZF, PF and CF for an unordered comparison (a
+                // NaN operand), so `<` and `<=` swap the operands to use the
+                // "above" conditions, which are false for it, and `==` and
+                // `!=` check PF.
+                match op {
+                    BinOp::Lt | BinOp::Le => writeln!(out, "    ucomisd %xmm1, %xmm0").unwrap(),
+                    _ => writeln!(out, "    ucomisd %xmm0, %xmm1").unwrap(),
+                }
+                match op {
+                    BinOp::Eq => {
+                        writeln!(out, "    sete %al").unwrap();
+                        writeln!(out, "    setnp %cl").unwrap();
+                        writeln!(out, "    andb %cl, %al").unwrap();
+                    }
+                    BinOp::Ne => {
+                        writeln!(out, "    setne %al").unwrap();
+                        writeln!(out, "    setp %cl").unwrap();
+                        writeln!(out, "    orb %cl, %al").unwrap();
+                    }
+                    BinOp::Lt | BinOp::Gt => writeln!(out, "    seta %al").unwrap(),
+                    _ => writeln!(out, "    setae %al").unwrap(),
+                }
+                writeln!(out, "    movzbq %al, %rax").unwrap();
+                return;
+            }
+        };
+        writeln!(out, "    {insn} %xmm0, %xmm1").unwrap();
+        writeln!(out, "    movq %xmm1, %rax").unwrap();
+    }
+
+    fn emit_int_to_float(&self, out: &mut String, signed: bool) {
+        if signed {
+            writeln!(out, "    cvtsi2sdq %rax, %xmm0").unwrap();
+        } else {
+            // There is no unsigned conversion. A value with the top bit set
+            // is halved, keeping its lowest bit so that it rounds the same,
+            // converted, and doubled.
+            writeln!(out, "    testq %rax, %rax").unwrap();
+            writeln!(out, "    js 1f").unwrap();
+            writeln!(out, "    cvtsi2sdq %rax, %xmm0").unwrap();
+            writeln!(out, "    jmp 2f").unwrap();
+            writeln!(out, "1:").unwrap();
+            writeln!(out, "    movq %rax, %rcx").unwrap();
+            writeln!(out, "    shrq %rcx").unwrap();
+            writeln!(out, "    andl $1, %eax").unwrap();
+            writeln!(out, "    orq %rcx, %rax").unwrap();
+            writeln!(out, "    cvtsi2sdq %rax, %xmm0").unwrap();
+            writeln!(out, "    addsd %xmm0, %xmm0").unwrap();
+            writeln!(out, "2:").unwrap();
+        }
+        writeln!(out, "    movq %xmm0, %rax").unwrap();
+    }
+
+    fn emit_float_to_int(&self, out: &mut String, signed: bool) {
+        if !signed {
+            self.gen_float_to_unsigned(out);
+            return;
+        }
+        // cvttsd2si gives i64::MIN for NaN and for values out of range.
+        // Turn it into i64::MAX (its complement) if the value was positive,
+        // and into 0 for NaN.
+        writeln!(out, "    movq %rax, %xmm0").unwrap();
+        writeln!(out, "    movq %rax, %rcx").unwrap();
+        writeln!(out, "    cvttsd2siq %xmm0, %rax").unwrap();
+        writeln!(out, "    notq %rcx").unwrap();
+        writeln!(out, "    andq %rax, %rcx").unwrap();
+        writeln!(out, "    sarq $63, %rcx").unwrap();
+        writeln!(out, "    xorq %rcx, %rax").unwrap();
+        writeln!(out, "    ucomisd %xmm0, %xmm0").unwrap();
+        writeln!(out, "    jnp 1f").unwrap();
+        writeln!(out, "    xorl %eax, %eax").unwrap();
+        writeln!(out, "1:").unwrap();
+    }
+
+    fn emit_print(&self, out: &mut String, fmt: &str) {
+        // System V passes variadic arguments in registers like any other
+        // argument: format string in %rdi, value in %rsi. %al holds the
+        // number of vector registers used (none).
+        writeln!(out, "    movq %rax, %rsi").unwrap();
+        writeln!(out, "    leaq {fmt}(%rip), %rdi").unwrap();
+        writeln!(out, "    xorl %eax, %eax").unwrap();
+        writeln!(out, "    call printf@PLT").unwrap();
+    }
+
+    fn emit_print_float(&self, out: &mut String, fmt: &str) {
+        // A variadic double goes in %xmm0, and %al counts the vector
+        // registers used.
+        writeln!(out, "    movq %rax, %xmm0").unwrap();
+        writeln!(out, "    leaq {fmt}(%rip), %rdi").unwrap();
+        writeln!(out, "    movl $1, %eax").unwrap();
+        writeln!(out, "    call printf@PLT").unwrap();
+    }
+
+    fn emit_scan(&self, out: &mut String, fmt: &str) {
+        writeln!(out, "    movq %rsp, %rsi").unwrap();
+        writeln!(out, "    leaq {fmt}(%rip), %rdi").unwrap();
+        writeln!(out, "    xorl %eax, %eax").unwrap();
+        writeln!(out, "    call scanf@PLT").unwrap();
+        // scanf returns an int
+        writeln!(out, "    movslq %eax, %rax").unwrap();
+    }
+
+    fn emit_runtime_error(&self, out: &mut String, fmt: &str) {
+        // dprintf(2, fmt, value), then exit(1), which flushes stdout.
+        writeln!(out, "    movq %rax, %rdx").unwrap();
+        writeln!(out, "    movl $2, %edi").unwrap();
+        writeln!(out, "    leaq {fmt}(%rip), %rsi").unwrap();
+        writeln!(out, "    xorl %eax, %eax").unwrap();
+        writeln!(out, "    call dprintf@PLT").unwrap();
+        writeln!(out, "    movl $1, %edi").unwrap();
+        writeln!(out, "    call exit@PLT").unwrap();
+    }
+
+    fn assemble_command(&self, asm: &Path, obj: &Path) -> Command {
+        let mut cmd = tool(self.triple(), "x86_64-linux-gnu", "as", "as");
+        cmd.arg("-o").arg(obj).arg(asm);
+        cmd
+    }
+
+    fn link_command(&self, obj: &Path, exe: &Path) -> Command {
+        let mut cmd = tool(self.triple(), "x86_64-linux-gnu", "cc", "gcc");
+        cmd.arg("-o").arg(exe).arg(obj);
+        cmd
+    }
+}
@@ -0,0 +1,898 @@
+use std::collections::HashMap;
+
+use crate::ast::{
+    BinOp, Block, Expr, ExprKind, FormatPiece, FormatRadix, LogicalOp, Program, Stmt, StmtKind,
+    Type,
+};
+use crate::diagnostic::{Diagnostic, Phase};
+use crate::interp;
+use crate::span::Span;
+
+/// Maximum number of function parameters: the integer argument registers
+/// of the System V x86-64 calling convention (AAPCS64 has eight).
+const MAX_PARAMS: usize = 6;
+
+/// The type of a variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VarType {
+    Scalar(Type),
+    /// A constant of type `ty`, whose value has the bits `value`.
+    Const {
+        ty: Type,
+        value: i64,
+    },
+    /// An array of `len` elements of type `elem`.
+    Array {
+        elem: Type,
+        len: usize,
+    },
+}
+
+/// Semantic analysis, run between parsing and code generation or
+/// interpretation: resolves every name and assigns a type to every
+/// expression, rejecting programs that are not well typed. Code generation
+/// and the interpreter rely on its checks and do not repeat them.
+struct TypeChecker {
+    /// Number of parameters of every function, by name.
+    functions: HashMap<String, usize>,
+    /// The variables in scope, innermost last. A block's variables are
+    /// removed again when it ends; a shadowing `let` simply adds an entry.
+    variables: Vec<(String, VarType)>,
+    /// The variables and constants declared in the function body or
+    /// top-level statements being checked, in source order, with the spans
+    /// of their declarations and of the blocks they are declared in (see
+    /// `declarations`).
+    declarations: Vec<(String, Span, Span)>,
+    /// Names declared by code that failed to parse (see `Program`) or to
+    /// type check.
+    lost_names: Vec<String>,
+    /// Whether the error being built is about the use of one of
+    /// `lost_names`.
+    used_lost_name: bool,
+    /// The errors found so far.
+    errors: Vec<Diagnostic>,
+}
+
+/// Check `program`, setting the type of every expression in it. Returns
+/// the errors found, if any.
+///
+/// Checking does not stop at an error: the statement with the error is
+/// skipped, and checking resumes after it. A program that had syntax errors
+/// is checked as far as it parsed. Uses of a name whose declaration failed
+/// to parse or to type check are not reported: the error in the declaration
+/// already explains them.
+pub fn check(program: &mut Program) -> Vec<Diagnostic> {
+    let mut checker = TypeChecker {
+        functions: HashMap::new(),
+        variables: Vec::new(),
+        declarations: Vec::new(),
+        lost_names: program.lost_names.clone(),
+        used_lost_name: false,
+        errors: Vec::new(),
+    };
+    checker.check_program(program);
+    checker.errors
+}
+
+impl TypeChecker {
+    fn check_program(&mut self, program: &mut Program) {
+        for function in &program.functions {
+            if function.params.len() > MAX_PARAMS {
+                self.report(error(
+                    "E0301",
+                    function.name_span,
+                    format!(
+                        "function '{}' has {} parameters, maximum is {}",
+                        function.name,
+                        function.params.len(),
+                        MAX_PARAMS
+                    ),
+                ));
+            }
+            // Calls are checked against the first definition
+            if self.functions.contains_key(function.name.as_str()) {
+                let first = program
+                    .functions
+                    .iter()
+                    .find(|other| other.name == function.name)
+                    .unwrap();
+                self.report(
+                    error(
+                        "E0302",
+                        function.name_span,
+                        format!("function '{}' is defined more than once", function.name),
+                    )
+                    .with_secondary_label(first.name_span, "first defined here"),
+                );
+            } else {
+                let arity = function.params.len();
+                self.functions.insert(function.name.clone(), arity);
+            }
+        }
+        for function in &mut program.functions {
+            self.variables = function
+                .params
+                .iter()
+                .map(|param| (param.clone(), VarType::Scalar(Type::I64)))
+                .collect();
+            self.declarations = declarations(&function.body.stmts);
+            self.check_stmts(&mut function.body.stmts);
+        }
+        self.variables.clear();
+        self.declarations = declarations(&program.stmts);
+        self.check_stmts(&mut program.stmts);
+    }
+
+    /// Record `diagnostic`, unless it is about the use of one of
+    /// `lost_names`.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        if !std::mem::take(&mut self.used_lost_name) {
+            self.errors.push(diagnostic);
+        }
+    }
+
+    /// Check `stmts`, reporting the error in each statement that has one.
+    fn check_stmts(&mut self, stmts: &mut [Stmt]) {
+        for stmt in stmts {
+            if let Err(diagnostic) = self.check_stmt(stmt) {
+                self.report(diagnostic);
+                // The name is not declared, but its uses are not errors of
+                // their own
+                if let StmtKind::Let { name, .. }
+                | StmtKind::Const { name, .. }
+                | StmtKind::LetArray { name, .. } = &stmt.kind
+                {
+                    self.lost_names.push(name.clone());
+                }
+            }
+        }
+    }
+
+    /// Check `stmt`, returning its first error. The errors in the blocks
+    /// of an `if`, a `while` or a block statement are reported instead.
+    fn check_stmt(&mut self, stmt: &mut Stmt) -> Result<(), Diagnostic> {
+        let span = stmt.span;
+        match &mut stmt.kind {
+            StmtKind::Let { name, ty, expr } => {
+                // The new variable is not in scope in its initializer
+                let ty = match ty {
+                    Some(ty) => self.expect(expr, *ty)?,
+                    None => self.check_expr(expr, None)?,
+                };
+                self.variables.push((name.clone(), VarType::Scalar(ty)));
+            }
+            StmtKind::Const {
+                name,
+                ty,
+                expr,
+                value,
+            } => {
+                let ty = match ty {
+                    Some(ty) => self.expect(expr, *ty)?,
+                    None => self.check_expr(expr, None)?,
+                };
+                let val = self.eval_const(expr)?;
+                *value = Some(val);
+                self.variables
+                    .push((name.clone(), VarType::Const { ty, value: val }));
+            }
+            StmtKind::LetArray {
+                name,
+                ty,
+                init,
+                len,
+            } => {
+                let elem = match ty {
+                    Some(ty) => self.expect(init, *ty)?,
+                    None => self.check_expr(init, None)?,
+                };
+                let var = VarType::Array { elem, len: *len };
+                self.variables.push((name.clone(), var));
+            }
+            StmtKind::Assign { name, expr } => {
+                if self.find(name).is_none() {
+                    return Err(self.undefined_variable(name, span, true));
+                }
+                let ty = self.assignable(name, span)?;
+                self.expect(expr, ty)?;
+            }
+            StmtKind::CompoundAssign { name, op, expr, ty } => {
+                if self.find(name).is_none() {
+                    return Err(self.undefined_variable(name, span, true));
+                }
+                let var_ty = self.assignable(name, span)?;
+                self.check_compound(*op, var_ty, expr, span)?;
+                *ty = Some(var_ty);
+            }
+            StmtKind::AssignIndex {
+                name,
+                index,
+                op,
+                expr,
+                ty,
+            } => {
+                let elem = self.check_index(name, index, span)?;
+                match op {
+                    Some(op) => self.check_compound(*op, elem, expr, span)?,
+                    None => {
+                        self.expect(expr, elem)?;
+                    }
+                }
+                *ty = Some(elem);
+            }
+            StmtKind::Print { expr } => {
+                self.check_expr(expr, None)?;
+            }
+            StmtKind::PrintFormat { pieces, args, .. } => {
+                let specs = pieces.iter().filter_map(|piece| match piece {
+                    FormatPiece::Arg(spec) => Some(spec),
+                    FormatPiece::Text(_) => None,
+                });
+                // The parser checked that there is one argument per
+                // placeholder
+                for (spec, arg) in specs.zip(args.iter_mut()) {
+                    let ty = self.check_expr(arg, None)?;
+                    let allowed = match ty {
+                        Type::Bool => spec.radix == FormatRadix::Decimal && !spec.zero_pad,
+                        Type::F64 => spec.radix == FormatRadix::Decimal,
+                        _ => true,
+                    };
+                    if !allowed {
+                        return Err(error(
+                            "E0303",
+                            arg.anchor(),
+                            format!("cannot format {} with '{}'", ty, spec),
+                        ));
+                    }
+                }
+            }
+            StmtKind::If {
+                cond,
+                then_block,
+                else_block,
+            } => {
+                if let Err(diagnostic) = self.check_condition(cond) {
+                    self.report(diagnostic);
+                }
+                self.check_block(then_block);
+                if let Some(else_block) = else_block {
+                    self.check_block(else_block);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                if let Err(diagnostic) = self.check_condition(cond) {
+                    self.report(diagnostic);
+                }
+                self.check_block(body);
+            }
+            StmtKind::Block(block) => self.check_block(block),
+            StmtKind::Return { expr } => {
+                self.expect(expr, Type::I64)?;
+            }
+            StmtKind::Expr { expr } => {
+                self.check_expr(expr, None)?;
+            }
+            StmtKind::Break | StmtKind::Continue => {}
+        }
+        Ok(())
+    }
+
+    fn check_block(&mut self, block: &mut Block) {
+        let outer_len = self.variables.len();
+        self.check_stmts(&mut block.stmts);
+        self.variables.truncate(outer_len);
+    }
+
+    /// Check `target op= expr`, where the target has type `ty`: `op` must
+    /// apply to the two types and give back `ty`. `span` locates the
+    /// statement.
+    fn check_compound(
+        &mut self,
+        op: BinOp,
+        ty: Type,
+        expr: &mut Expr,
+        span: Span,
+    ) -> Result<(), Diagnostic> {
+        // A shift count may have any integer type
+        let hint = (!matches!(op, BinOp::Shl | BinOp::Shr)).then_some(ty);
+        let rhs = self.check_expr(expr, hint)?;
+        if binop_type(op, ty, rhs) != Some(ty) {
+            return Err(error(
+                "E0305",
+                span,
+                format!("cannot apply '{}=' to {} and {}", op.symbol(), ty, rhs),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that `expr` has type `ty`, and return it.
+    fn expect(&mut self, expr: &mut Expr, ty: Type) -> Result<Type, Diagnostic> {
+        let found = self.check_expr(expr, Some(ty))?;
+        if found != ty {
+            return Err(mismatch(ty, found, expr));
+        }
+        Ok(ty)
+    }
+
+    /// Check the condition of an `if` or `while`, which must be a `bool` or
+    /// an integer (true if nonzero).
+    fn check_condition(&mut self, cond: &mut Expr) -> Result<(), Diagnostic> {
+        self.check_truth_value(cond).map(|_| ())
+    }
+
+    /// Check an expression tested for truth: a condition, or an operand of
+    /// `!`, `&&` or `||`. It must be a `bool` or an integer, which is true
+    /// if nonzero. Returns its type.
+    fn check_truth_value(&mut self, expr: &mut Expr) -> Result<Type, Diagnostic> {
+        let found = self.check_expr(expr, None)?;
+        if found != Type::Bool && !found.is_integer() {
+            return Err(error(
+                "E0304",
+                expr.anchor(),
+                format!("mismatched types: expected bool or an integer, found {}", found),
+            ));
+        }
+        Ok(found)
+    }
+
+    /// Assign types to `expr` and its subexpressions, returning its type.
+    /// `hint` is the type the context expects, if known: integer literals
+    /// take it if it is an integer type, and are `i64` otherwise. The caller
+    /// checks that the result has the expected type.
+    fn check_expr(&mut self, expr: &mut Expr, hint: Option<Type>) -> Result<Type, Diagnostic> {
+        let span = expr.span;
+        let int_hint = hint.filter(|ty| ty.is_integer());
+        let ty = match &mut expr.kind {
+            ExprKind::IntLit(val) => {
+                let ty = int_hint.unwrap_or(Type::I64);
+                self.check_literal(*val as i128, ty, span)?;
+                ty
+            }
+            ExprKind::FloatLit(_) => Type::F64,
+            ExprKind::BoolLit(_) => Type::Bool,
+            ExprKind::Var(name) => self.scalar(name, span)?,
+            ExprKind::UnaryMinus(inner) => {
+                let operand = if let ExprKind::IntLit(val) = inner.kind {
+                    // The magnitude of a negated literal may be one more
+                    // than the maximum of its type
+                    let ty = int_hint.unwrap_or(Type::I64);
+                    if ty.is_signed() {
+                        self.check_literal(-(val as i128), ty, span)?;
+                    }
+                    inner.ty = Some(ty);
+                    ty
+                } else {
+                    self.check_expr(inner, int_hint)?
+                };
+                if !operand.is_signed() && operand != Type::F64 {
+                    return Err(error(
+                        "E0305",
+                        span,
+                        format!("cannot apply '-' to {}", operand),
+                    ));
+                }
+                operand
+            }
+            ExprKind::BitNot(inner) => {
+                let operand = self.check_expr(inner, int_hint)?;
+                if !operand.is_integer() {
+                    return Err(error(
+                        "E0305",
+                        span,
+                        format!("cannot apply '~' to {}", operand),
+                    ));
+                }
+                operand
+            }
+            ExprKind::Not(inner) => {
+                // Gives a bool for a bool, and 0 or 1 for an integer
+                let operand = self.check_truth_value(inner)?;
+                if operand == Type::Bool {
+                    Type::Bool
+                } else {
+                    Type::I64
+                }
+            }
+            ExprKind::BinOp {
+                op,
+                left,
+                right,
+                op_span,
+            } => {
+                let (l, r) = self.check_operands(*op, left, right, int_hint)?;
+                binop_type(*op, l, r).ok_or_else(|| {
+                    error(
+                        "E0305",
+                        *op_span,
+                        format!("cannot apply '{}' to {} and {}", op.symbol(), l, r),
+                    )
+                })?
+            }
+            ExprKind::Logical {
+                op,
+                left,
+                right,
+                op_span,
+            } => {
+                // Gives a bool for two bools, and 0 or 1 otherwise
+                let l = self.check_expr(left, None)?;
+                let r = self.check_expr(right, None)?;
+                let testable = |ty: Type| ty == Type::Bool || ty.is_integer();
+                if !testable(l) || !testable(r) {
+                    return Err(error(
+                        "E0305",
+                        *op_span,
+                        format!("cannot apply '{}' to {} and {}", op.symbol(), l, r),
+                    ));
+                }
+                if l == Type::Bool && r == Type::Bool {
+                    Type::Bool
+                } else {
+                    Type::I64
+                }
+            }
+            ExprKind::Call { name, args } => {
+                let Some(&arity) = self.functions.get(name.as_str()) else {
+                    return Err(self.undefined_function(name, span));
+                };
+                if args.len() != arity {
+                    return Err(error(
+                        "E0307",
+                        span,
+                        format!(
+                            "function '{}' takes {} argument(s) but {} were supplied",
+                            name,
+                            arity,
+                            args.len()
+                        ),
+                    ));
+                }
+                for arg in args {
+                    self.expect(arg, Type::I64)?;
+                }
+                Type::I64
+            }
+            ExprKind::Index { name, index } => self.check_index(name, index, span)?,
+            ExprKind::Len(name) => {
+                self.array(name, span)?;
+                Type::I64
+            }
+            ExprKind::Input => Type::I64,
+            ExprKind::Cast {
+                expr: inner,
+                ty,
+                op_span,
+            } => {
+                let from = self.check_expr(inner, None)?;
+                let allowed = match ty {
+                    Type::Bool => from == Type::Bool,
+                    Type::F64 => from.is_numeric(),
+                    _ => true,
+                };
+                if !allowed {
+                    return Err(error(
+                        "E0308",
+                        *op_span,
+                        format!("cannot cast {} to {}", from, ty),
+                    ));
+                }
+                *ty
+            }
+        };
+        expr.ty = Some(ty);
+        Ok(ty)
+    }
+
+    /// Check the operands of the binary operator `op`, returning their
+    /// types. `hint` is the integer type expected of the result, if any. A
+    /// literal operand takes the type of the other operand, except for a
+    /// shift count, which may have any integer type.
+    fn check_operands(
+        &mut self,
+        op: BinOp,
+        left: &mut Expr,
+        right: &mut Expr,
+        hint: Option<Type>,
+    ) -> Result<(Type, Type), Diagnostic> {
+        if matches!(op, BinOp::Shl | BinOp::Shr) {
+            let l = self.check_expr(left, hint)?;
+            let r = self.check_expr(right, None)?;
+            return Ok((l, r));
+        }
+        // The type of a comparison's operands has nothing to do with its
+        // result
+        let hint = if is_comparison(op) { None } else { hint };
+        if is_literal(left) && !is_literal(right) {
+            let r = self.check_expr(right, hint)?;
+            let l = self.check_expr(left, Some(r))?;
+            Ok((l, r))
+        } else {
+            let l = self.check_expr(left, hint)?;
+            let r = self.check_expr(right, Some(l))?;
+            Ok((l, r))
+        }
+    }
+
+    /// The value of the initializer of a constant, computed at compile time
+    /// as the compiled code would compute it at run time. Only literals,
+    /// constants, operators and casts may appear in it, and it is an error
+    /// for it to divide by zero.
+    fn eval_const(&mut self, expr: &Expr) -> Result<i64, Diagnostic> {
+        let span = expr.span;
+        match &expr.kind {
+            // A literal above i64::MAX is a u64 with the same bits
+            ExprKind::IntLit(val) => Ok(*val as i64),
+            ExprKind::FloatLit(val) => Ok(interp::from_float(*val)),
+            ExprKind::BoolLit(val) => Ok(*val as i64),
+            ExprKind::Var(name) => match self.lookup(name, span)? {
+                VarType::Const { value, .. } => Ok(value),
+                _ => Err(error(
+                    "E0309",
+                    span,
+                    format!("cannot use variable '{}' in a constant expression", name),
+                )
+                .with_note(
+                    "constants are computed at compile time, before any variable has a value",
+                )),
+            },
+            ExprKind::UnaryMinus(inner) => {
+                Ok(interp::negate(self.eval_const(inner)?, expr.ty.unwrap()))
+            }
+            ExprKind::Not(inner) => Ok((self.eval_const(inner)? == 0) as i64),
+            ExprKind::BitNot(inner) => Ok(expr.ty.unwrap().wrap(!self.eval_const(inner)?)),
+            ExprKind::Cast {
+                expr: inner, ty, ..
+            } => Ok(interp::cast(
+                self.eval_const(inner)?,
+                inner.ty.unwrap(),
+                *ty,
+            )),
+            ExprKind::Logical {
+                op, left, right, ..
+            } => {
+                // Short-circuits like at run time: `false && 1 / 0 == 0` is
+                // a constant
+                let l = self.eval_const(left)? != 0;
+                let result = match op {
+                    LogicalOp::And => l && self.eval_const(right)? != 0,
+                    LogicalOp::Or => l || self.eval_const(right)? != 0,
+                };
+                Ok(result as i64)
+            }
+            ExprKind::BinOp {
+                op,
+                left,
+                right,
+                op_span,
+            } => {
+                let ty = left.ty.unwrap();
+                let l = self.eval_const(left)?;
+                let r = self.eval_const(right)?;
+                if r == 0 && ty.is_integer() && matches!(op, BinOp::Div | BinOp::Mod) {
+                    let what = if *op == BinOp::Div {
+                        "division"
+                    } else {
+                        "modulo"
+                    };
+                    return Err(error(
+                        "E0310",
+                        *op_span,
+                        format!("{} by zero in a constant expression", what),
+                    ));
+                }
+                // Division by zero was ruled out, so this cannot fail
+                Ok(interp::binop(*op, ty, l, r, 0).unwrap())
+            }
+            ExprKind::Call { name, .. } => Err(error(
+                "E0309",
+                span,
+                format!("cannot call function '{}' in a constant expression", name),
+            )),
+            ExprKind::Input => Err(error(
+                "E0309",
+                span,
+                "cannot read input in a constant expression".to_string(),
+            )),
+            ExprKind::Index { name, .. } | ExprKind::Len(name) => Err(error(
+                "E0309",
+                span,
+                format!("cannot use array '{}' in a constant expression", name),
+            )),
+        }
+    }
+
+    /// Check an index into the array `name` at `span`, returning the
+    /// element type. The index may have any integer type, and a literal
+    /// index must be in bounds.
+    fn check_index(
+        &mut self,
+        name: &str,
+        index: &mut Expr,
+        span: Span,
+    ) -> Result<Type, Diagnostic> {
+        let (elem, len) = self.array(name, span)?;
+        let ty = self.check_expr(index, Some(Type::I64))?;
+        if !ty.is_integer() {
+            return Err(error(
+                "E0304",
+                index.anchor(),
+                format!("mismatched types: expected an integer, found {}", ty),
+            ));
+        }
+        if let Some(val) = index.literal_value()
+            && !usize::try_from(val).is_ok_and(|val| val < len)
+        {
+            return Err(error(
+                "E0311",
+                span,
+                format!(
+                    "index {} is out of bounds for array '{}' of length {}",
+                    val, name, len
+                ),
+            ));
+        }
+        Ok(elem)
+    }
+
+    /// Check that the value of a literal, at `span`, is in the range of its
+    /// type `ty`.
+    fn check_literal(&self, val: i128, ty: Type, span: Span) -> Result<(), Diagnostic> {
+        if val < ty.min() || val > ty.max() {
+            return Err(error(
+                "E0312",
+                span,
+                format!(
+                    "literal {} is out of range for {} ({} to {})",
+                    val,
+                    ty,
+                    ty.min(),
+                    ty.max()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The innermost variable called `name`, if there is one in scope.
+    fn find(&self, name: &str) -> Option<VarType> {
+        self.variables
+            .iter()
+            .rev()
+            .find(|(declared, _)| declared == name)
+            .map(|(_, var)| *var)
+    }
+
+    /// The innermost variable called `name`, used at `span`.
+    fn lookup(&mut self, name: &str, span: Span) -> Result<VarType, Diagnostic> {
+        self.find(name)
+            .ok_or_else(|| self.undefined_variable(name, span, false))
+    }
+
+    /// The error for a use at `span` of `name`, which is not a variable in
+    /// scope, or for an assignment or compound assignment to it if
+    /// `assigned`. It points at a later declaration of `name` in the same
+    /// block or an enclosing one if there is one, and otherwise suggests a
+    /// variable in scope with a similar name or, for an assignment,
+    /// declaring `name` with `let`.
+    fn undefined_variable(&mut self, name: &str, span: Span, assigned: bool) -> Diagnostic {
+        self.check_lost(name);
+        let diagnostic = error("E0313", span, format!("undefined variable '{}'", name));
+        let later = self.declarations.iter().find(|(declared, decl, block)| {
+            declared == name
+                && decl.start > span.start
+                && block.start <= span.start
+                && span.end <= block.end
+        });
+        if let Some((_, decl, _)) = later {
+            return diagnostic
+                .with_secondary_label(*decl, format!("'{}' is declared here, after its use", name))
+                .with_note("a variable can only be used after the statement that declares it");
+        }
+        let names = self.variables.iter().map(|(declared, _)| declared.as_str());
+        match similar_name(name, names) {
+            Some(similar) => diagnostic.with_help(format!("did you mean '{}'?", similar)),
+            None if assigned => diagnostic.with_help(format!(
+                "use 'let' to declare a new variable: `let {} = ...;`",
+                name
+            )),
+            None => diagnostic,
+        }
+    }
+
+    /// The error for a call at `span` of `name`, which is not a function.
+    fn undefined_function(&mut self, name: &str, span: Span) -> Diagnostic {
+        self.check_lost(name);
+        let diagnostic = error("E0306", span, format!("undefined function '{}'", name));
+        let names = self.functions.keys().map(String::as_str);
+        match similar_name(name, names) {
+            Some(similar) => diagnostic.with_help(format!("did you mean '{}'?", similar)),
+            None => diagnostic,
+        }
+    }
+
+    /// Record whether the undefined `name` is one of `lost_names`.
+    fn check_lost(&mut self, name: &str) {
+        if self.lost_names.iter().any(|lost| lost == name) {
+            self.used_lost_name = true;
+        }
+    }
+
+    /// The type of the scalar variable or constant `name`, used at
+    /// `span`.
+    fn scalar(&mut self, name: &str, span: Span) -> Result<Type, Diagnostic> {
+        match self.lookup(name, span)? {
+            VarType::Scalar(ty) | VarType::Const { ty, .. } => Ok(ty),
+            VarType::Array { .. } => Err(error(
+                "E0314",
+                span,
+                format!("'{}' is an array, not a scalar variable", name),
+            )),
+        }
+    }
+
+    /// The type of the scalar variable `name`, assigned to at `span`.
+    fn assignable(&mut self, name: &str, span: Span) -> Result<Type, Diagnostic> {
+        if let VarType::Const { .. } = self.lookup(name, span)? {
+            return Err(error(
+                "E0315",
+                span,
+                format!("cannot assign to constant '{}'", name),
+            )
+            .with_help(format!(
+                "declare '{}' with 'let' instead of 'const' to assign to it",
+                name
+            )));
+        }
+        self.scalar(name, span)
+    }
+
+    /// The element type and length of the array `name`, used at `span`.
+    fn array(&mut self, name: &str, span: Span) -> Result<(Type, usize), Diagnostic> {
+        match self.lookup(name, span)? {
+            VarType::Array { elem, len } => Ok((elem, len)),
+            VarType::Scalar(_) | VarType::Const { .. } => {
+                Err(error("E0314", span, format!("'{}' is not an array", name)))
+            }
+        }
+    }
+}
+
+/// The type of `l op r`, or `None` if the operator does not apply to
+/// operands of those types. Arithmetic and ordering need two numbers of the
+/// same type, and `%` two integers; a shift takes any integer type as its
+/// count. A comparison of numbers gives 0 or 1 as an `i64`. Equality and
+/// the bitwise operators also work on two `bool`s, giving a `bool`, but the
+/// bitwise operators not on `f64`s.
+fn binop_type(op: BinOp, l: Type, r: Type) -> Option<Type> {
+    match op {
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
+            (l == r && l.is_numeric()).then_some(l)
+        }
+        BinOp::Mod => (l == r && l.is_integer()).then_some(l),
+        BinOp::Shl | BinOp::Shr => (l.is_integer() && r.is_integer()).then_some(l),
+        BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+            (l == r && l.is_numeric()).then_some(Type::I64)
+        }
+        BinOp::Eq | BinOp::Ne if l == Type::Bool => (r == Type::Bool).then_some(Type::Bool),
+        BinOp::Eq | BinOp::Ne => (l == r).then_some(Type::I64),
+        BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor => (l == r && l != Type::F64).then_some(l),
+    }
+}
+
+fn is_comparison(op: BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge
+    )
+}
+
+/// The variables and constants declared by `stmts`, including in nested
+/// blocks, in source order, with the spans of their declarations and of the
+/// statements of the block they are declared in. A name can only be in
+/// scope at a use inside the span of its block.
+fn declarations(stmts: &[Stmt]) -> Vec<(String, Span, Span)> {
+    let mut found = Vec::new();
+    collect_declarations(stmts, &mut found);
+    found
+}
+
+fn collect_declarations(stmts: &[Stmt], found: &mut Vec<(String, Span, Span)>) {
+    let (Some(first), Some(last)) = (stmts.first(), stmts.last()) else {
+        return;
+    };
+    let block = Span::new(first.span.start, last.span.end);
+    for stmt in stmts {
+        match &stmt.kind {
+            StmtKind::Let { name, .. }
+            | StmtKind::Const { name, .. }
+            | StmtKind::LetArray { name, .. } => found.push((name.clone(), stmt.span, block)),
+            StmtKind::If {
+                then_block,
+                else_block,
+                ..
+            } => {
+                collect_declarations(&then_block.stmts, found);
+                if let Some(else_block) = else_block {
+                    collect_declarations(&else_block.stmts, found);
+                }
+            }
+            StmtKind::While { body: block, .. } | StmtKind::Block(block) => {
+                collect_declarations(&block.stmts, found)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The name among `candidates` closest to `name`, if it is close enough to
+/// be a likely misspelling: at most one edit (see `edit_distance`) for
+/// every three characters of `name`, and fewer edits than it has characters,
+/// so that `y` does not suggest `x`. Ties go to the first in sorted order.
+fn similar_name<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let len = name.chars().count();
+    let limit = (len.max(3) / 3).min(len - 1);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of edits that turn `a` into `b`, where an edit inserts,
+/// deletes or replaces a character, or swaps two adjacent ones.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // d[i][j] is the distance between the first i characters of `a` and
+    // the first j of `b`
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let replace = d[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = replace.min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// The error for `expr` having type `found` where `ty` is expected.
+fn mismatch(ty: Type, found: Type, expr: &Expr) -> Diagnostic {
+    error(
+        "E0304",
+        expr.anchor(),
+        format!("mismatched types: expected {}, found {}", ty, found),
+    )
+}
+
+/// A semantic error at `span`.
+fn error(code: &'static str, span: Span, message: String) -> Diagnostic {
+    Diagnostic::error(Phase::Semantic, code, message).with_label(span, "")
+}
+
+/// Whether `expr` is computed from integer literals alone, so that its type
+/// comes from its context: in `x + (1 << 4)` it is the type of `x`.
+fn is_literal(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::IntLit(_) => true,
+        ExprKind::UnaryMinus(inner) | ExprKind::BitNot(inner) => is_literal(inner),
+        ExprKind::BinOp {
+            op: BinOp::Shl | BinOp::Shr,
+            left,
+            ..
+        } => is_literal(left),
+        ExprKind::BinOp {
+            op, left, right, ..
+        } => !is_comparison(*op) && is_literal(left) && is_literal(right),
+        _ => false,
+    }
+}
@@ -0,0 +1,179 @@
+//! Snapshot tests of rendered error messages: every program in
+//! `tests/diagnostics` must fail to compile, and the error output must match
+//! the checked-in `<name>.stderr`. Run with `UPDATE_GOLDEN=1` to regenerate
+//! the files after an intentional change to the messages or their layout.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/diagnostics")
+}
+
+/// Compile `<name>.toy` to assembly, which must fail, and return the error
+/// output. The compiler runs in the snapshot directory so that messages
+/// name the file as just `<name>.toy`.
+fn render_compile_errors(name: &str, extra_args: &[&str]) -> String {
+    let file = format!("{name}.toy");
+    let out_path =
+        std::env::temp_dir().join(format!("toy_diagnostic_{}_{}.s", std::process::id(), name));
+    let compile_output = Command::new(env!("CARGO_BIN_EXE_toy-compiler"))
+        .current_dir(snapshot_dir())
+        .arg(&file)
+        .args(["-S", "-o"])
+        .arg(&out_path)
+        .args(extra_args)
+        .output()
+        .expect("failed to run toy-compiler");
+    let _ = fs::remove_file(&out_path);
+    assert!(!compile_output.status.success(), "{file} compiled");
+    String::from_utf8(compile_output.stderr).unwrap()
+}
+
+/// Compile and run `<name>.toy`, which must fail the same way both times,
+/// and return the error output.
+fn render_errors(name: &str, extra_args: &[&str]) -> String {
+    let file = format!("{name}.toy");
+    let stderr = render_compile_errors(name, extra_args);
+    let run_output = Command::new(env!("CARGO_BIN_EXE_toy-compiler"))
+        .current_dir(snapshot_dir())
+        .args(["run", &file])
+        .args(extra_args)
+        .output()
+        .expect("failed to run toy-compiler run");
+    assert!(!run_output.status.success(), "{file} ran");
+    assert_eq!(
+        String::from_utf8(run_output.stderr).unwrap(),
+        stderr,
+        "compile and run errors for {file} differ"
+    );
+    stderr
+}
+
+fn check_snapshot(name: &str, snapshot: &str, extra_args: &[&str]) {
+    let stderr = render_errors(name, extra_args);
+    compare_snapshot(&stderr, name, snapshot);
+}
+
+/// Compare `stderr`, the errors for `<name>.toy`, with the file `snapshot`.
+fn compare_snapshot(stderr: &str, name: &str, snapshot: &str) {
+    let snapshot_path = snapshot_dir().join(snapshot);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&snapshot_path, stderr).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&snapshot_path)
+        .unwrap_or_else(|e| panic!("reading {}: {}", snapshot_path.display(), e));
+    assert_eq!(
+        stderr,
+        expected,
+        "errors for {name}.toy differ from {}",
+        snapshot_path.display()
+    );
+}
+
+/// Check the plain rendering of `<name>.toy`'s errors, which is what is
+/// printed when standard error is not a terminal.
+fn check_plain(name: &str) {
+    check_snapshot(name, &format!("{name}.stderr"), &[]);
+    // Asking for no colour changes nothing
+    check_snapshot(name, &format!("{name}.stderr"), &["--color", "never"]);
+}
+
+#[test]
+fn snapshot_missing_semicolon() {
+    check_plain("missing_semicolon");
+}
+
+#[test]
+fn snapshot_unclosed_block() {
+    check_plain("unclosed_block");
+}
+
+#[test]
+fn snapshot_tab_indented() {
+    check_plain("tab_indented");
+}
+
+#[test]
+fn snapshot_duplicate_function() {
+    check_plain("duplicate_function");
+}
+
+#[test]
+fn snapshot_duplicate_parameter() {
+    check_plain("duplicate_parameter");
+}
+
+#[test]
+fn snapshot_assign_to_const() {
+    check_plain("assign_to_const");
+}
+
+#[test]
+fn snapshot_const_uses_variable() {
+    check_plain("const_uses_variable");
+}
+
+#[test]
+fn snapshot_wide_gutter() {
+    check_plain("wide_gutter");
+}
+
+#[test]
+fn snapshot_unterminated_string() {
+    check_plain("unterminated_string");
+}
+
+#[test]
+fn snapshot_multiple_errors() {
+    check_plain("multiple_errors");
+}
+
+#[test]
+fn snapshot_syntax_and_semantic_errors() {
+    check_plain("syntax_and_semantic_errors");
+}
+
+#[test]
+fn snapshot_len_statement() {
+    check_plain("len_statement");
+}
+
+#[test]
+fn snapshot_color() {
+    check_snapshot(
+        "duplicate_parameter",
+        "duplicate_parameter.color.stderr",
+        &["--color", "always"],
+    );
+    check_snapshot(
+        "assign_to_const",
+        "assign_to_const.color.stderr",
+        &["--color", "always"],
+    );
+}
+
+#[test]
+fn snapshot_use_before_let() {
+    check_plain("use_before_let");
+}
+
+/// Check the plain rendering of the errors that code generation finds in
+/// `<name>.toy`. Only the compiler reports them: the interpreter does not
+/// have the target's limits.
+fn check_codegen(name: &str) {
+    let stderr = render_compile_errors(name, &[]);
+    compare_snapshot(&stderr, name, &format!("{name}.stderr"));
+}
+
+#[test]
+fn snapshot_too_many_variables() {
+    check_codegen("too_many_variables");
+}
+
+#[test]
+fn snapshot_arrays_too_large() {
+    check_codegen("arrays_too_large");
+}
@@ -0,0 +1,72 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #6
+    str x0, [x29, #-8]
+    movz x0, #4464
+    movk x0, #1, lsl #16
+    neg x0, x0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #7
+    ldr x1, [sp], #16
+    mul x0, x1, x0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    str x0, [sp, #-16]!
+    mov x0, #3
+    ldr x1, [sp], #16
+    sdiv x0, x1, x0
+    str x0, [sp, #-16]!
+    mov x0, #5
+    ldr x1, [sp], #16
+    sdiv x2, x1, x0
+    msub x0, x2, x0, x1
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    sub x0, x1, x0
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-8]
+    neg x0, x0
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    movz x0, #65535
+    movk x0, #65535, lsl #16
+    movk x0, #65535, lsl #32
+    movk x0, #32767, lsl #48
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
@@ -0,0 +1,92 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq $6, %rax
+    movq %rax, -8(%rbp)
+    movq $70000, %rax
+    negq %rax
+    movq %rax, -16(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $7, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    imulq %rcx, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -16(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    addq %rcx, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $3, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    cmpq $-1, %rcx
+    jne 1f
+    negq %rax
+    jmp 2f
+1:
+    cqto
+    idivq %rcx
+2:
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $5, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    cmpq $-1, %rcx
+    jne 1f
+    xorl %eax, %eax
+    jmp 2f
+1:
+    cqto
+    idivq %rcx
+    movq %rdx, %rax
+2:
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    subq %rax, %rcx
+    movq %rcx, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -8(%rbp), %rax
+    negq %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movabsq $9223372036854775807, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,89 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+_str0:
+    .asciz "Runtime error: 4:1: index %lld is out of bounds for array 'a' of length 4\n"
+_str1:
+    .asciz "Runtime error: 5:9: index %lld is out of bounds for array 'a' of length 4\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #48
+    mov x0, #0
+    sub x1, x29, #40
+    mov x2, #4
+1:
+    cbz x2, 2f
+    str x0, [x1], #8
+    sub x2, x2, #1
+    b 1b
+2:
+    mov x0, #1
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    mov x1, #4
+    cmp x0, x1
+    b.lo L0
+    str x0, [sp, #-16]!
+    mov x0, #2
+    adrp x1, _str0@PAGE
+    add x1, x1, _str0@PAGEOFF
+    bl _dprintf
+    mov x0, #1
+    bl _exit
+L0:
+    str x0, [sp, #-16]!
+    mov x0, #5
+    ldr x1, [sp], #16
+    add x1, x29, x1, lsl #3
+    sub x1, x1, #40
+    str x0, [x1]
+    mov x0, #2
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    add x0, x29, x0, lsl #3
+    sub x0, x0, #40
+    ldr x0, [x0]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    mov x1, #4
+    cmp x0, x1
+    b.lo L1
+    str x0, [sp, #-16]!
+    mov x0, #2
+    adrp x1, _str1@PAGE
+    add x1, x1, _str1@PAGEOFF
+    bl _dprintf
+    mov x0, #1
+    bl _exit
+L1:
+    add x0, x29, x0, lsl #3
+    sub x0, x0, #40
+    ldr x0, [x0]
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    ldr x1, [sp], #16
+    add x1, x29, x1, lsl #3
+    sub x1, x1, #40
+    str x0, [x1]
+    mov x0, #2
+    add x0, x29, x0, lsl #3
+    sub x0, x0, #40
+    ldr x0, [x0]
+    str x0, [sp, #-16]!
+    mov x0, #4
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
@@ -0,0 +1,90 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "Runtime error: 4:1: index %lld is out of bounds for array 'a' of length 4\n"
+str1:
+    .asciz "Runtime error: 5:9: index %lld is out of bounds for array 'a' of length 4\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #48
+    mov x0, #0
+    sub x1, x29, #40
+    mov x2, #4
+1:
+    cbz x2, 2f
+    str x0, [x1], #8
+    sub x2, x2, #1
+    b 1b
+2:
+    mov x0, #1
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    mov x1, #4
+    cmp x0, x1
+    b.lo .L0
+    mov x2, x0
+    mov x0, #2
+    adrp x1, str0
+    add x1, x1, :lo12:str0
+    bl dprintf
+    mov x0, #1
+    bl exit
+.L0:
+    str x0, [sp, #-16]!
+    mov x0, #5
+    ldr x1, [sp], #16
+    add x1, x29, x1, lsl #3
+    sub x1, x1, #40
+    str x0, [x1]
+    mov x0, #2
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    add x0, x29, x0, lsl #3
+    sub x0, x0, #40
+    ldr x0, [x0]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    mov x1, #4
+    cmp x0, x1
+    b.lo .L1
+    mov x2, x0
+    mov x0, #2
+    adrp x1, str1
+    add x1, x1, :lo12:str1
+    bl dprintf
+    mov x0, #1
+    bl exit
+.L1:
+    add x0, x29, x0, lsl #3
+    sub x0, x0, #40
+    ldr x0, [x0]
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    ldr x1, [sp], #16
+    add x1, x29, x1, lsl #3
+    sub x1, x1, #40
+    str x0, [x1]
+    mov x0, #2
+    add x0, x29, x0, lsl #3
+    sub x0, x0, #40
+    ldr x0, [x0]
+    str x0, [sp, #-16]!
+    mov x0, #4
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,80 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "Runtime error: 4:1: index %lld is out of bounds for array 'a' of length 4\n"
+str1:
+    .asciz "Runtime error: 5:9: index %lld is out of bounds for array 'a' of length 4\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $48, %rsp
+    movq $0, %rax
+    leaq -40(%rbp), %rdi
+    movq $4, %rcx
+    rep stosq
+    movq $1, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    cmpq $4, %rax
+    jb .L0
+    movq %rax, %rdx
+    movl $2, %edi
+    leaq str0(%rip), %rsi
+    xorl %eax, %eax
+    call dprintf@PLT
+    movl $1, %edi
+    call exit@PLT
+.L0:
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $5, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    movq %rax, -40(%rbp,%rcx,8)
+    movq $2, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    movq -40(%rbp,%rax,8), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -8(%rbp), %rax
+    cmpq $4, %rax
+    jb .L1
+    movq %rax, %rdx
+    movl $2, %edi
+    leaq str1(%rip), %rsi
+    xorl %eax, %eax
+    call dprintf@PLT
+    movl $1, %edi
+    call exit@PLT
+.L1:
+    movq -40(%rbp,%rax,8), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    addq %rcx, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    movq %rax, -40(%rbp,%rcx,8)
+    movq $2, %rax
+    movq -40(%rbp,%rax,8), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $4, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    addq %rcx, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
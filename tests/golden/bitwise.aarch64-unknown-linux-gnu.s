@@ -0,0 +1,55 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #12
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #10
+    ldr x1, [sp], #16
+    and x0, x1, x0
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #3
+    ldr x1, [sp], #16
+    eor x0, x1, x0
+    ldr x1, [sp], #16
+    orr x0, x1, x0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-8]
+    mvn x0, x0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #4
+    ldr x1, [sp], #16
+    lsl x0, x1, x0
+    str x0, [sp, #-16]!
+    mov x0, #2
+    ldr x1, [sp], #16
+    asr x0, x1, x0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,86 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #3
+    str x0, [x29, #-8]
+    mov x0, #4
+    neg x0, x0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, eq
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, ne
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, lt
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, le
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, gt
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, ge
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
@@ -0,0 +1,82 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #3
+    str x0, [x29, #-8]
+    mov x0, #4
+    neg x0, x0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, eq
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, ne
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, lt
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, le
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, gt
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, ge
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
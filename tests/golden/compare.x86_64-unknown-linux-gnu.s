@@ -0,0 +1,99 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq $3, %rax
+    movq %rax, -8(%rbp)
+    movq $4, %rax
+    negq %rax
+    movq %rax, -16(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -16(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    sete %al
+    movzbq %al, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -16(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setne %al
+    movzbq %al, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -16(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setl %al
+    movzbq %al, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -16(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setle %al
+    movzbq %al, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -16(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setg %al
+    movzbq %al, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -16(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setge %al
+    movzbq %al, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
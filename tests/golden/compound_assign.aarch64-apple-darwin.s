@@ -0,0 +1,35 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #3
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #4
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    lsl x0, x1, x0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
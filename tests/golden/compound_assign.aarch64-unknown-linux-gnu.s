@@ -0,0 +1,36 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #3
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #4
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    lsl x0, x1, x0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,40 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq $3, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $4, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    addq %rcx, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    shlq %cl, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,61 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "%hhu\n"
+str1:
+    .asciz "%g\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #1000
+    str x0, [sp, #-16]!
+    mov x0, #4
+    ldr x1, [sp], #16
+    sdiv x0, x1, x0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #1000
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x0, #255
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    mov x1, x0
+    adrp x0, str0
+    add x0, x0, :lo12:str0
+    bl printf
+    add sp, sp, #16
+    movz x0, #0
+    movk x0, #16352, lsl #48
+    str x0, [sp, #-16]!
+    movz x0, #0
+    movk x0, #16392, lsl #48
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fmul d0, d1, d0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    fmov d0, x0
+    adrp x0, str1
+    add x0, x0, :lo12:str1
+    bl printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,74 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "%hhu\n"
+str1:
+    .asciz "%g\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq $1000, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $4, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    cmpq $-1, %rcx
+    jne 1f
+    negq %rax
+    jmp 2f
+1:
+    cqto
+    idivq %rcx
+2:
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1000, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    addq %rcx, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $255, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    movq %rax, %rsi
+    leaq str0(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $16, %rsp
+    movabsq $4602678819172646912, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movabsq $4613937818241073152, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    movq %rcx, %xmm1
+    movq %rax, %xmm0
+    mulsd %xmm0, %xmm1
+    movq %xmm1, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    movq %rax, %xmm0
+    leaq str1(%rip), %rdi
+    movl $1, %eax
+    call printf@PLT
+    addq $16, %rsp
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,102 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+_str0:
+    .asciz "%g\n"
+_str1:
+    .asciz "%llu\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    movz x0, #0
+    movk x0, #16376, lsl #48
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    movz x0, #0
+    movk x0, #16384, lsl #48
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fmul d0, d1, d0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    movz x0, #0
+    movk x0, #16336, lsl #48
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fsub d0, d1, d0
+    fmov x0, d0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fdiv d0, d1, d0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    str x0, [sp, #-16]!
+    adrp x0, _str0@PAGE
+    add x0, x0, _str0@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    add sp, sp, #16
+    ldr x0, [x29, #-16]
+    fmov d0, x0
+    fneg d0, d0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fcmp d1, d0
+    cset x0, mi
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-16]
+    fmov d0, x0
+    fcvtzs x0, d0
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #3
+    scvtf d0, x0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    str x0, [sp, #-16]!
+    adrp x0, _str0@PAGE
+    add x0, x0, _str0@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    add sp, sp, #16
+    ldr x0, [x29, #-16]
+    fmov d0, x0
+    fcvtzu x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    str x0, [sp, #-16]!
+    adrp x0, _str1@PAGE
+    add x0, x0, _str1@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
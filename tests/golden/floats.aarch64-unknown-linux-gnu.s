@@ -0,0 +1,99 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "%g\n"
+str1:
+    .asciz "%llu\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    movz x0, #0
+    movk x0, #16376, lsl #48
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    movz x0, #0
+    movk x0, #16384, lsl #48
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fmul d0, d1, d0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    movz x0, #0
+    movk x0, #16336, lsl #48
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fsub d0, d1, d0
+    fmov x0, d0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fdiv d0, d1, d0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    fmov d0, x0
+    adrp x0, str0
+    add x0, x0, :lo12:str0
+    bl printf
+    add sp, sp, #16
+    ldr x0, [x29, #-16]
+    fmov d0, x0
+    fneg d0, d0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    ldr x1, [sp], #16
+    fmov d1, x1
+    fmov d0, x0
+    fcmp d1, d0
+    cset x0, mi
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-16]
+    fmov d0, x0
+    fcvtzs x0, d0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x0, #3
+    scvtf d0, x0
+    fmov x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    fmov d0, x0
+    adrp x0, str0
+    add x0, x0, :lo12:str0
+    bl printf
+    add sp, sp, #16
+    ldr x0, [x29, #-16]
+    fmov d0, x0
+    fcvtzu x0, d0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    mov x1, x0
+    adrp x0, str1
+    add x0, x0, :lo12:str1
+    bl printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,131 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "%g\n"
+str1:
+    .asciz "%llu\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movabsq $4609434218613702656, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movabsq $4611686018427387904, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    movq %rcx, %xmm1
+    movq %rax, %xmm0
+    mulsd %xmm0, %xmm1
+    movq %xmm1, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movabsq $4598175219545276416, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    movq %rcx, %xmm1
+    movq %rax, %xmm0
+    subsd %xmm0, %xmm1
+    movq %xmm1, %rax
+    movq %rax, -16(%rbp)
+    movq -16(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -8(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    movq %rcx, %xmm1
+    movq %rax, %xmm0
+    divsd %xmm0, %xmm1
+    movq %xmm1, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    movq %rax, %xmm0
+    leaq str0(%rip), %rdi
+    movl $1, %eax
+    call printf@PLT
+    addq $16, %rsp
+    movq -16(%rbp), %rax
+    btcq $63, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -8(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    movq %rcx, %xmm1
+    movq %rax, %xmm0
+    ucomisd %xmm1, %xmm0
+    seta %al
+    movzbq %al, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -16(%rbp), %rax
+    movq %rax, %xmm0
+    movq %rax, %rcx
+    cvttsd2siq %xmm0, %rax
+    notq %rcx
+    andq %rax, %rcx
+    sarq $63, %rcx
+    xorq %rcx, %rax
+    ucomisd %xmm0, %xmm0
+    jnp 1f
+    xorl %eax, %eax
+1:
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $3, %rax
+    cvtsi2sdq %rax, %xmm0
+    movq %xmm0, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    movq %rax, %xmm0
+    leaq str0(%rip), %rdi
+    movl $1, %eax
+    call printf@PLT
+    addq $16, %rsp
+    movq -16(%rbp), %rax
+    movq %rax, %xmm0
+    movabsq $0x43e0000000000000, %rcx
+    movq %rcx, %xmm1
+    ucomisd %xmm1, %xmm0
+    jae 1f
+    cvttsd2siq %xmm0, %rax
+    movq %rax, %rcx
+    sarq $63, %rcx
+    notq %rcx
+    andq %rcx, %rax
+    jmp 2f
+1:
+    subsd %xmm1, %xmm0
+    cvttsd2siq %xmm0, %rax
+    movq %rax, %rcx
+    sarq $63, %rcx
+    btcq $63, %rax
+    orq %rcx, %rax
+2:
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    movq %rax, %rsi
+    leaq str1(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $16, %rsp
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,155 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+_str0:
+    .asciz "x = %lld"
+_str1:
+    .asciz ", hex %04llx\n"
+_str2:
+    .asciz "!\n"
+_str3:
+    .asciz "0"
+_str4:
+    .asciz " "
+_str5:
+    .asciz "%lld"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #10
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #16]
+    str x0, [sp, #-16]!
+    adrp x0, _str0@PAGE
+    add x0, x0, _str0@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [sp, #0]
+    str x0, [sp, #-16]!
+    adrp x0, _str1@PAGE
+    add x0, x0, _str1@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    add sp, sp, #32
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    str x0, [sp, #-16]!
+    mov x0, #0
+    str x0, [sp, #-16]!
+    mov x0, #0
+    str x0, [sp, #-16]!
+    ldr x2, [sp], #16
+    ldr x1, [sp], #16
+    ldr x0, [sp], #16
+    bl _toyrt_print_binary
+    add sp, sp, #16
+    str x0, [sp, #-16]!
+    adrp x0, _str2@PAGE
+    add x0, x0, _str2@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.globl _toyrt_print_binary
+.p2align 2
+_toyrt_print_binary:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #32
+    str x0, [x29, #-8]
+    str x1, [x29, #-16]
+    str x2, [x29, #-24]
+    mov x0, #1
+    str x0, [x29, #-32]
+L0:
+    ldr x0, [x29, #-32]
+    str x0, [sp, #-16]!
+    mov x0, #64
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, lt
+    cbz x0, L1
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-32]
+    ldr x1, [sp], #16
+    asr x0, x1, x0
+    cbz x0, L1
+    ldr x0, [x29, #-32]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    str x0, [x29, #-32]
+    b L0
+L1:
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-32]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, gt
+    cbz x0, L4
+    ldr x0, [x29, #-24]
+    cbz x0, L2
+    str x0, [sp, #-16]!
+    adrp x0, _str3@PAGE
+    add x0, x0, _str3@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    b L3
+L2:
+    str x0, [sp, #-16]!
+    adrp x0, _str4@PAGE
+    add x0, x0, _str4@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+L3:
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    sub x0, x1, x0
+    str x0, [x29, #-16]
+    b L1
+L4:
+    ldr x0, [x29, #-32]
+    cbz x0, L5
+    ldr x0, [x29, #-32]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    sub x0, x1, x0
+    str x0, [x29, #-32]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-32]
+    ldr x1, [sp], #16
+    asr x0, x1, x0
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    and x0, x1, x0
+    str x0, [sp, #-16]!
+    adrp x0, _str5@PAGE
+    add x0, x0, _str5@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    b L4
+L5:
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
@@ -0,0 +1,151 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "x = %lld"
+str1:
+    .asciz ", hex %04llx\n"
+str2:
+    .asciz "!\n"
+str3:
+    .asciz "0"
+str4:
+    .asciz " "
+str5:
+    .asciz "%lld"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #10
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #16]
+    mov x1, x0
+    adrp x0, str0
+    add x0, x0, :lo12:str0
+    bl printf
+    ldr x0, [sp, #0]
+    mov x1, x0
+    adrp x0, str1
+    add x0, x0, :lo12:str1
+    bl printf
+    add sp, sp, #32
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    str x0, [sp, #-16]!
+    mov x0, #0
+    str x0, [sp, #-16]!
+    mov x0, #0
+    str x0, [sp, #-16]!
+    ldr x2, [sp], #16
+    ldr x1, [sp], #16
+    ldr x0, [sp], #16
+    bl toyrt_print_binary
+    add sp, sp, #16
+    mov x1, x0
+    adrp x0, str2
+    add x0, x0, :lo12:str2
+    bl printf
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.globl toyrt_print_binary
+.p2align 2
+toyrt_print_binary:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #32
+    str x0, [x29, #-8]
+    str x1, [x29, #-16]
+    str x2, [x29, #-24]
+    mov x0, #1
+    str x0, [x29, #-32]
+.L0:
+    ldr x0, [x29, #-32]
+    str x0, [sp, #-16]!
+    mov x0, #64
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, lt
+    cbz x0, .L1
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-32]
+    ldr x1, [sp], #16
+    asr x0, x1, x0
+    cbz x0, .L1
+    ldr x0, [x29, #-32]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    str x0, [x29, #-32]
+    b .L0
+.L1:
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-32]
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, gt
+    cbz x0, .L4
+    ldr x0, [x29, #-24]
+    cbz x0, .L2
+    mov x1, x0
+    adrp x0, str3
+    add x0, x0, :lo12:str3
+    bl printf
+    b .L3
+.L2:
+    mov x1, x0
+    adrp x0, str4
+    add x0, x0, :lo12:str4
+    bl printf
+.L3:
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    sub x0, x1, x0
+    str x0, [x29, #-16]
+    b .L1
+.L4:
+    ldr x0, [x29, #-32]
+    cbz x0, .L5
+    ldr x0, [x29, #-32]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    sub x0, x1, x0
+    str x0, [x29, #-32]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-32]
+    ldr x1, [sp], #16
+    asr x0, x1, x0
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    and x0, x1, x0
+    mov x1, x0
+    adrp x0, str5
+    add x0, x0, :lo12:str5
+    bl printf
+    b .L4
+.L5:
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,185 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "x = %lld"
+str1:
+    .asciz ", hex %04llx\n"
+str2:
+    .asciz "!\n"
+str3:
+    .asciz "0"
+str4:
+    .asciz " "
+str5:
+    .asciz "%lld"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq $10, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 16(%rsp), %rax
+    movq %rax, %rsi
+    leaq str0(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq 0(%rsp), %rax
+    movq %rax, %rsi
+    leaq str1(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $32, %rsp
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $0, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $0, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq (%rsp), %rdx
+    addq $16, %rsp
+    movq (%rsp), %rsi
+    addq $16, %rsp
+    movq (%rsp), %rdi
+    addq $16, %rsp
+    call toyrt_print_binary
+    addq $16, %rsp
+    movq %rax, %rsi
+    leaq str2(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.globl toyrt_print_binary
+toyrt_print_binary:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $32, %rsp
+    movq %rdi, -8(%rbp)
+    movq %rsi, -16(%rbp)
+    movq %rdx, -24(%rbp)
+    movq $1, %rax
+    movq %rax, -32(%rbp)
+.L0:
+    movq -32(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $64, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setl %al
+    movzbq %al, %rax
+    testq %rax, %rax
+    jz .L1
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -32(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    sarq %cl, %rax
+    testq %rax, %rax
+    jz .L1
+    movq -32(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    addq %rcx, %rax
+    movq %rax, -32(%rbp)
+    jmp .L0
+.L1:
+    movq -16(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -32(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setg %al
+    movzbq %al, %rax
+    testq %rax, %rax
+    jz .L4
+    movq -24(%rbp), %rax
+    testq %rax, %rax
+    jz .L2
+    movq %rax, %rsi
+    leaq str3(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    jmp .L3
+.L2:
+    movq %rax, %rsi
+    leaq str4(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+.L3:
+    movq -16(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    subq %rax, %rcx
+    movq %rcx, %rax
+    movq %rax, -16(%rbp)
+    jmp .L1
+.L4:
+    movq -32(%rbp), %rax
+    testq %rax, %rax
+    jz .L5
+    movq -32(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    subq %rax, %rcx
+    movq %rcx, %rax
+    movq %rax, -32(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -32(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    sarq %cl, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    andq %rcx, %rax
+    movq %rax, %rsi
+    leaq str5(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    jmp .L4
+.L5:
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,66 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    mov x0, #2
+    str x0, [sp, #-16]!
+    mov x0, #10
+    str x0, [sp, #-16]!
+    ldr x1, [sp], #16
+    ldr x0, [sp], #16
+    bl _toy_power
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.globl _toy_power
+.p2align 2
+_toy_power:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    str x0, [x29, #-8]
+    str x1, [x29, #-16]
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    mov x0, #0
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, eq
+    cbz x0, L1
+    mov x0, #1
+    b L0
+L1:
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    sub x0, x1, x0
+    str x0, [sp, #-16]!
+    ldr x1, [sp], #16
+    ldr x0, [sp], #16
+    bl _toy_power
+    ldr x1, [sp], #16
+    mul x0, x1, x0
+    b L0
+    mov x0, #0
+L0:
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
@@ -0,0 +1,82 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    movq $2, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $10, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq (%rsp), %rsi
+    addq $16, %rsp
+    movq (%rsp), %rdi
+    addq $16, %rsp
+    call toy_power
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.globl toy_power
+toy_power:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq %rdi, -8(%rbp)
+    movq %rsi, -16(%rbp)
+    movq -16(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $0, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    sete %al
+    movzbq %al, %rax
+    testq %rax, %rax
+    jz .L1
+    movq $1, %rax
+    jmp .L0
+.L1:
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -16(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    subq %rax, %rcx
+    movq %rcx, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq (%rsp), %rsi
+    addq $16, %rsp
+    movq (%rsp), %rdi
+    addq $16, %rsp
+    call toy_power
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    imulq %rcx, %rax
+    jmp .L0
+    movq $0, %rax
+.L0:
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,71 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #7
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #0
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, lt
+    cbz x0, L0
+    mov x0, #1
+    neg x0, x0
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    b L1
+L0:
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #0
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, eq
+    cbz x0, L2
+    mov x0, #0
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    b L3
+L2:
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #2
+    ldr x1, [sp], #16
+    mul x0, x1, x0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+L3:
+L1:
+    ldr x0, [x29, #-8]
+    cbz x0, L4
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+L4:
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
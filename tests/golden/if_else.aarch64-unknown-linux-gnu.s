@@ -0,0 +1,69 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #7
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #0
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, lt
+    cbz x0, .L0
+    mov x0, #1
+    neg x0, x0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    b .L1
+.L0:
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #0
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, eq
+    cbz x0, .L2
+    mov x0, #0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    b .L3
+.L2:
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #2
+    ldr x1, [sp], #16
+    mul x0, x1, x0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-16]
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+.L3:
+.L1:
+    ldr x0, [x29, #-8]
+    cbz x0, .L4
+    ldr x0, [x29, #-8]
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+.L4:
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
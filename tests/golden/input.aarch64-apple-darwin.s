@@ -0,0 +1,64 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+_str0:
+    .asciz "%lld"
+_str1:
+    .asciz "Runtime error: 3:9: unexpected end of input\n"
+_str2:
+    .asciz "Runtime error: 3:9: expected an integer in the input\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    str x0, [sp, #-16]!
+    mov x1, sp
+    str x1, [sp, #-16]!
+    adrp x0, _str0@PAGE
+    add x0, x0, _str0@PAGEOFF
+    bl _scanf
+    add sp, sp, #16
+    sxtw x0, w0
+    mov x1, #1
+    cmp x0, x1
+    b.lo L0
+    mov x1, #2
+    cmp x0, x1
+    b.lo L1
+    str x0, [sp, #-16]!
+    mov x0, #2
+    adrp x1, _str1@PAGE
+    add x1, x1, _str1@PAGEOFF
+    bl _dprintf
+    mov x0, #1
+    bl _exit
+L0:
+    str x0, [sp, #-16]!
+    mov x0, #2
+    adrp x1, _str2@PAGE
+    add x1, x1, _str2@PAGEOFF
+    bl _dprintf
+    mov x0, #1
+    bl _exit
+L1:
+    ldr x0, [sp, #0]
+    add sp, sp, #16
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #2
+    ldr x1, [sp], #16
+    mul x0, x1, x0
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
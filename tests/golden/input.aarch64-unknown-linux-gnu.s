@@ -0,0 +1,63 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "%lld"
+str1:
+    .asciz "Runtime error: 3:9: unexpected end of input\n"
+str2:
+    .asciz "Runtime error: 3:9: expected an integer in the input\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    str x0, [sp, #-16]!
+    mov x1, sp
+    adrp x0, str0
+    add x0, x0, :lo12:str0
+    bl scanf
+    sxtw x0, w0
+    mov x1, #1
+    cmp x0, x1
+    b.lo .L0
+    mov x1, #2
+    cmp x0, x1
+    b.lo .L1
+    mov x2, x0
+    mov x0, #2
+    adrp x1, str1
+    add x1, x1, :lo12:str1
+    bl dprintf
+    mov x0, #1
+    bl exit
+.L0:
+    mov x2, x0
+    mov x0, #2
+    adrp x1, str2
+    add x1, x1, :lo12:str2
+    bl dprintf
+    mov x0, #1
+    bl exit
+.L1:
+    ldr x0, [sp, #0]
+    add sp, sp, #16
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #2
+    ldr x1, [sp], #16
+    mul x0, x1, x0
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
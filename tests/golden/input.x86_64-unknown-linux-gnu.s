@@ -0,0 +1,63 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "%lld"
+str1:
+    .asciz "Runtime error: 3:9: unexpected end of input\n"
+str2:
+    .asciz "Runtime error: 3:9: expected an integer in the input\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq %rsp, %rsi
+    leaq str0(%rip), %rdi
+    xorl %eax, %eax
+    call scanf@PLT
+    movslq %eax, %rax
+    cmpq $1, %rax
+    jb .L0
+    cmpq $2, %rax
+    jb .L1
+    movq %rax, %rdx
+    movl $2, %edi
+    leaq str1(%rip), %rsi
+    xorl %eax, %eax
+    call dprintf@PLT
+    movl $1, %edi
+    call exit@PLT
+.L0:
+    movq %rax, %rdx
+    movl $2, %edi
+    leaq str2(%rip), %rsi
+    xorl %eax, %eax
+    call dprintf@PLT
+    movl $1, %edi
+    call exit@PLT
+.L1:
+    movq 0(%rsp), %rax
+    addq $16, %rsp
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $2, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    imulq %rcx, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
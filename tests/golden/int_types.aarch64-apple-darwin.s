@@ -0,0 +1,70 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+_str0:
+    .asciz "%hhu\n"
+_str1:
+    .asciz "%hx\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #200
+    str x0, [x29, #-8]
+    mov x0, #3
+    neg x0, x0
+    sxth x0, w0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #100
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    uxtb w0, w0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #3
+    ldr x1, [sp], #16
+    udiv x0, x1, x0
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    lsr x0, x1, x0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    str x0, [sp, #-16]!
+    adrp x0, _str0@PAGE
+    add x0, x0, _str0@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    add sp, sp, #16
+    ldr x0, [x29, #-16]
+    mov w0, w0
+    str x0, [sp, #-16]!
+    mov x0, #7
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, hi
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    str x0, [sp, #-16]!
+    adrp x0, _str1@PAGE
+    add x0, x0, _str1@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
@@ -0,0 +1,69 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "%hhu\n"
+str1:
+    .asciz "%hx\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #200
+    str x0, [x29, #-8]
+    mov x0, #3
+    neg x0, x0
+    sxth x0, w0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #100
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    uxtb w0, w0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #3
+    ldr x1, [sp], #16
+    udiv x0, x1, x0
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    lsr x0, x1, x0
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    mov x1, x0
+    adrp x0, str0
+    add x0, x0, :lo12:str0
+    bl printf
+    add sp, sp, #16
+    ldr x0, [x29, #-16]
+    mov w0, w0
+    str x0, [sp, #-16]!
+    mov x0, #7
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, hi
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    ldr x0, [sp, #0]
+    mov x1, x0
+    adrp x0, str1
+    add x0, x0, :lo12:str1
+    bl printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
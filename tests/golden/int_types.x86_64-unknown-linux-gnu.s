@@ -0,0 +1,82 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "%hhu\n"
+str1:
+    .asciz "%hx\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq $200, %rax
+    movq %rax, -8(%rbp)
+    movq $3, %rax
+    negq %rax
+    movswq %ax, %rax
+    movq %rax, -16(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $100, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    addq %rcx, %rax
+    movzbq %al, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $3, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    xorl %edx, %edx
+    divq %rcx
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    shrq %cl, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    movq %rax, %rsi
+    leaq str0(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $16, %rsp
+    movq -16(%rbp), %rax
+    movl %eax, %eax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $7, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    seta %al
+    movzbq %al, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -16(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq 0(%rsp), %rax
+    movq %rax, %rsi
+    leaq str1(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $16, %rsp
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,62 @@
+.section __DATA,__data
+_fmt:
+    .asciz "%lld\n"
+_str0:
+    .asciz "Runtime error: line 3: division by zero\n"
+
+.section __TEXT,__text
+.globl _main
+.p2align 2
+_main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    cbz x0, L0
+    mov x0, #1
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    cbnz x0, L2
+    str x0, [sp, #-16]!
+    mov x0, #2
+    adrp x1, _str0@PAGE
+    add x1, x1, _str0@PAGEOFF
+    bl _dprintf
+    mov x0, #1
+    bl _exit
+L2:
+    ldr x1, [sp], #16
+    sdiv x0, x1, x0
+    cbz x0, L0
+    mov x0, #1
+    b L1
+L0:
+    mov x0, #0
+L1:
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    ldr x0, [x29, #-8]
+    cbnz x0, L3
+    ldr x0, [x29, #-8]
+    cmp x0, #0
+    cset x0, eq
+    cbnz x0, L3
+    mov x0, #0
+    b L4
+L3:
+    mov x0, #1
+L4:
+    str x0, [sp, #-16]!
+    adrp x0, _fmt@PAGE
+    add x0, x0, _fmt@PAGEOFF
+    bl _printf
+    add sp, sp, #16
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
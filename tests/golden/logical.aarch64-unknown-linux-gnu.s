@@ -0,0 +1,62 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "Runtime error: line 3: division by zero\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    cbz x0, .L0
+    mov x0, #1
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    cbnz x0, .L2
+    mov x2, x0
+    mov x0, #2
+    adrp x1, str0
+    add x1, x1, :lo12:str0
+    bl dprintf
+    mov x0, #1
+    bl exit
+.L2:
+    ldr x1, [sp], #16
+    sdiv x0, x1, x0
+    cbz x0, .L0
+    mov x0, #1
+    b .L1
+.L0:
+    mov x0, #0
+.L1:
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    ldr x0, [x29, #-8]
+    cbnz x0, .L3
+    ldr x0, [x29, #-8]
+    cmp x0, #0
+    cset x0, eq
+    cbnz x0, .L3
+    mov x0, #0
+    b .L4
+.L3:
+    mov x0, #1
+.L4:
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,77 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "Runtime error: line 3: division by zero\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq $0, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    testq %rax, %rax
+    jz .L0
+    movq $1, %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -8(%rbp), %rax
+    testq %rax, %rax
+    jnz .L2
+    movq %rax, %rdx
+    movl $2, %edi
+    leaq str0(%rip), %rsi
+    xorl %eax, %eax
+    call dprintf@PLT
+    movl $1, %edi
+    call exit@PLT
+.L2:
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    cmpq $-1, %rcx
+    jne 1f
+    negq %rax
+    jmp 2f
+1:
+    cqto
+    idivq %rcx
+2:
+    testq %rax, %rax
+    jz .L0
+    movq $1, %rax
+    jmp .L1
+.L0:
+    movq $0, %rax
+.L1:
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq -8(%rbp), %rax
+    testq %rax, %rax
+    jnz .L3
+    movq -8(%rbp), %rax
+    testq %rax, %rax
+    sete %al
+    movzbq %al, %rax
+    testq %rax, %rax
+    jnz .L3
+    movq $0, %rax
+    jmp .L4
+.L3:
+    movq $1, %rax
+.L4:
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,37 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "Hello, \"world\"!\n"
+str1:
+    .asciz "tab\there~\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    mov x1, x0
+    adrp x0, str0
+    add x0, x0, :lo12:str0
+    bl printf
+    mov x0, #42
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    mov x1, x0
+    adrp x0, str1
+    add x0, x0, :lo12:str1
+    bl printf
+    mov x1, x0
+    adrp x0, str0
+    add x0, x0, :lo12:str0
+    bl printf
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
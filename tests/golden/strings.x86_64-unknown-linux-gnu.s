@@ -0,0 +1,36 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+str0:
+    .asciz "Hello, \"world\"!\n"
+str1:
+    .asciz "tab\there~\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    movq %rax, %rsi
+    leaq str0(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $42, %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq %rax, %rsi
+    leaq str1(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq %rax, %rsi
+    leaq str0(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
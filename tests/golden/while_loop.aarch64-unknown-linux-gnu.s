@@ -0,0 +1,64 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+.p2align 2
+main:
+    stp x29, x30, [sp, #-16]!
+    mov x29, sp
+    sub sp, sp, #16
+    mov x0, #0
+    str x0, [x29, #-8]
+.L0:
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #10
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, lt
+    cbz x0, .L1
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #1
+    ldr x1, [sp], #16
+    add x0, x1, x0
+    str x0, [x29, #-8]
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    mov x0, #2
+    ldr x1, [sp], #16
+    sdiv x2, x1, x0
+    msub x0, x2, x0, x1
+    cbz x0, .L2
+    b .L0
+.L2:
+    ldr x0, [x29, #-8]
+    str x0, [sp, #-16]!
+    ldr x0, [x29, #-8]
+    ldr x1, [sp], #16
+    mul x0, x1, x0
+    str x0, [x29, #-16]
+    ldr x0, [x29, #-16]
+    str x0, [sp, #-16]!
+    mov x0, #50
+    ldr x1, [sp], #16
+    cmp x1, x0
+    cset x0, gt
+    cbz x0, .L3
+    b .L1
+.L3:
+    ldr x0, [x29, #-16]
+    mov x1, x0
+    adrp x0, fmt
+    add x0, x0, :lo12:fmt
+    bl printf
+    b .L0
+.L1:
+    mov x0, #0
+    mov sp, x29
+    ldp x29, x30, [sp], #16
+    ret
+
+.section .note.GNU-stack,"",@progbits
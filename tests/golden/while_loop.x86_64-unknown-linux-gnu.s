@@ -0,0 +1,86 @@
+.section .data
+fmt:
+    .asciz "%lld\n"
+
+.section .text
+.globl main
+main:
+    pushq %rbp
+    movq %rsp, %rbp
+    subq $16, %rsp
+    movq $0, %rax
+    movq %rax, -8(%rbp)
+.L0:
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $10, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setl %al
+    movzbq %al, %rax
+    testq %rax, %rax
+    jz .L1
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $1, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    addq %rcx, %rax
+    movq %rax, -8(%rbp)
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $2, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    xchgq %rax, %rcx
+    cmpq $-1, %rcx
+    jne 1f
+    xorl %eax, %eax
+    jmp 2f
+1:
+    cqto
+    idivq %rcx
+    movq %rdx, %rax
+2:
+    testq %rax, %rax
+    jz .L2
+    jmp .L0
+.L2:
+    movq -8(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq -8(%rbp), %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    imulq %rcx, %rax
+    movq %rax, -16(%rbp)
+    movq -16(%rbp), %rax
+    subq $16, %rsp
+    movq %rax, (%rsp)
+    movq $50, %rax
+    movq (%rsp), %rcx
+    addq $16, %rsp
+    cmpq %rax, %rcx
+    setg %al
+    movzbq %al, %rax
+    testq %rax, %rax
+    jz .L3
+    jmp .L1
+.L3:
+    movq -16(%rbp), %rax
+    movq %rax, %rsi
+    leaq fmt(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    jmp .L0
+.L1:
+    movq $0, %rax
+    movq %rbp, %rsp
+    popq %rbp
+    ret
+
+.section .note.GNU-stack,"",@progbits
@@ -1,12 +1,19 @@
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 static TEST_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 /// Compile a Toy program and run it, returning its stdout.
 fn run_toy(source: &str) -> String {
+    run_toy_with_input(source, "")
+}
+
+/// Compile a Toy program and run it with `input` as its stdin, returning its
+/// stdout.
+fn run_toy_with_input(source: &str, input: &str) -> String {
     let id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
     let tmp_dir = std::env::temp_dir().join(format!("toy_test_{}", id));
     fs::create_dir_all(&tmp_dir).unwrap();
@@ -37,9 +44,7 @@ fn run_toy(source: &str) -> String {
     );
 
     // Run the compiled executable
-    let run_output = Command::new(&exe_path)
-        .output()
-        .expect("failed to run compiled program");
+    let run_output = output_with_input(&mut Command::new(&exe_path), input);
 
     assert!(
         run_output.status.success(),
@@ -48,12 +53,215 @@ fn run_toy(source: &str) -> String {
         String::from_utf8_lossy(&run_output.stderr)
     );
 
+    // Run the same program through the interpreter; it must agree exactly
+    let interp_output = output_with_input(
+        Command::new(&compiler_path).args(["run", src_path.to_str().unwrap()]),
+        input,
+    );
+
+    assert!(
+        interp_output.status.success(),
+        "Interpretation failed for program:\n{}\nstderr: {}",
+        source,
+        String::from_utf8_lossy(&interp_output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&interp_output.stdout),
+        String::from_utf8_lossy(&run_output.stdout),
+        "Interpreter and compiled output differ for program:\n{}",
+        source
+    );
+
     // Clean up
     let _ = fs::remove_dir_all(&tmp_dir);
 
     String::from_utf8(run_output.stdout).unwrap()
 }
 
+/// Compile a Toy program and run it, expecting a runtime error: exit status
+/// 1 and a message on stderr. The interpreter must fail with the same output.
+/// Returns stdout and stderr.
+fn run_toy_failure(source: &str) -> (String, String) {
+    run_toy_failure_with_input(source, "")
+}
+
+/// Like `run_toy_failure`, with `input` as the program's stdin.
+fn run_toy_failure_with_input(source: &str, input: &str) -> (String, String) {
+    let id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let tmp_dir = std::env::temp_dir().join(format!("toy_test_{}", id));
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let src_path = tmp_dir.join("test.toy");
+    let exe_path = tmp_dir.join("test_exe");
+    fs::write(&src_path, source).unwrap();
+
+    let compiler_path = PathBuf::from(env!("CARGO_BIN_EXE_toy-compiler"));
+    let compile_output = Command::new(&compiler_path)
+        .args([
+            src_path.to_str().unwrap(),
+            "-o",
+            exe_path.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run toy-compiler");
+    assert!(
+        compile_output.status.success(),
+        "Compilation failed for program:\n{}\nstderr: {}",
+        source,
+        String::from_utf8_lossy(&compile_output.stderr)
+    );
+
+    let run_output = output_with_input(&mut Command::new(&exe_path), input);
+    assert_eq!(
+        run_output.status.code(),
+        Some(1),
+        "Expected exit status 1 for program:\n{}",
+        source
+    );
+
+    let interp_output = output_with_input(
+        Command::new(&compiler_path).args(["run", src_path.to_str().unwrap()]),
+        input,
+    );
+    assert_eq!(interp_output.status.code(), Some(1));
+    assert_eq!(
+        (&interp_output.stdout, &interp_output.stderr),
+        (&run_output.stdout, &run_output.stderr),
+        "Interpreter and compiled output differ for program:\n{}",
+        source
+    );
+
+    let _ = fs::remove_dir_all(&tmp_dir);
+
+    (
+        String::from_utf8(run_output.stdout).unwrap(),
+        String::from_utf8(run_output.stderr).unwrap(),
+    )
+}
+
+/// Run `command` with `input` as its stdin, returning its exit status and
+/// output.
+fn output_with_input(command: &mut Command, input: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start process");
+    // A program may exit without reading all of its input
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child
+        .wait_with_output()
+        .expect("failed to wait for process")
+}
+
+/// Run a Toy program with `toy-compiler run`, returning its exit status,
+/// stdout and stderr.
+fn interpret_toy(source: &str) -> (bool, String, String) {
+    let id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let tmp_dir = std::env::temp_dir().join(format!("toy_test_{}", id));
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let src_path = tmp_dir.join("test.toy");
+    fs::write(&src_path, source).unwrap();
+
+    let compiler_path = PathBuf::from(env!("CARGO_BIN_EXE_toy-compiler"));
+    let output = Command::new(&compiler_path)
+        .args(["run", src_path.to_str().unwrap()])
+        .output()
+        .expect("failed to run toy-compiler run");
+
+    let _ = fs::remove_dir_all(&tmp_dir);
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// Compile a Toy program that must be rejected before code generation,
+/// returning the error output, with the path of the source file replaced by
+/// `test.toy`. Running it with `run` must fail with the same error.
+fn compile_error(source: &str) -> String {
+    compile_error_with_args(source, &[])
+}
+
+/// Like `compile_error`, passing the compiler the extra options `args`.
+fn compile_error_with_args(source: &str, args: &[&str]) -> String {
+    let id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let tmp_dir = std::env::temp_dir().join(format!("toy_test_{}", id));
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let src_path = tmp_dir.join("test.toy");
+    let exe_path = tmp_dir.join("test_exe");
+    fs::write(&src_path, source).unwrap();
+    let src = src_path.to_str().unwrap();
+
+    let compiler_path = PathBuf::from(env!("CARGO_BIN_EXE_toy-compiler"));
+    let compile_output = Command::new(&compiler_path)
+        .args([src, "-o", exe_path.to_str().unwrap()])
+        .args(args)
+        .output()
+        .expect("failed to run toy-compiler");
+    let run_output = Command::new(&compiler_path)
+        .args(["run", src])
+        .args(args)
+        .output()
+        .expect("failed to run toy-compiler run");
+    let _ = fs::remove_dir_all(&tmp_dir);
+    assert!(
+        !compile_output.status.success(),
+        "Expected compilation to fail for program:\n{}",
+        source,
+    );
+    assert!(
+        !run_output.status.success(),
+        "Expected run to fail for program:\n{}",
+        source
+    );
+    assert_eq!(run_output.stdout, b"");
+    assert_eq!(
+        run_output.stderr, compile_output.stderr,
+        "compile and run errors differ"
+    );
+    String::from_utf8(compile_output.stderr)
+        .unwrap()
+        .replace(src, "test.toy")
+}
+
+/// Compile a Toy program to assembly for the host, returning the assembly.
+fn compile_to_asm(source: &str) -> String {
+    let id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let tmp_dir = std::env::temp_dir().join(format!("toy_test_{}", id));
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let src_path = tmp_dir.join("test.toy");
+    let asm_path = tmp_dir.join("test.s");
+    fs::write(&src_path, source).unwrap();
+
+    let compiler_path = PathBuf::from(env!("CARGO_BIN_EXE_toy-compiler"));
+    let output = Command::new(&compiler_path)
+        .args([
+            src_path.to_str().unwrap(),
+            "-S",
+            "-o",
+            asm_path.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run toy-compiler");
+    assert!(
+        output.status.success(),
+        "Compilation failed for program:\n{}\nstderr: {}",
+        source,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let asm = fs::read_to_string(&asm_path).unwrap();
+    let _ = fs::remove_dir_all(&tmp_dir);
+    asm
+}
+
 /// Compile a Toy program and expect compilation to fail.
 fn expect_compile_error(source: &str) {
     let id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
@@ -98,64 +306,2891 @@ fn zero() {
 }
 
 #[test]
-fn addition() {
-    assert_eq!(run_toy("print 2 + 3;"), "5\n");
+fn addition() {
+    assert_eq!(run_toy("print 2 + 3;"), "5\n");
+}
+
+#[test]
+fn subtraction() {
+    assert_eq!(run_toy("print 10 - 3;"), "7\n");
+}
+
+#[test]
+fn multiplication() {
+    assert_eq!(run_toy("print 6 * 7;"), "42\n");
+}
+
+#[test]
+fn division() {
+    assert_eq!(run_toy("print 7 / 2;"), "3\n");
+}
+
+#[test]
+fn modulo() {
+    assert_eq!(run_toy("print 10 % 3;"), "1\n");
+}
+
+#[test]
+fn precedence_mul_over_add() {
+    assert_eq!(run_toy("print 2 + 3 * 4;"), "14\n");
+}
+
+#[test]
+fn precedence_mul_over_sub() {
+    assert_eq!(run_toy("print 10 - 2 * 3;"), "4\n");
+}
+
+#[test]
+fn parentheses_override_precedence() {
+    assert_eq!(run_toy("print (2 + 3) * 4;"), "20\n");
+}
+
+#[test]
+fn left_associative_subtraction() {
+    assert_eq!(run_toy("print 10 - 3 - 2;"), "5\n");
+}
+
+#[test]
+fn left_associative_division() {
+    assert_eq!(run_toy("print 24 / 4 / 2;"), "3\n");
+}
+
+#[test]
+fn nested_parentheses() {
+    assert_eq!(run_toy("print ((2 + 3) * (4 + 1));"), "25\n");
+}
+
+#[test]
+fn complex_expression() {
+    // 1 + (2*3) - (4/2) + 5 = 1 + 6 - 2 + 5 = 10
+    assert_eq!(run_toy("print 1 + 2 * 3 - 4 / 2 + 5;"), "10\n");
+}
+
+// ==================== Comparison tests ====================
+
+#[test]
+fn comparisons_true() {
+    let src = "\
+print 3 == 3;
+print 3 != 4;
+print 3 < 4;
+print 3 <= 3;
+print 4 > 3;
+print 3 >= 3;
+";
+    assert_eq!(run_toy(src), "1\n1\n1\n1\n1\n1\n");
+}
+
+#[test]
+fn comparisons_false() {
+    let src = "\
+print 3 == 4;
+print 3 != 3;
+print 4 < 3;
+print 4 <= 3;
+print 3 > 4;
+print 3 >= 4;
+";
+    assert_eq!(run_toy(src), "0\n0\n0\n0\n0\n0\n");
+}
+
+#[test]
+fn comparison_is_signed() {
+    assert_eq!(run_toy("print -1 < 1;\nprint -9223372036854775807 - 1 < 0;"), "1\n1\n");
+}
+
+#[test]
+fn comparison_binds_looser_than_arithmetic() {
+    // (1 + 2) == (6 / 2)
+    assert_eq!(run_toy("print 1 + 2 == 6 / 2;"), "1\n");
+}
+
+#[test]
+fn comparison_is_left_associative() {
+    // (3 > 2) > 1 is 1 > 1, which is false
+    assert_eq!(run_toy("print 3 > 2 > 1;"), "0\n");
+}
+
+#[test]
+fn comparison_result_is_an_integer() {
+    assert_eq!(run_toy("let x = 5;\nprint (x > 2) * 10 + (x < 2);"), "10\n");
+}
+
+#[test]
+fn comparison_of_bools_is_a_bool() {
+    let src = "let big = true;\nprint big == false;\nprint (big != false) == big;";
+    assert_eq!(run_toy(src), "false\ntrue\n");
+}
+
+#[test]
+fn comparison_tokens_without_spaces() {
+    assert_eq!(run_toy("let x=2;\nprint x==2;\nprint x!=2;\nprint x<=2;"), "1\n0\n1\n");
+}
+
+#[test]
+fn error_bang_after_operand() {
+    // `!` is a prefix operator only
+    expect_compile_error("print 3!;");
+}
+
+#[test]
+fn error_assignment_is_not_comparison() {
+    // `x = 1 = 2` is not valid: `=` is only the assignment statement
+    expect_compile_error("let x = 0;\nx = 1 = 2;");
+}
+
+// ==================== If / else tests ====================
+
+#[test]
+fn if_true_runs_then_block() {
+    assert_eq!(run_toy("if 1 { print 10; }\nprint 20;"), "10\n20\n");
+}
+
+#[test]
+fn if_false_skips_then_block() {
+    assert_eq!(run_toy("if 0 { print 10; }\nprint 20;"), "20\n");
+}
+
+#[test]
+fn if_any_nonzero_is_true() {
+    assert_eq!(run_toy("if -7 { print 1; } else { print 0; }"), "1\n");
+}
+
+#[test]
+fn if_else() {
+    let src = "\
+let x = 3;
+if x > 5 {
+    print 1;
+} else {
+    print 2;
+}
+";
+    assert_eq!(run_toy(src), "2\n");
+}
+
+#[test]
+fn else_if_chain() {
+    let program = |x: i64| {
+        format!(
+            "let x = {x};\n\
+             if x < 0 {{ print -1; }} else if x == 0 {{ print 0; }} else {{ print 1; }}\n"
+        )
+    };
+    assert_eq!(run_toy(&program(-5)), "-1\n");
+    assert_eq!(run_toy(&program(0)), "0\n");
+    assert_eq!(run_toy(&program(5)), "1\n");
+}
+
+#[test]
+fn nested_if() {
+    let src = "\
+let a = 1;
+let b = 0;
+if a {
+    if b { print 11; } else { print 10; }
+} else {
+    print 0;
+}
+";
+    assert_eq!(run_toy(src), "10\n");
+}
+
+#[test]
+fn empty_blocks() {
+    assert_eq!(run_toy("if 1 {} else {}\nprint 5;"), "5\n");
+}
+
+#[test]
+fn assignment_in_branch_updates_outer_variable() {
+    assert_eq!(run_toy("let x = 1;\nif 1 { x = x + 41; }\nprint x;"), "42\n");
+}
+
+#[test]
+fn let_in_branch_shadows_until_end_of_block() {
+    let src = "\
+let x = 1;
+if 1 {
+    let x = 2;
+    print x;
+    x = 3;
+    print x;
+}
+print x;
+";
+    assert_eq!(run_toy(src), "2\n3\n1\n");
+}
+
+#[test]
+fn error_let_in_branch_not_visible_after() {
+    expect_compile_error("if 1 { let y = 1; }\nprint y;");
+}
+
+#[test]
+fn error_if_without_braces() {
+    expect_compile_error("if 1 print 1;");
+}
+
+#[test]
+fn error_unclosed_block() {
+    expect_compile_error("if 1 { print 1;");
+}
+
+#[test]
+fn error_else_without_if() {
+    expect_compile_error("else { print 1; }");
+}
+
+#[test]
+fn lets_in_branches_share_slots() {
+    // 31 top-level lets plus one in each branch: only one branch's variable
+    // is live at a time, so this needs 32 slots
+    let mut src = String::new();
+    for i in 0..31 {
+        src.push_str(&format!("let v{i} = {i};\n"));
+    }
+    src.push_str("if v0 { let a = 1; print a; } else { let b = 2; print b; }\n");
+    assert_eq!(run_toy(&src), "2\n");
+}
+
+#[test]
+fn lets_in_branches_count_toward_variable_limit() {
+    // A branch that never runs still needs its slot: 32 + 1 is too many
+    let mut src = String::new();
+    for i in 0..32 {
+        src.push_str(&format!("let v{i} = {i};\n"));
+    }
+    src.push_str("if v0 { let a = 1; }\n");
+    expect_compile_error(&src);
+}
+
+// ==================== While loop tests ====================
+
+#[test]
+fn while_countdown() {
+    let src = "\
+let n = 3;
+while n > 0 {
+    print n;
+    n = n - 1;
+}
+";
+    assert_eq!(run_toy(src), "3\n2\n1\n");
+}
+
+#[test]
+fn while_false_never_runs() {
+    assert_eq!(run_toy("while 0 { print 1; }\nprint 2;"), "2\n");
+}
+
+#[test]
+fn fibonacci_loop() {
+    let src = "\
+let a = 0;
+let b = 1;
+let i = 0;
+while i < 10 {
+    print a;
+    let c = a + b;
+    a = b;
+    b = c;
+    i = i + 1;
+}
+";
+    assert_eq!(run_toy(src), "0\n1\n1\n2\n3\n5\n8\n13\n21\n34\n");
+}
+
+#[test]
+fn while_break() {
+    let src = "\
+let i = 0;
+while 1 {
+    if i == 3 { break; }
+    print i;
+    i = i + 1;
+}
+print 100;
+";
+    assert_eq!(run_toy(src), "0\n1\n2\n100\n");
+}
+
+#[test]
+fn while_continue() {
+    let src = "\
+let i = 0;
+while i < 6 {
+    i = i + 1;
+    if i % 2 { continue; }
+    print i;
+}
+";
+    assert_eq!(run_toy(src), "2\n4\n6\n");
+}
+
+#[test]
+fn break_exits_innermost_loop_only() {
+    let src = "\
+let i = 0;
+while i < 3 {
+    let j = 0;
+    while 1 {
+        if j == i { break; }
+        j = j + 1;
+    }
+    print j;
+    i = i + 1;
+}
+";
+    assert_eq!(run_toy(src), "0\n1\n2\n");
+}
+
+#[test]
+fn let_in_loop_body_reuses_its_slot() {
+    // 31 top-level lets plus one inside a loop that runs 1000 times
+    let mut src = String::new();
+    for i in 0..31 {
+        src.push_str(&format!("let v{i} = {i};\n"));
+    }
+    src.push_str("let total = 0;\n");
+    src.push_str("while v1 <= 1000 {\n    let t = v1 * 2;\n    total = total + t;\n    v1 = v1 + 1;\n}\n");
+    src.push_str("print total;\n");
+    expect_compile_error(&src);
+    // ... which is one too many; with 30 top-level lets it fits
+    let src = src.replacen("let v30 = 30;\n", "", 1);
+    assert_eq!(run_toy(&src), "1001000\n");
+}
+
+#[test]
+fn error_break_outside_loop() {
+    expect_compile_error("break;");
+}
+
+#[test]
+fn error_continue_outside_loop() {
+    expect_compile_error("if 1 { continue; }");
+}
+
+#[test]
+fn error_break_without_semicolon() {
+    expect_compile_error("while 1 { break }");
+}
+
+// ==================== Function tests ====================
+
+#[test]
+fn function_call_returns_value() {
+    let src = "\
+fn answer() {
+    return 42;
+}
+print answer();
+";
+    assert_eq!(run_toy(src), "42\n");
+}
+
+#[test]
+fn function_parameters() {
+    let src = "\
+fn sub(a, b) {
+    return a - b;
+}
+print sub(10, 3);
+print sub(3, 10);
+";
+    assert_eq!(run_toy(src), "7\n-7\n");
+}
+
+#[test]
+fn function_six_parameters() {
+    let src = "\
+fn weigh(a, b, c, d, e, f) {
+    return a + 2 * b + 3 * c + 4 * d + 5 * e + 6 * f;
+}
+print weigh(1, 10, 100, 1000, 10000, 100000);
+";
+    assert_eq!(run_toy(src), "654321\n");
+}
+
+#[test]
+fn function_arguments_evaluated_left_to_right() {
+    let src = "\
+fn show(x) {
+    print x;
+    return x;
+}
+fn pair(a, b) {
+    return a * 10 + b;
+}
+print pair(show(1), show(2));
+";
+    assert_eq!(run_toy(src), "1\n2\n12\n");
+}
+
+#[test]
+fn function_recursion_factorial() {
+    let src = "\
+fn fact(n) {
+    if n <= 1 { return 1; }
+    return n * fact(n - 1);
+}
+print fact(10);
+print fact(20);
+";
+    assert_eq!(run_toy(src), "3628800\n2432902008176640000\n");
+}
+
+#[test]
+fn function_recursion_fibonacci() {
+    let src = "\
+fn fib(n) {
+    if n < 2 { return n; }
+    return fib(n - 1) + fib(n - 2);
+}
+print fib(20);
+";
+    assert_eq!(run_toy(src), "6765\n");
+}
+
+#[test]
+fn function_mutual_recursion() {
+    // Functions may be called before they are declared
+    let src = "\
+print is_even(10);
+print is_even(7);
+fn is_even(n) {
+    if n == 0 { return 1; }
+    return is_odd(n - 1);
+}
+fn is_odd(n) {
+    if n == 0 { return 0; }
+    return is_even(n - 1);
+}
+";
+    assert_eq!(run_toy(src), "1\n0\n");
+}
+
+#[test]
+fn function_call_as_statement() {
+    let src = "\
+fn greet(n) {
+    print n;
+    return 0;
+}
+greet(5);
+greet(6);
+";
+    assert_eq!(run_toy(src), "5\n6\n");
+}
+
+#[test]
+fn function_without_return_returns_zero() {
+    let src = "\
+fn nothing(x) {
+    let y = x + 1;
+}
+print nothing(3);
+";
+    assert_eq!(run_toy(src), "0\n");
+}
+
+#[test]
+fn function_return_from_inside_loop() {
+    let src = "\
+fn first_multiple(n, k) {
+    let i = 1;
+    while 1 {
+        if (i * k) % n == 0 { return i * k; }
+        i = i + 1;
+    }
+}
+print first_multiple(7, 3);
+";
+    assert_eq!(run_toy(src), "21\n");
+}
+
+#[test]
+fn function_locals_are_separate_from_callers() {
+    let src = "\
+let x = 1;
+fn set(v) {
+    let x = v;
+    return x;
+}
+print set(99);
+print x;
+";
+    assert_eq!(run_toy(src), "99\n1\n");
+}
+
+#[test]
+fn function_parameters_are_mutable_copies() {
+    let src = "\
+fn count_down(n) {
+    while n > 0 { n = n - 1; }
+    return n;
+}
+let n = 5;
+print count_down(n);
+print n;
+";
+    assert_eq!(run_toy(src), "0\n5\n");
+}
+
+#[test]
+fn function_has_own_variable_limit() {
+    // 30 lets at the top level and 30 in the function: each frame fits
+    let mut src = String::from("fn f(p) {\n");
+    for i in 0..30 {
+        src.push_str(&format!("    let v{i} = p + {i};\n"));
+    }
+    src.push_str("    return v29;\n}\n");
+    for i in 0..30 {
+        src.push_str(&format!("let v{i} = {i};\n"));
+    }
+    src.push_str("print f(v29);\n");
+    assert_eq!(run_toy(&src), "58\n");
+}
+
+#[test]
+fn error_function_too_many_variables() {
+    let mut src = String::from("fn f(a, b) {\n");
+    for i in 0..31 {
+        src.push_str(&format!("    let v{i} = {i};\n"));
+    }
+    src.push_str("}\n");
+    expect_compile_error(&src);
+}
+
+#[test]
+fn error_undefined_function() {
+    expect_compile_error("print nope(1);");
+}
+
+#[test]
+fn error_function_arity_mismatch() {
+    expect_compile_error("fn f(a, b) { return a; }\nprint f(1);");
+    expect_compile_error("fn f(a, b) { return a; }\nprint f(1, 2, 3);");
+}
+
+#[test]
+fn error_return_outside_function() {
+    expect_compile_error("return 1;");
+}
+
+#[test]
+fn error_nested_function() {
+    expect_compile_error("fn outer() {\n    fn inner() { return 1; }\n}");
+}
+
+#[test]
+fn error_duplicate_parameter() {
+    expect_compile_error("fn f(a, a) { return a; }");
+}
+
+#[test]
+fn error_duplicate_function() {
+    expect_compile_error("fn f() { return 1; }\nfn f() { return 2; }");
+}
+
+#[test]
+fn error_too_many_parameters() {
+    expect_compile_error("fn f(a, b, c, d, e, f, g) { return a; }");
+}
+
+#[test]
+fn error_function_cannot_see_globals() {
+    expect_compile_error("let x = 1;\nfn f() { return x; }");
+}
+
+#[test]
+fn error_call_statement_with_more_than_a_call() {
+    assert_eq!(
+        compile_error("fn f(x) { return x; }\nf(1) + 2;"),
+        "\
+error[E0213]: only a call can be used as a statement
+ --> test.toy:2:1
+  |
+2 | f(1) + 2;
+  | ^^^^^^^^
+  |
+  = help: to use the value, assign it to a variable with 'let'
+"
+    );
+    expect_compile_error("fn f(x) { return x; }\nf(1) as u8;");
+    expect_compile_error("input() == 0;");
+}
+
+#[test]
+fn error_break_does_not_cross_function() {
+    expect_compile_error("while 1 {\n    print 1;\n}\nfn f() { break; }");
+}
+
+// ==================== Block tests ====================
+
+#[test]
+fn block_shadowing_ends_at_closing_brace() {
+    let src = "\
+let x = 1;
+{
+    let x = 2;
+    print x;
+}
+print x;
+";
+    assert_eq!(run_toy(src), "2\n1\n");
+}
+
+#[test]
+fn block_assigns_outer_variable() {
+    let src = "\
+let x = 1;
+{
+    x = x + 10;
+    let y = x * 2;
+    print y;
+}
+print x;
+";
+    assert_eq!(run_toy(src), "22\n11\n");
+}
+
+#[test]
+fn nested_blocks() {
+    let src = "\
+let x = 1;
+{
+    let x = x + 1;
+    {
+        let x = x + 1;
+        print x;
+    }
+    print x;
+}
+print x;
+";
+    assert_eq!(run_toy(src), "3\n2\n1\n");
+}
+
+#[test]
+fn empty_block() {
+    assert_eq!(run_toy("{}\n{ {} }\nprint 7;"), "7\n");
+}
+
+#[test]
+fn block_inside_loop_with_break() {
+    let src = "\
+let i = 0;
+while 1 {
+    {
+        let next = i + 1;
+        if next > 3 { break; }
+        i = next;
+    }
+    print i;
+}
+";
+    assert_eq!(run_toy(src), "1\n2\n3\n");
+}
+
+#[test]
+fn sequential_blocks_reuse_slots() {
+    // 31 top-level lets plus 10 blocks of 1 let each: at most 32 are live
+    let mut src = String::new();
+    for i in 0..31 {
+        src.push_str(&format!("let v{i} = {i};\n"));
+    }
+    for i in 0..10 {
+        src.push_str(&format!("{{\n    let b = v{i} * 100;\n    print b;\n}}\n"));
+    }
+    src.push_str("print v30;\n");
+    let expected: String = (0..10).map(|i| format!("{}\n", i * 100)).collect();
+    assert_eq!(run_toy(&src), expected + "30\n");
+}
+
+#[test]
+fn freed_slot_holds_new_value() {
+    // The second block's variable reuses the first one's slot
+    let src = "\
+{ let a = 5; print a; }
+{ let b = 6; print b; }
+let c = 7;
+print c;
+";
+    assert_eq!(run_toy(src), "5\n6\n7\n");
+}
+
+#[test]
+fn error_nested_blocks_exceed_variable_limit() {
+    // Nested blocks keep their variables live: 30 + 2 + 1 = 33
+    let mut src = String::new();
+    for i in 0..30 {
+        src.push_str(&format!("let v{i} = {i};\n"));
+    }
+    src.push_str("{\n    let a = 1;\n    let b = 2;\n    { let c = 3; }\n}\n");
+    expect_compile_error(&src);
+}
+
+#[test]
+fn error_block_variable_out_of_scope() {
+    expect_compile_error("{ let y = 1; }\nprint y;");
+}
+
+#[test]
+fn error_unclosed_standalone_block() {
+    expect_compile_error("{ print 1;");
+}
+
+// ==================== Logical operator tests ====================
+
+#[test]
+fn logical_and_truth_table() {
+    assert_eq!(
+        run_toy("print 0 && 0;\nprint 0 && 1;\nprint 1 && 0;\nprint 1 && 1;"),
+        "0\n0\n0\n1\n"
+    );
+}
+
+#[test]
+fn logical_or_truth_table() {
+    assert_eq!(
+        run_toy("print 0 || 0;\nprint 0 || 1;\nprint 1 || 0;\nprint 1 || 1;"),
+        "0\n1\n1\n1\n"
+    );
+}
+
+#[test]
+fn logical_operators_produce_zero_or_one() {
+    assert_eq!(
+        run_toy("print 5 && 7;\nprint 0 || -3;\nprint -1 && 2 || 0;"),
+        "1\n1\n1\n"
+    );
+}
+
+#[test]
+fn logical_not() {
+    assert_eq!(
+        run_toy("print !0;\nprint !5;\nprint !!5;\nprint !-1;"),
+        "1\n0\n1\n0\n"
+    );
+}
+
+#[test]
+fn logical_precedence() {
+    // && binds tighter than ||, comparisons bind tighter than both,
+    // and ! binds tighter than everything
+    let src = "\
+print 1 || 0 && 0;
+print (1 || 0) && 0;
+print 1 < 2 && 2 < 3;
+print !1 == 0;
+print !(1 == 0);
+print !0 + 1;
+";
+    assert_eq!(run_toy(src), "1\n0\n1\n1\n1\n2\n");
+}
+
+#[test]
+fn logical_and_short_circuits() {
+    // The right operand would divide by zero if it were evaluated
+    assert_eq!(run_toy("let x = 0;\nprint x && 1 / x;"), "0\n");
+}
+
+#[test]
+fn logical_or_short_circuits() {
+    assert_eq!(run_toy("let x = 0;\nprint 1 || 1 % x;"), "1\n");
+}
+
+#[test]
+fn logical_right_operand_side_effects() {
+    let src = "\
+fn show(x) {
+    print x;
+    return x;
+}
+let a = show(0) && show(1);
+let b = show(2) && show(3);
+let c = show(4) || show(5);
+let d = show(0) || show(6);
+print a + b * 10 + c * 100 + d * 1000;
+";
+    assert_eq!(run_toy(src), "0\n2\n3\n4\n0\n6\n1110\n");
+}
+
+#[test]
+fn logical_operators_in_conditions() {
+    let src = "\
+let i = 0;
+while i < 20 && !(i > 0 && i % 7 == 0) {
+    i = i + 1;
+}
+print i;
+if i == 7 || i == 14 { print 1; } else { print 0; }
+";
+    assert_eq!(run_toy(src), "7\n1\n");
+}
+
+#[test]
+fn error_not_without_operand() {
+    expect_compile_error("print !;");
+}
+
+#[test]
+fn error_logical_and_missing_right_operand() {
+    expect_compile_error("print 1 &&;");
+}
+
+// ==================== Bitwise operator tests ====================
+
+#[test]
+fn bitwise_and_or_xor() {
+    assert_eq!(
+        run_toy("print 12 & 10;\nprint 12 | 10;\nprint 12 ^ 10;\nprint -1 & 255;"),
+        "8\n14\n6\n255\n"
+    );
+}
+
+#[test]
+fn bitwise_not() {
+    assert_eq!(
+        run_toy("print ~0;\nprint ~5;\nprint ~-1;\nprint ~9223372036854775807;"),
+        "-1\n-6\n0\n-9223372036854775808\n"
+    );
+}
+
+#[test]
+fn shift_left_and_right() {
+    assert_eq!(
+        run_toy("print 1 << 10;\nprint 1024 >> 3;\nprint 3 << 62;"),
+        "1024\n128\n-4611686018427387904\n"
+    );
+}
+
+#[test]
+fn shift_right_is_arithmetic() {
+    // The sign bit is copied into the vacated high bits
+    assert_eq!(
+        run_toy("print -16 >> 2;\nprint -1 >> 63;\nprint -9223372036854775807 - 1 >> 63;"),
+        "-4\n-1\n-1\n"
+    );
+}
+
+#[test]
+fn shift_by_zero_and_63() {
+    assert_eq!(
+        run_toy("print 5 << 0;\nprint 5 >> 0;\nprint 1 << 63;\nprint 4611686018427387904 >> 62;"),
+        "5\n5\n-9223372036854775808\n1\n"
+    );
+}
+
+#[test]
+fn shift_count_uses_low_six_bits() {
+    // Counts outside 0..63 are taken modulo 64: 64 is 0, 65 is 1 and -1 is 63
+    let src = "\
+let n = 64;
+print 5 << n;
+print 5 >> n;
+print 5 << n + 1;
+print 1 << -1;
+print -8 >> 66;
+";
+    assert_eq!(run_toy(src), "5\n5\n10\n-9223372036854775808\n-2\n");
+}
+
+#[test]
+fn bitwise_precedence() {
+    // As in Rust: shifts bind looser than + and -, then &, ^, | in that
+    // order, all tighter than comparisons
+    let src = "\
+print 1 << 2 + 1;
+print 6 & 3 ^ 5;
+print 1 | 2 ^ 3;
+print 1 | 4 & 5;
+print 6 & 3 == 2;
+print 1 << 3 > 7;
+print ~1 + 1;
+";
+    assert_eq!(run_toy(src), "8\n7\n1\n5\n1\n1\n-1\n");
+}
+
+#[test]
+fn bit_tricks() {
+    let src = "\
+fn popcount(x) {
+    let n = 0;
+    while x != 0 {
+        x = x & (x - 1);
+        n = n + 1;
+    }
+    return n;
+}
+print popcount(255);
+print popcount(-1);
+let x = 40;
+print x & -x;
+print (x ^ (x >> 63)) - (x >> 63);
+";
+    assert_eq!(run_toy(src), "8\n64\n8\n40\n");
+}
+
+#[test]
+fn shift_tokens_are_maximal_munch() {
+    // `<<` and `>>` are single tokens; `< <` is a syntax error
+    assert_eq!(run_toy("print 1<<2;\nprint 8>>1;\nprint 1<=2;"), "4\n4\n1\n");
+    expect_compile_error("print 1 < < 2;");
+}
+
+#[test]
+fn error_bitwise_missing_operand() {
+    expect_compile_error("print 1 &;");
+    expect_compile_error("print | 1;");
+}
+
+// ==================== Compound assignment tests ====================
+
+#[test]
+fn compound_assignment_arithmetic() {
+    let src = "\
+let x = 10;
+x += 5;
+print x;
+x -= 3;
+print x;
+x *= 4;
+print x;
+x /= 5;
+print x;
+x %= 5;
+print x;
+";
+    assert_eq!(run_toy(src), "15\n12\n48\n9\n4\n");
+}
+
+#[test]
+fn compound_assignment_bitwise() {
+    let src = "\
+let x = 12;
+x &= 10;
+print x;
+x |= 5;
+print x;
+x ^= 3;
+print x;
+x <<= 4;
+print x;
+x >>= 2;
+print x;
+";
+    assert_eq!(run_toy(src), "8\n13\n14\n224\n56\n");
+}
+
+#[test]
+fn compound_assignment_right_side_is_whole_expression() {
+    // `x *= 2 + 3` is `x = x * (2 + 3)`, not `x = x * 2 + 3`
+    assert_eq!(run_toy("let x = 2;\nx *= 2 + 3;\nprint x;"), "10\n");
+    assert_eq!(run_toy("let x = 100;\nx -= 10 - 1;\nprint x;"), "91\n");
+}
+
+#[test]
+fn compound_assignment_uses_variable_on_right() {
+    assert_eq!(run_toy("let x = 7;\nx += x;\nx *= x - 4;\nprint x;"), "140\n");
+}
+
+#[test]
+fn compound_assignment_wraps() {
+    assert_eq!(
+        run_toy("let x = 9223372036854775807;\nx += 1;\nprint x;"),
+        "-9223372036854775808\n"
+    );
+}
+
+#[test]
+fn compound_assignment_in_loop_and_function() {
+    let src = "\
+fn sum_to(n) {
+    let total = 0;
+    while n > 0 {
+        total += n;
+        n -= 1;
+    }
+    return total;
+}
+print sum_to(100);
+";
+    assert_eq!(run_toy(src), "5050\n");
+}
+
+#[test]
+fn compound_assignment_targets_innermost_variable() {
+    let src = "\
+let x = 1;
+{
+    let x = 10;
+    x += 1;
+    print x;
+}
+x += 1;
+print x;
+";
+    assert_eq!(run_toy(src), "11\n2\n");
+}
+
+#[test]
+fn compound_assignment_tokens_without_spaces() {
+    assert_eq!(run_toy("let x=3;\nx+=-1;\nx<<=3;\nprint x;"), "16\n");
+}
+
+#[test]
+fn error_compound_assignment_undeclared() {
+    expect_compile_error("y += 1;");
+    expect_compile_error("{ let y = 1; }\ny <<= 1;");
+}
+
+#[test]
+fn error_compound_assignment_split_token() {
+    // Compound operators are single tokens
+    expect_compile_error("let x = 1;\nx + = 1;");
+}
+
+#[test]
+fn error_compound_assignment_is_not_expression() {
+    expect_compile_error("let x = 1;\nprint x += 1;");
+}
+
+// ==================== Integer literal syntax tests ====================
+
+#[test]
+fn hex_binary_octal_literals() {
+    assert_eq!(
+        run_toy("print 0xff;\nprint 0xDeadBeef;\nprint 0b1010;\nprint 0o777;\nprint 0x0;"),
+        "255\n3735928559\n10\n511\n0\n"
+    );
+}
+
+#[test]
+fn literal_digit_separators() {
+    assert_eq!(
+        run_toy("print 1_000_000;\nprint 0xFFFF_FFFF;\nprint 0b1111_0000;\nprint 1_;\nprint 0x_10;"),
+        "1000000\n4294967295\n240\n1\n16\n"
+    );
+}
+
+#[test]
+fn decimal_literal_leading_zeros() {
+    // A leading zero does not mean octal
+    assert_eq!(run_toy("print 007;\nprint 0010;"), "7\n10\n");
+}
+
+#[test]
+fn prefixed_literal_maximum() {
+    assert_eq!(
+        run_toy("print 0x7FFF_FFFF_FFFF_FFFF;\nprint 0o777777777777777777777;"),
+        "9223372036854775807\n9223372036854775807\n"
+    );
+}
+
+#[test]
+fn negated_minimum_literal() {
+    // 9223372036854775808 is only allowed directly after unary minus
+    let src = "\
+print -9223372036854775808;
+print -0x8000_0000_0000_0000;
+print -0b1000000000000000000000000000000000000000000000000000000000000000;
+print --9223372036854775808;
+print -9223372036854775808 + 1;
+print 1 - -9223372036854775808;
+";
+    assert_eq!(
+        run_toy(src),
+        "-9223372036854775808\n-9223372036854775808\n-9223372036854775808\n\
+         -9223372036854775808\n-9223372036854775807\n-9223372036854775807\n"
+    );
+}
+
+#[test]
+fn literals_in_expressions() {
+    assert_eq!(run_toy("let mask = 0xF0;\nprint 0xAB & mask | 0b101;"), "165\n");
+}
+
+#[test]
+fn error_prefixed_literal_out_of_range() {
+    expect_compile_error("print 0x8000_0000_0000_0000;");
+    expect_compile_error("print 0xFFFF_FFFF_FFFF_FFFF;");
+    expect_compile_error("print -0x1_0000_0000_0000_0000;");
+    expect_compile_error("print -0x8000_0000_0000_0001;");
+}
+
+#[test]
+fn error_minimum_literal_without_minus() {
+    // Binary minus does not make the literal negative
+    expect_compile_error("print 0 - 9223372036854775808;");
+    expect_compile_error("print (9223372036854775808);");
+}
+
+#[test]
+fn error_invalid_digit_for_radix() {
+    assert_eq!(
+        compile_error("print 0b102;"),
+        "\
+error[E0101]: invalid digit '2' in binary literal '0b102'
+ --> test.toy:1:11
+  |
+1 | print 0b102;
+  |           ^
+"
+    );
+    expect_compile_error("print 0o8;");
+    expect_compile_error("print 0xfg;");
+    // Prefixes are lowercase only
+    expect_compile_error("print 0X10;");
+}
+
+#[test]
+fn error_prefix_without_digits() {
+    let (ok, _, stderr) = interpret_toy("print 0x;");
+    assert!(!ok);
+    assert!(
+        stderr.contains("hexadecimal literal '0x' has no digits"),
+        "stderr: {stderr}"
+    );
+    expect_compile_error("print 0b_;");
+}
+
+#[test]
+fn error_literal_followed_by_letter() {
+    assert_eq!(
+        compile_error("let x = 1;\nprint 3x;"),
+        "\
+error[E0101]: invalid digit 'x' in decimal literal '3x'
+ --> test.toy:2:8
+  |
+2 | print 3x;
+  |        ^
+"
+    );
+}
+
+// ==================== String tests ====================
+
+#[test]
+fn print_string() {
+    assert_eq!(run_toy("print \"Hello, world!\";"), "Hello, world!\n");
+}
+
+#[test]
+fn print_empty_string() {
+    assert_eq!(run_toy("print \"\";\nprint 1;"), "\n1\n");
+}
+
+#[test]
+fn string_escapes() {
+    let src = r#"
+print "tab\there";
+print "two\nlines";
+print "say \"hi\"";
+print "back\\slash";
+print "\x41\x62\x7e";
+"#;
+    assert_eq!(
+        run_toy(src),
+        "tab\there\ntwo\nlines\nsay \"hi\"\nback\\slash\nAb~\n"
+    );
+}
+
+#[test]
+fn string_with_percent_is_printed_verbatim() {
+    // The text is not used as a printf format
+    assert_eq!(run_toy("print \"100% %d %s %%\";"), "100% %d %s %%\n");
+}
+
+#[test]
+fn string_with_non_ascii_characters() {
+    assert_eq!(run_toy("print \"héllo → wörld\";"), "héllo → wörld\n");
+}
+
+#[test]
+fn string_with_comment_marker() {
+    assert_eq!(run_toy("print \"a // b\"; // comment"), "a // b\n");
+}
+
+#[test]
+fn strings_and_numbers_interleaved() {
+    let src = "\
+let i = 1;
+while i <= 3 {
+    print \"i is\";
+    print i;
+    i += 1;
+}
+print \"done\";
+";
+    assert_eq!(run_toy(src), "i is\n1\ni is\n2\ni is\n3\ndone\n");
+}
+
+#[test]
+fn repeated_string_literal_emitted_once() {
+    let src = "print \"same\";\nprint \"other\";\nprint \"same\";\n";
+    let asm = compile_to_asm(src);
+    assert_eq!(asm.matches(".asciz \"same\\n\"").count(), 1, "asm:\n{asm}");
+    assert_eq!(asm.matches(".asciz \"other\\n\"").count(), 1, "asm:\n{asm}");
+    assert_eq!(run_toy(src), "same\nother\nsame\n");
+}
+
+#[test]
+fn error_unterminated_string() {
+    assert_eq!(
+        compile_error("print \"abc;\nprint 1;"),
+        "\
+error[E0103]: unterminated string literal
+ --> test.toy:1:7
+  |
+1 | print \"abc;
+  |       ^^^^^
+"
+    );
+    expect_compile_error("print \"abc");
+    expect_compile_error("print \"abc\\");
+}
+
+#[test]
+fn error_unknown_escape() {
+    assert_eq!(
+        compile_error("print \"a\\qb\";"),
+        "\
+error[E0105]: unknown escape sequence '\\q'
+ --> test.toy:1:9
+  |
+1 | print \"a\\qb\";
+  |         ^^
+"
+    );
+}
+
+#[test]
+fn error_bad_hex_escape() {
+    // NUL would end the string early, and only ASCII can be escaped
+    let (ok, _, stderr) = interpret_toy("print \"\\x00\";");
+    assert!(!ok);
+    assert!(stderr.contains("NUL character in string literal"), "stderr: {stderr}");
+    let (ok, _, stderr) = interpret_toy("print \"\\x80\";");
+    assert!(!ok);
+    assert!(stderr.contains("'\\x80' is out of range"), "stderr: {stderr}");
+    expect_compile_error("print \"\\x4\";");
+    expect_compile_error("print \"\\xg0\";");
+}
+
+#[test]
+fn error_string_in_expression() {
+    expect_compile_error("let s = \"text\";");
+    expect_compile_error("print \"a\" + 1;");
+    expect_compile_error("print 1 + \"a\";");
+}
+
+// ==================== Formatted print tests ====================
+
+#[test]
+fn format_decimal_and_hex() {
+    let src = "let x = 3;\nlet y = 255;\nprint \"x = {}, y = {:x}\", x, y;";
+    assert_eq!(run_toy(src), "x = 3, y = ff\n");
+}
+
+#[test]
+fn format_radixes() {
+    let src = "print \"{} {:x} {:X} {:b}\", 46, 46, 46, 46;";
+    assert_eq!(run_toy(src), "46 2e 2E 101110\n");
+}
+
+#[test]
+fn format_negative_values() {
+    // Hexadecimal and binary show the 64-bit two's complement
+    let src = "print \"{} {:x} {:X}\", -1, -1, -2;\nprint \"{:b}\", -6;";
+    assert_eq!(
+        run_toy(src),
+        format!("-1 ffffffffffffffff FFFFFFFFFFFFFFFE\n{}010\n", "1".repeat(61))
+    );
+}
+
+#[test]
+fn format_width_and_zero_padding() {
+    let src = r#"
+print "[{:5}] [{:05}] [{:05}]", 42, 42, -42;
+print "[{:4x}] [{:04X}] [{:08b}] [{:8b}]", 10, 10, 5, 5;
+print "[{:2}] [{:02b}] [{:0}]", 12345, 7, 9;
+"#;
+    assert_eq!(
+        run_toy(src),
+        "[   42] [00042] [-0042]\n[   a] [000A] [00000101] [     101]\n[12345] [111] [9]\n"
+    );
+}
+
+#[test]
+fn format_escaped_braces() {
+    assert_eq!(run_toy("print \"{{}} {{{}}}\", 7;"), "{} {7}\n");
+}
+
+#[test]
+fn format_text_with_percent() {
+    assert_eq!(run_toy("print \"{}% of %d\", 50;"), "50% of %d\n");
+}
+
+#[test]
+fn format_placeholders_only() {
+    assert_eq!(run_toy("print \"{}{}{:b}\", 1, 2, 3;"), "1211\n");
+}
+
+#[test]
+fn format_arguments_are_expressions() {
+    let src = "\
+fn sq(n) {
+    return n * n;
+}
+let a = 4;
+print \"{} squared is {}\", a, sq(a);
+print \"{:x}\", a * 64 + 1;
+";
+    assert_eq!(run_toy(src), "4 squared is 16\n101\n");
+}
+
+#[test]
+fn format_arguments_evaluated_before_printing() {
+    let src = "\
+fn noisy(n) {
+    print \"eval {}\", n;
+    return n;
+}
+print \"{} and {}\", noisy(1), noisy(2);
+";
+    assert_eq!(run_toy(src), "eval 1\neval 2\n1 and 2\n");
+}
+
+#[test]
+fn write_without_newline() {
+    let src = r#"
+let i = 0;
+while i < 3 {
+    write "{},", i;
+    i += 1;
+}
+write 42;
+write " ";
+write "{:b}", 2;
+print "";
+"#;
+    assert_eq!(run_toy(src), "0,1,2,42 10\n");
+}
+
+#[test]
+fn write_is_a_keyword() {
+    expect_compile_error("let write = 1;");
+}
+
+#[test]
+fn error_format_argument_count() {
+    assert_eq!(
+        compile_error("let x = 1;\nprint \"{} {}\", x;"),
+        "\
+error[E0208]: format string has 2 placeholder(s) but 1 argument(s) were supplied
+ --> test.toy:2:7
+  |
+2 | print \"{} {}\", x;
+  |       ^^^^^^^
+"
+    );
+    expect_compile_error("print \"{}\";");
+    expect_compile_error("print \"no placeholders\", 1;");
+    expect_compile_error("write \"{}\", 1, 2;");
+}
+
+#[test]
+fn error_unknown_format_specifier() {
+    assert_eq!(
+        compile_error("print 1;\nprint \"ab {:q}\", 1;"),
+        "\
+error[E0208]: unknown format specifier '{:q}'
+ --> test.toy:2:11
+  |
+2 | print \"ab {:q}\", 1;
+  |           ^^^^
+"
+    );
+    expect_compile_error("print \"{x}\", 1;");
+    expect_compile_error("print \"{:-5}\", 1;");
+    expect_compile_error("print \"{:5.2}\", 1;");
+    expect_compile_error("print \"{:xb}\", 1;");
+    expect_compile_error("print \"{:99999}\", 1;");
+}
+
+#[test]
+fn error_unmatched_brace() {
+    assert_eq!(
+        compile_error("print \"a { b\", 1;"),
+        "\
+error[E0208]: unmatched '{' in format string
+ --> test.toy:1:10
+  |
+1 | print \"a { b\", 1;
+  |          ^
+"
+    );
+    assert_eq!(
+        compile_error("print \"a } b\";"),
+        "\
+error[E0208]: unmatched '}' in format string
+ --> test.toy:1:10
+  |
+1 | print \"a } b\";
+  |          ^
+"
+    );
+}
+
+#[test]
+fn error_format_with_trailing_comma() {
+    expect_compile_error("print \"{}\", 1,;");
+    expect_compile_error("print 1, 2;");
+}
+
+// ==================== Array tests ====================
+
+#[test]
+fn array_initialized_with_repeated_value() {
+    let src = "let a = [7; 3];\nprint \"{} {} {}\", a[0], a[1], a[2];";
+    assert_eq!(run_toy(src), "7 7 7\n");
+}
+
+#[test]
+fn array_element_assignment() {
+    let src = "\
+let a = [0; 10];
+let i = 0;
+while i < len(a) {
+    a[i] = i * i;
+    i += 1;
+}
+a[3] += 100;
+a[9] <<= 1;
+let sum = 0;
+i = 0;
+while i < len(a) {
+    sum += a[i];
+    i += 1;
+}
+print \"{} {} {}\", a[3], a[9], sum;
+";
+    assert_eq!(run_toy(src), "109 162 466\n");
+}
+
+#[test]
+fn array_len() {
+    assert_eq!(run_toy("let a = [0; 16];\nprint len(a);"), "16\n");
+    assert_eq!(run_toy("let a = [1; 0];\nprint len(a) * 2;"), "0\n");
+}
+
+#[test]
+fn array_index_expressions() {
+    let src = "\
+let a = [0; 8];
+let b = [3; 2];
+a[b[0] + 1] = 5;
+print a[a[4] - 1];
+a[a[4]] = a[4] * 2;
+print a[5];
+";
+    assert_eq!(run_toy(src), "5\n10\n");
+}
+
+#[test]
+fn array_initializer_is_evaluated_once() {
+    let src = "\
+fn noisy() {
+    print \"init\";
+    return 4;
+}
+let a = [noisy(); 3];
+print a[2];
+";
+    assert_eq!(run_toy(src), "init\n4\n");
+}
+
+#[test]
+fn array_initializer_sees_outer_variable() {
+    // As with `let`, the new array is not in scope in its initializer
+    assert_eq!(run_toy("let a = 5;\nlet a = [a; 2];\nprint a[1];"), "5\n");
+}
+
+#[test]
+fn array_shadowing_and_scope() {
+    let src = "\
+let a = [1; 4];
+{
+    let a = [2; 4];
+    a[0] = 20;
+    print a[0];
+}
+print a[0];
+let a = 3;
+print a;
+";
+    assert_eq!(run_toy(src), "20\n1\n3\n");
+}
+
+#[test]
+fn arrays_in_functions_are_local() {
+    let src = "\
+fn fib(n) {
+    let f = [0; 50];
+    f[1] = 1;
+    let i = 2;
+    while i <= n {
+        f[i] = f[i - 1] + f[i - 2];
+        i += 1;
+    }
+    return f[n];
+}
+let f = [9; 2];
+print fib(40);
+print f[0];
+";
+    assert_eq!(run_toy(src), "102334155\n9\n");
+}
+
+#[test]
+fn array_in_recursive_function() {
+    // Every call has its own copy
+    let src = "\
+fn f(n) {
+    let a = [n; 2];
+    if n > 0 {
+        f(n - 1);
+    }
+    return a[1];
+}
+print f(10);
+";
+    assert_eq!(run_toy(src), "10\n");
+}
+
+#[test]
+fn arrays_do_not_overlap_variables() {
+    let src = "\
+let x = 1;
+let a = [2; 3];
+let y = 3;
+let b = [4; 3];
+let z = 5;
+a[2] = 20;
+b[0] = 40;
+print \"{} {} {} {} {}\", x, y, z, a[2] + a[0], b[0] + b[2];
+";
+    assert_eq!(run_toy(src), "1 3 5 22 44\n");
+}
+
+#[test]
+fn arrays_in_blocks_share_space() {
+    // Two 4096-element arrays fit because they are never live together
+    let src = "\
+{
+    let a = [1; 4096];
+    print a[4095];
+}
+{
+    let b = [2; 4096];
+    print b[4095];
+}
+";
+    assert_eq!(run_toy(src), "1\n2\n");
+}
+
+#[test]
+fn large_array_frame() {
+    let src = "\
+let small = [5; 3];
+let a = [0; 4000];
+let i = 0;
+while i < len(a) {
+    a[i] = i;
+    i += 1;
+}
+print a[3999] + a[1] + small[2];
+";
+    assert_eq!(run_toy(src), "4005\n");
+}
+
+#[test]
+fn array_index_out_of_bounds() {
+    let src = "let a = [0; 16];\nlet i = 16;\nprint \"before\";\nprint a[i];\nprint \"after\";";
+    let (stdout, stderr) = run_toy_failure(src);
+    assert_eq!(stdout, "before\n");
+    assert_eq!(
+        stderr,
+        "Runtime error: 4:7: index 16 is out of bounds for array 'a' of length 16\n"
+    );
+}
+
+#[test]
+fn array_negative_index() {
+    let src = "let a = [0; 4];\nlet i = -1;\na[i] = 1;";
+    let (_, stderr) = run_toy_failure(src);
+    assert_eq!(
+        stderr,
+        "Runtime error: 3:1: index -1 is out of bounds for array 'a' of length 4\n"
+    );
+}
+
+#[test]
+fn array_out_of_bounds_in_compound_assignment() {
+    let src = "let a = [0; 4];\nlet i = 0;\nwhile 1 {\n    a[i] += i;\n    i += 1;\n}";
+    let (_, stderr) = run_toy_failure(src);
+    assert_eq!(
+        stderr,
+        "Runtime error: 4:5: index 4 is out of bounds for array 'a' of length 4\n"
+    );
+}
+
+#[test]
+fn error_literal_index_out_of_bounds() {
+    // Nothing runs: the error is found before the program starts
+    assert_eq!(
+        compile_error("let a = [0; 4];\nprint 1;\nprint a[4];"),
+        "\
+error[E0311]: index 4 is out of bounds for array 'a' of length 4
+ --> test.toy:3:7
+  |
+3 | print a[4];
+  |       ^^^^
+"
+    );
+    expect_compile_error("let a = [0; 4];\na[-1] = 0;");
+    expect_compile_error("let a = [0; 0];\nprint a[0];");
+}
+
+#[test]
+fn error_array_used_as_integer() {
+    expect_compile_error("let a = [0; 4];\nprint a;");
+    expect_compile_error("let a = [0; 4];\nprint a + 1;");
+    expect_compile_error("let a = [0; 4];\na = 1;");
+    expect_compile_error("let a = [0; 4];\na += 1;");
+    expect_compile_error("let a = [0; 4];\nlet b = a;");
+    expect_compile_error("fn f(x) {\n    return x;\n}\nlet a = [0; 4];\nf(a);");
+}
+
+#[test]
+fn error_integer_used_as_array() {
+    assert_eq!(
+        compile_error("let x = 1;\nprint x[0];"),
+        "\
+error[E0314]: 'x' is not an array
+ --> test.toy:2:7
+  |
+2 | print x[0];
+  |       ^^^^
+"
+    );
+    expect_compile_error("let x = 1;\nx[0] = 1;");
+    expect_compile_error("let x = 1;\nprint len(x);");
+    expect_compile_error("print len(nothing);");
+    expect_compile_error("print nothing[0];");
+}
+
+#[test]
+fn error_array_length_not_literal() {
+    expect_compile_error("let n = 4;\nlet a = [0; n];");
+    expect_compile_error("let a = [0; 2 + 2];");
+    expect_compile_error("let a = [0; -1];");
+    expect_compile_error("let a = [0, 4];");
+    expect_compile_error("let a = [0; 4;");
+    expect_compile_error("let a = [0; 4]");
+}
+
+#[test]
+fn error_array_too_large() {
+    assert_eq!(
+        compile_error("let a = [0; 4097];"),
+        "\
+error[E0207]: array length '4097' is too large (maximum is 4096)
+ --> test.toy:1:13
+  |
+1 | let a = [0; 4097];
+  |             ^^^^
+"
+    );
+    // Each array fits, but not both at once
+    expect_compile_error("let a = [0; 4096];\nlet b = [0; 1];");
+}
+
+#[test]
+fn error_len_is_builtin() {
+    expect_compile_error("fn len(a) {\n    return 0;\n}");
+    expect_compile_error("let a = [0; 4];\nprint len(a, a);");
+    expect_compile_error("print len(1);");
+}
+
+#[test]
+fn len_is_not_a_keyword() {
+    assert_eq!(run_toy("let len = 3;\nlen += 1;\nprint len;"), "4\n");
+}
+
+// ==================== Type checking tests ====================
+
+#[test]
+fn bool_literals_and_variables() {
+    let src = "\
+let yes = true;
+let no = !yes;
+print yes;
+print no;
+write no;
+print \"\";
+if yes && !no { print 1; }
+";
+    assert_eq!(run_toy(src), "true\nfalse\nfalse\n1\n");
+}
+
+#[test]
+fn integers_and_bools_are_both_tested_for_truth() {
+    // Integers of any type are true if nonzero; the logical operators give
+    // a bool only when every operand is one
+    let src = "\
+let small: u8 = 3;
+if small { print 1; }
+while small { small -= 1; }
+print !true;
+print !small;
+print true && small;
+print small || false;
+print true || false;
+";
+    assert_eq!(run_toy(src), "1\nfalse\n1\n0\n0\ntrue\n");
+}
+
+#[test]
+fn bool_equality_and_bitwise_operators() {
+    let src = "\
+print true == false;
+print true != false;
+print true & false;
+print true | false;
+print true ^ true;
+let flag = false;
+flag |= !flag;
+print flag;
+";
+    assert_eq!(run_toy(src), "false\ntrue\nfalse\ntrue\nfalse\ntrue\n");
+}
+
+#[test]
+fn bool_in_formatted_print() {
+    let src = "print \"[{}] [{:6}] [{:2}]\", true, !true, true;";
+    assert_eq!(run_toy(src), "[true] [ false] [true]\n");
+}
+
+#[test]
+fn bool_array_elements() {
+    let src = "\
+let seen = [false; 4];
+seen[2] = true;
+let i = 0;
+while i < len(seen) {
+    write \"{} \", seen[i];
+    i += 1;
+}
+print \"\";
+";
+    assert_eq!(run_toy(src), "false false true false \n");
+}
+
+#[test]
+fn arithmetic_on_bool_is_rejected() {
+    assert_eq!(
+        compile_error("print true + 1;"),
+        "\
+error[E0305]: cannot apply '+' to bool and i64
+ --> test.toy:1:12
+  |
+1 | print true + 1;
+  |            ^
+"
+    );
+    assert_eq!(
+        compile_error("let b = false;\nb += 1;"),
+        "\
+error[E0305]: cannot apply '+=' to bool and i64
+ --> test.toy:2:1
+  |
+2 | b += 1;
+  | ^^^^^^^
+"
+    );
+    assert_eq!(
+        compile_error("print -true;"),
+        "\
+error[E0305]: cannot apply '-' to bool
+ --> test.toy:1:7
+  |
+1 | print -true;
+  |       ^^^^^
+"
+    );
+}
+
+#[test]
+fn ordering_bools_is_rejected() {
+    assert_eq!(
+        compile_error("print false < true;"),
+        "\
+error[E0305]: cannot apply '<' to bool and bool
+ --> test.toy:1:13
+  |
+1 | print false < true;
+  |             ^
+"
+    );
+}
+
+#[test]
+fn comparing_bool_with_integer_is_rejected() {
+    assert_eq!(
+        compile_error("let x = 1;\nprint x == true;"),
+        "\
+error[E0305]: cannot apply '==' to i64 and bool
+ --> test.toy:2:9
+  |
+2 | print x == true;
+  |         ^^
+"
+    );
+}
+
+#[test]
+fn assignment_must_keep_the_variable_type() {
+    assert_eq!(
+        compile_error("let x = 1;\nx = true;"),
+        "\
+error[E0304]: mismatched types: expected i64, found bool
+ --> test.toy:2:5
+  |
+2 | x = true;
+  |     ^^^^
+"
+    );
+    assert_eq!(
+        compile_error("let a = [0; 2];\na[1] = true;"),
+        "\
+error[E0304]: mismatched types: expected i64, found bool
+ --> test.toy:2:8
+  |
+2 | a[1] = true;
+  |        ^^^^
+"
+    );
+}
+
+#[test]
+fn while_condition_must_be_bool_or_integer() {
+    assert_eq!(
+        compile_error("while 1.5 { break; }"),
+        "\
+error[E0304]: mismatched types: expected bool or an integer, found f64
+ --> test.toy:1:7
+  |
+1 | while 1.5 { break; }
+  |       ^^^
+"
+    );
+}
+
+#[test]
+fn integer_is_not_a_bool_outside_a_condition() {
+    // Only a condition or a logical operand may be an integer
+    assert_eq!(
+        compile_error("let y: bool = 3;"),
+        "\
+error[E0304]: mismatched types: expected bool, found i64
+ --> test.toy:1:15
+  |
+1 | let y: bool = 3;
+  |               ^
+"
+    );
+}
+
+#[test]
+fn function_arguments_and_results_are_integers() {
+    assert_eq!(
+        compile_error("fn f(x) { return x; }\nprint f(true);"),
+        "\
+error[E0304]: mismatched types: expected i64, found bool
+ --> test.toy:2:9
+  |
+2 | print f(true);
+  |         ^^^^
+"
+    );
+    assert_eq!(
+        compile_error("fn f(x) { return !true; }"),
+        "\
+error[E0304]: mismatched types: expected i64, found bool
+ --> test.toy:1:18
+  |
+1 | fn f(x) { return !true; }
+  |                  ^^^^^
+"
+    );
+}
+
+#[test]
+fn bool_with_radix_placeholder_is_rejected() {
+    assert_eq!(
+        compile_error("print \"{:x}\", true;"),
+        "\
+error[E0303]: cannot format bool with '{:x}'
+ --> test.toy:1:15
+  |
+1 | print \"{:x}\", true;
+  |               ^^^^
+"
+    );
+    assert_eq!(
+        compile_error("print \"{:05}\", true;"),
+        "\
+error[E0303]: cannot format bool with '{:05}'
+ --> test.toy:1:16
+  |
+1 | print \"{:05}\", true;
+  |                ^^^^
+"
+    );
+}
+
+#[test]
+fn undefined_variable_is_located() {
+    assert_eq!(
+        compile_error("let x = 1;\nprint x + y;"),
+        "\
+error[E0313]: undefined variable 'y'
+ --> test.toy:2:11
+  |
+2 | print x + y;
+  |           ^
+"
+    );
+}
+
+#[test]
+fn misspelled_variable_suggests_similar_name() {
+    assert_eq!(
+        compile_error("let count = 0;\nwhile count < 3 {\n    cuont = cuont + 1;\n}"),
+        "\
+error[E0313]: undefined variable 'cuont'
+ --> test.toy:3:5
+  |
+3 |     cuont = cuont + 1;
+  |     ^^^^^^^^^^^^^^^^^^
+  |
+  = help: did you mean 'count'?
+"
+    );
+    // Only names in scope are suggested
+    assert_eq!(
+        compile_error("{ let total = 1; }\nprint totl;"),
+        "\
+error[E0313]: undefined variable 'totl'
+ --> test.toy:2:7
+  |
+2 | print totl;
+  |       ^^^^
+"
+    );
+}
+
+#[test]
+fn misspelled_function_suggests_similar_name() {
+    assert_eq!(
+        compile_error("fn square(n) {\n    return n * n;\n}\nprint sqaure(3);"),
+        "\
+error[E0306]: undefined function 'sqaure'
+ --> test.toy:4:7
+  |
+4 | print sqaure(3);
+  |       ^^^^^^^^^
+  |
+  = help: did you mean 'square'?
+"
+    );
+}
+
+#[test]
+fn use_before_let_points_at_declaration() {
+    assert_eq!(
+        compile_error("let x = 1;\nprint total + x;\nlet total = 5;"),
+        "\
+error[E0313]: undefined variable 'total'
+ --> test.toy:2:7
+  |
+2 | print total + x;
+  |       ^^^^^
+3 | let total = 5;
+  | -------------- 'total' is declared here, after its use
+  |
+  = note: a variable can only be used after the statement that declares it
+"
+    );
+}
+
+#[test]
+fn assignment_to_undeclared_name_suggests_let() {
+    assert_eq!(
+        compile_error("let x = 2;\ntotal = x;"),
+        "\
+error[E0313]: undefined variable 'total'
+ --> test.toy:2:1
+  |
+2 | total = x;
+  | ^^^^^^^^^^
+  |
+  = help: use 'let' to declare a new variable: `let total = ...;`
+"
+    );
+}
+
+#[test]
+fn compound_assignment_to_undeclared_name_suggests_let() {
+    assert_eq!(
+        compile_error("let x = 2;\ntotal += x;"),
+        "\
+error[E0313]: undefined variable 'total'
+ --> test.toy:2:1
+  |
+2 | total += x;
+  | ^^^^^^^^^^^
+  |
+  = help: use 'let' to declare a new variable: `let total = ...;`
+"
+    );
+}
+
+#[test]
+fn declaration_in_another_block_is_not_pointed_at() {
+    // Neither `total` could ever be in scope at the use
+    let src = "\
+if 1 {
+    print total;
+} else {
+    let total = 1;
+}
+{
+    {
+        let total = 2;
+    }
+}";
+    assert_eq!(
+        compile_error(src),
+        "\
+error[E0313]: undefined variable 'total'
+ --> test.toy:2:11
+  |
+2 |     print total;
+  |           ^^^^^
+"
+    );
+    // A later declaration in an enclosing block is
+    assert_eq!(
+        compile_error("{\n    print total;\n}\nlet total = 1;"),
+        "\
+error[E0313]: undefined variable 'total'
+ --> test.toy:2:11
+  |
+2 |     print total;
+  |           ^^^^^
+...
+4 | let total = 1;
+  | -------------- 'total' is declared here, after its use
+  |
+  = note: a variable can only be used after the statement that declares it
+"
+    );
+}
+
+#[test]
+fn array_used_as_scalar_is_rejected() {
+    assert_eq!(
+        compile_error("let a = [1; 3];\nprint a;"),
+        "\
+error[E0314]: 'a' is an array, not a scalar variable
+ --> test.toy:2:7
+  |
+2 | print a;
+  |       ^
+"
+    );
+}
+
+// ==================== Integer type tests ====================
+
+#[test]
+fn annotated_variables_wrap_at_their_width() {
+    let src = "\
+let a: u8 = 200;
+print a + 100;
+let b: i8 = 127;
+b += 1;
+print b;
+let c: i16 = -32768;
+print c - 1;
+let d: u16 = 1;
+print d << 15 << 1;
+let e: i32 = 65536;
+print e * e;
+let f: u32 = 0;
+print f - 1;
+";
+    assert_eq!(run_toy(src), "44\n-128\n32767\n0\n0\n4294967295\n");
+}
+
+#[test]
+fn unannotated_variables_are_i64() {
+    let src = "let x = 255;\nlet y: u8 = 255;\nprint x + 1;\nprint y + 1;";
+    assert_eq!(run_toy(src), "256\n0\n");
+}
+
+#[test]
+fn unsigned_division_shift_and_comparison() {
+    let src = "\
+let big: u64 = 18446744073709551615;
+print big;
+print big / 2;
+print big % 10;
+print big >> 60;
+print big > 1;
+let small: u8 = 255;
+print small / 16;
+print small >> 4;
+print small > 128;
+";
+    assert_eq!(
+        run_toy(src),
+        "18446744073709551615\n9223372036854775807\n5\n15\n1\n15\n15\n1\n"
+    );
+}
+
+#[test]
+fn signed_narrow_division_and_shift() {
+    let src = "\
+let min: i8 = -128;
+print min / -1;
+print min % -1;
+print min >> 1;
+print min / 3;
+";
+    assert_eq!(run_toy(src), "-128\n0\n-64\n-42\n");
+}
+
+#[test]
+fn negation_and_bit_not_wrap() {
+    let src = "\
+let a: i8 = -128;
+print -a;
+let b: u8 = 5;
+print ~b;
+let c: i32 = 0;
+print ~c;
+";
+    assert_eq!(run_toy(src), "-128\n250\n-1\n");
+}
+
+#[test]
+fn casts_between_integer_types() {
+    let src = "\
+let a: i16 = -1;
+print a as u16;
+print a as u8;
+print a as u64;
+print a as i64;
+print 300 as u8;
+print 200 as i8;
+let big: u64 = 18446744073709551615;
+print big as i64;
+print big as u32;
+let small: u8 = 250;
+print small as i8 + 1;
+print true as u8 + false as u8;
+print (1 < 2) as i64;
+";
+    assert_eq!(
+        run_toy(src),
+        "65535\n255\n18446744073709551615\n-1\n44\n-56\n-1\n4294967295\n-5\n1\n1\n"
+    );
+}
+
+#[test]
+fn cast_binds_tighter_than_binary_operators() {
+    let src = "let x: u8 = 200;\nprint x as i64 * 2;\nprint -1 as u8;";
+    assert_eq!(run_toy(src), "400\n255\n");
+}
+
+#[test]
+fn literals_take_the_type_of_their_context() {
+    let src = "\
+let a: u8 = 10;
+print a + 245;
+print 245 + a;
+let b: i8 = -128;
+print b == -128;
+let c: u64 = 18446744073709551615;
+print c == 18446744073709551615;
+";
+    assert_eq!(run_toy(src), "255\n255\n1\n1\n");
+}
+
+#[test]
+fn typed_arrays() {
+    let src = "\
+let a: [u8; 4] = [250; 4];
+a[1] += 10;
+print a[1];
+print a[0] + a[2];
+let i: u8 = 3;
+print a[i];
+";
+    assert_eq!(run_toy(src), "4\n244\n250\n");
+}
+
+#[test]
+fn printing_narrow_types() {
+    let src = "\
+let a: i8 = -1;
+let b: u32 = 4294967295;
+let c: i16 = -300;
+print \"{:x} {:X} {:b}\", a, c, a;
+print \"{} {:x} {:b}\", b, b, b;
+print \"[{:5}] [{:05}] [{:04x}]\", a, c, c;
+";
+    assert_eq!(
+        run_toy(src),
+        "ff FED4 11111111\n\
+         4294967295 ffffffff 11111111111111111111111111111111\n\
+         [   -1] [-0300] [fed4]\n"
+    );
+}
+
+#[test]
+fn literal_out_of_range_for_its_type() {
+    assert_eq!(
+        compile_error("let x: u8 = 256;"),
+        "\
+error[E0312]: literal 256 is out of range for u8 (0 to 255)
+ --> test.toy:1:13
+  |
+1 | let x: u8 = 256;
+  |             ^^^
+"
+    );
+    assert_eq!(
+        compile_error("let x: i8 = -129;"),
+        "\
+error[E0312]: literal -129 is out of range for i8 (-128 to 127)
+ --> test.toy:1:13
+  |
+1 | let x: i8 = -129;
+  |             ^^^^
+"
+    );
+    assert_eq!(
+        compile_error("let x: u16 = 1;\nx = x + 70000;"),
+        "\
+error[E0312]: literal 70000 is out of range for u16 (0 to 65535)
+ --> test.toy:2:9
+  |
+2 | x = x + 70000;
+  |         ^^^^^
+"
+    );
+    assert_eq!(
+        compile_error("print 9223372036854775808;"),
+        "\
+error[E0312]: literal 9223372036854775808 is out of range for i64 (-9223372036854775808 to 9223372036854775807)
+ --> test.toy:1:7
+  |
+1 | print 9223372036854775808;
+  |       ^^^^^^^^^^^^^^^^^^^
+"
+    );
+}
+
+#[test]
+fn mixed_integer_types_are_rejected() {
+    assert_eq!(
+        compile_error("let a: u8 = 1;\nlet b: u16 = 2;\nprint a + b;"),
+        "\
+error[E0305]: cannot apply '+' to u8 and u16
+ --> test.toy:3:9
+  |
+3 | print a + b;
+  |         ^
+"
+    );
+    assert_eq!(
+        compile_error("let a: i32 = 1;\nlet b = a;\nb = 5 + b;\nlet c: i64 = b;"),
+        "\
+error[E0304]: mismatched types: expected i64, found i32
+ --> test.toy:4:14
+  |
+4 | let c: i64 = b;
+  |              ^
+"
+    );
+}
+
+#[test]
+fn negating_unsigned_is_rejected() {
+    assert_eq!(
+        compile_error("let x: u8 = 1;\nprint -x;"),
+        "\
+error[E0305]: cannot apply '-' to u8
+ --> test.toy:2:7
+  |
+2 | print -x;
+  |       ^^
+"
+    );
+    assert_eq!(
+        compile_error("let x: u64 = -1;"),
+        "\
+error[E0305]: cannot apply '-' to u64
+ --> test.toy:1:14
+  |
+1 | let x: u64 = -1;
+  |              ^^
+"
+    );
+}
+
+#[test]
+fn cast_to_bool_is_rejected() {
+    assert_eq!(
+        compile_error("print 1 as bool;"),
+        "\
+error[E0308]: cannot cast i64 to bool
+ --> test.toy:1:9
+  |
+1 | print 1 as bool;
+  |         ^^
+"
+    );
+}
+
+#[test]
+fn bad_type_annotations_are_rejected() {
+    expect_compile_error("let x: i128 = 1;");
+    expect_compile_error("let x: u8 = [0; 2];");
+    expect_compile_error("let a: [u8; 3] = [0; 4];");
+    expect_compile_error("let b: bool = 1;");
+}
+
+// ==================== Float tests ====================
+
+#[test]
+fn float_literals() {
+    let src = "\
+print 1.5;
+print 2.25e2;
+print 1E-3;
+print 6.02e+23;
+print 1_000.000_1;
+let x: f64 = 0.5;
+print x;
+";
+    assert_eq!(run_toy(src), "1.5\n225\n0.001\n6.02e+23\n1000\n0.5\n");
+}
+
+#[test]
+fn float_arithmetic() {
+    let src = "\
+let x = 1.5;
+let y = 4.0;
+print x + y;
+print x - y;
+print x * y;
+print x / y;
+print -x;
+let z = 2.0;
+z *= 3.5;
+z -= 1.0;
+print z;
+";
+    assert_eq!(run_toy(src), "5.5\n-2.5\n6\n0.375\n-1.5\n6\n");
+}
+
+#[test]
+fn float_default_format_is_six_significant_digits() {
+    let src = "\
+print 0.1 + 0.2;
+print 1.0 / 3.0;
+print 123456.0;
+print 1234567.0;
+print 0.0001;
+print 0.00001;
+print 1e20;
+print 100.0;
+";
+    assert_eq!(
+        run_toy(src),
+        "0.3\n0.333333\n123456\n1.23457e+06\n0.0001\n1e-05\n1e+20\n100\n"
+    );
+}
+
+#[test]
+fn float_division_by_zero_is_infinite() {
+    let src = "print 1.0 / 0.0;\nprint -1.0 / 0.0;\nlet nan = 0.0 / 0.0;\nprint nan == nan;";
+    assert_eq!(run_toy(src), "inf\n-inf\n0\n");
+}
+
+#[test]
+fn float_comparisons() {
+    let src = "\
+let x = 1.5;
+print x < 2.0;
+print x <= 1.5;
+print x > 1.5;
+print x >= 2.0;
+print x == 1.5;
+print x != 1.5;
+let nan = 0.0 / 0.0;
+print nan < 1.0 || nan >= 1.0;
+print nan != nan;
+";
+    assert_eq!(
+        run_toy(src),
+        "1\n1\n0\n0\n1\n0\n0\n1\n"
+    );
+}
+
+#[test]
+fn casts_between_floats_and_integers() {
+    let src = "\
+print 7 as f64 / 2.0;
+print 3.99 as i64;
+print -3.99 as i64;
+print 300.7 as u8;
+print 1e300 as i64;
+print -1e300 as i64;
+print (0.0 / 0.0) as i64;
+let big: u64 = 18446744073709551615;
+print big as f64;
+let small: i8 = -5;
+print small as f64;
+";
+    assert_eq!(
+        run_toy(src),
+        "3.5\n3\n-3\n44\n9223372036854775807\n-9223372036854775808\n0\n\
+         1.84467e+19\n-5\n"
+    );
+}
+
+#[test]
+fn float_to_u64_casts_use_the_whole_range() {
+    // Unlike the other integer types, u64 does not go through i64
+    let src = "\
+print 1e19 as u64;
+print 9223372036854775808.0 as u64;
+let x = 18446744073709549568.0;
+print x as u64;
+print (x * 2.0) as u64;
+print 9223372036854775807.0 as u64;
+print 3.99 as u64;
+print -1.0 as u64;
+print -0.5 as u64;
+print -1e300 as u64;
+print (0.0 / 0.0) as u64;
+const BIG: u64 = 1.5e19 as u64;
+print BIG;
+";
+    assert_eq!(
+        run_toy(src),
+        "10000000000000000000\n9223372036854775808\n18446744073709549568\n\
+         18446744073709551615\n9223372036854775808\n3\n0\n0\n0\n0\n\
+         15000000000000000000\n"
+    );
+}
+
+#[test]
+fn float_in_formatted_print() {
+    let src = "print \"[{}] [{:8}] [{:08}]\", 2.5, 1e-7, -1.25;";
+    assert_eq!(run_toy(src), "[2.5] [   1e-07] [-0001.25]\n");
+}
+
+#[test]
+fn float_arrays() {
+    let src = "let a: [f64; 3] = [0.5; 3];\na[1] += 1.0;\nprint a[1] + a[0];";
+    assert_eq!(run_toy(src), "2\n");
+}
+
+#[test]
+fn mixing_floats_and_integers_is_rejected() {
+    assert_eq!(
+        compile_error("let x = 1.5;\nprint x + 1;"),
+        "\
+error[E0305]: cannot apply '+' to f64 and i64
+ --> test.toy:2:9
+  |
+2 | print x + 1;
+  |         ^
+"
+    );
+    assert_eq!(
+        compile_error("let x: f64 = 1;"),
+        "\
+error[E0304]: mismatched types: expected f64, found i64
+ --> test.toy:1:14
+  |
+1 | let x: f64 = 1;
+  |              ^
+"
+    );
+}
+
+#[test]
+fn integer_only_operators_on_floats_are_rejected() {
+    assert_eq!(
+        compile_error("print 1.5 % 2.0;"),
+        "\
+error[E0305]: cannot apply '%' to f64 and f64
+ --> test.toy:1:11
+  |
+1 | print 1.5 % 2.0;
+  |           ^
+"
+    );
+    assert_eq!(
+        compile_error("print ~1.5;"),
+        "\
+error[E0305]: cannot apply '~' to f64
+ --> test.toy:1:7
+  |
+1 | print ~1.5;
+  |       ^^^^
+"
+    );
+    assert_eq!(
+        compile_error("print \"{:x}\", 1.5;"),
+        "\
+error[E0303]: cannot format f64 with '{:x}'
+ --> test.toy:1:15
+  |
+1 | print \"{:x}\", 1.5;
+  |               ^^^
+"
+    );
+    assert_eq!(
+        compile_error("print true as f64;"),
+        "\
+error[E0308]: cannot cast bool to f64
+ --> test.toy:1:12
+  |
+1 | print true as f64;
+  |            ^^
+"
+    );
+}
+
+#[test]
+fn malformed_float_literals() {
+    assert_eq!(
+        compile_error("print 1e;"),
+        "\
+error[E0102]: float literal '1e' has no digits in its exponent
+ --> test.toy:1:7
+  |
+1 | print 1e;
+  |       ^^
+"
+    );
+    assert_eq!(
+        compile_error("print 1.5x;"),
+        "\
+error[E0101]: invalid digit 'x' in float literal '1.5x'
+ --> test.toy:1:10
+  |
+1 | print 1.5x;
+  |          ^
+"
+    );
+    expect_compile_error("print 1e400;");
+    expect_compile_error("print 1.;");
+}
+
+// ==================== Constant tests ====================
+
+#[test]
+fn const_declarations() {
+    let src = "\
+const LIMIT = 1000;
+const HALF = LIMIT / 2;
+const SCALE: f64 = 2.5;
+const ON = HALF < LIMIT && !false;
+print LIMIT;
+print HALF + 1;
+print SCALE * 2.0;
+print ON;
+";
+    assert_eq!(run_toy(src), "1000\n501\n5\n1\n");
+}
+
+#[test]
+fn const_arithmetic_wraps() {
+    let src = "\
+const BIG: u8 = 200 + 100;
+const MIN = -9223372036854775807 - 1;
+const WRAPPED = MIN - 1;
+const NEG: i8 = -(-128);
+const BITS: i32 = (1.5e10 as i32) << 4;
+print BIG;
+print WRAPPED;
+print NEG;
+print BITS;
+";
+    assert_eq!(run_toy(src), "44\n9223372036854775807\n-128\n-518168576\n");
+}
+
+#[test]
+fn const_is_scoped_like_let() {
+    let src = "\
+const N = 1;
+{
+    const N = N + 1;
+    print N;
+}
+print N;
+let N = N * 10;
+N += 5;
+print N;
+fn f(x) {
+    const K = 3;
+    return x * K;
+}
+print f(4);
+";
+    assert_eq!(run_toy(src), "2\n1\n15\n12\n");
+}
+
+#[test]
+fn consts_take_no_variable_slots() {
+    // 32 variables is the limit; constants do not count toward it
+    let mut src = String::new();
+    for i in 0..40 {
+        src.push_str(&format!("const C{i} = {i};\n"));
+    }
+    for i in 0..32 {
+        src.push_str(&format!("let v{i} = C{i};\n"));
+    }
+    src.push_str("print v31 + C39;\n");
+    assert_eq!(run_toy(&src), "70\n");
+}
+
+#[test]
+fn const_division_by_zero_is_a_compile_error() {
+    assert_eq!(
+        compile_error("const X = 10;\nconst Y = X / (X - 10);"),
+        "\
+error[E0310]: division by zero in a constant expression
+ --> test.toy:2:13
+  |
+2 | const Y = X / (X - 10);
+  |             ^
+"
+    );
+    assert_eq!(
+        compile_error("const Y: u8 = 1 % 0;"),
+        "\
+error[E0310]: modulo by zero in a constant expression
+ --> test.toy:1:17
+  |
+1 | const Y: u8 = 1 % 0;
+  |                 ^
+"
+    );
+    // The right operand of a short-circuited operator is not evaluated
+    assert_eq!(
+        run_toy("const OK = false && 1 / 0 == 0;\nprint OK;"),
+        "0\n"
+    );
 }
 
 #[test]
-fn subtraction() {
-    assert_eq!(run_toy("print 10 - 3;"), "7\n");
+fn const_initializer_must_be_constant() {
+    assert_eq!(
+        compile_error("let x = 1;\nconst Y = x + 1;"),
+        "\
+error[E0309]: cannot use variable 'x' in a constant expression
+ --> test.toy:2:11
+  |
+2 | const Y = x + 1;
+  |           ^
+  |
+  = note: constants are computed at compile time, before any variable has a value
+"
+    );
+    assert_eq!(
+        compile_error("fn f() { return 1; }\nconst Y = f();"),
+        "\
+error[E0309]: cannot call function 'f' in a constant expression
+ --> test.toy:2:11
+  |
+2 | const Y = f();
+  |           ^^^
+"
+    );
+    assert_eq!(
+        compile_error("let a = [1; 4];\nconst Y = len(a);"),
+        "\
+error[E0309]: cannot use array 'a' in a constant expression
+ --> test.toy:2:11
+  |
+2 | const Y = len(a);
+  |           ^^^^^^
+"
+    );
 }
 
 #[test]
-fn multiplication() {
-    assert_eq!(run_toy("print 6 * 7;"), "42\n");
+fn assigning_to_a_const_is_rejected() {
+    assert_eq!(
+        compile_error("const X = 1;\nX = 2;"),
+        "\
+error[E0315]: cannot assign to constant 'X'
+ --> test.toy:2:1
+  |
+2 | X = 2;
+  | ^^^^^^
+  |
+  = help: declare 'X' with 'let' instead of 'const' to assign to it
+"
+    );
+    assert_eq!(
+        compile_error("const X = 1;\nX += 2;"),
+        "\
+error[E0315]: cannot assign to constant 'X'
+ --> test.toy:2:1
+  |
+2 | X += 2;
+  | ^^^^^^^
+  |
+  = help: declare 'X' with 'let' instead of 'const' to assign to it
+"
+    );
+    assert_eq!(
+        compile_error("const X: u8 = 1;\nconst Y: i64 = X;"),
+        "\
+error[E0304]: mismatched types: expected i64, found u8
+ --> test.toy:2:16
+  |
+2 | const Y: i64 = X;
+  |                ^
+"
+    );
 }
 
-#[test]
-fn division() {
-    assert_eq!(run_toy("print 7 / 2;"), "3\n");
-}
+// ==================== Input tests ====================
 
 #[test]
-fn modulo() {
-    assert_eq!(run_toy("print 10 % 3;"), "1\n");
+fn input_reads_integers() {
+    let src = "\
+let n = input();
+let sum = 0;
+while n > 0 {
+    sum += input();
+    n -= 1;
+}
+print sum;
+";
+    assert_eq!(run_toy_with_input(src, "3\n10 -4\n+7\n"), "13\n");
 }
 
 #[test]
-fn precedence_mul_over_add() {
-    assert_eq!(run_toy("print 2 + 3 * 4;"), "14\n");
+fn input_skips_whitespace() {
+    let src = "print input();\nprint input();\nprint input();";
+    assert_eq!(
+        run_toy_with_input(src, " \t1\n\n  2\r\n\x0b\x0c3"),
+        "1\n2\n3\n"
+    );
 }
 
 #[test]
-fn precedence_mul_over_sub() {
-    assert_eq!(run_toy("print 10 - 2 * 3;"), "4\n");
+fn input_statement_discards_a_value() {
+    let src = "input();\nprint input() * 2;";
+    assert_eq!(run_toy_with_input(src, "100 21"), "42\n");
 }
 
 #[test]
-fn parentheses_override_precedence() {
-    assert_eq!(run_toy("print (2 + 3) * 4;"), "20\n");
+fn input_stops_at_the_end_of_a_number() {
+    // "12-3" is two numbers, and "0x10" is 0 followed by malformed input
+    let src = "print input();\nprint input();\nprint input();\nprint input();";
+    let (stdout, stderr) = run_toy_failure_with_input(src, "12-3 0x10");
+    assert_eq!(stdout, "12\n-3\n0\n");
+    assert_eq!(
+        stderr,
+        "Runtime error: 4:7: expected an integer in the input\n"
+    );
 }
 
 #[test]
-fn left_associative_subtraction() {
-    assert_eq!(run_toy("print 10 - 3 - 2;"), "5\n");
+fn input_out_of_range_saturates() {
+    let src = "print input();\nprint input();";
+    assert_eq!(
+        run_toy_with_input(src, "99999999999999999999 -99999999999999999999"),
+        "9223372036854775807\n-9223372036854775808\n"
+    );
 }
 
 #[test]
-fn left_associative_division() {
-    assert_eq!(run_toy("print 24 / 4 / 2;"), "3\n");
+fn input_at_end_of_input_is_a_runtime_error() {
+    let src = "print input();\nlet x = input();\nprint x;";
+    let (stdout, stderr) = run_toy_failure_with_input(src, "5\n  \n");
+    assert_eq!(stdout, "5\n");
+    assert_eq!(stderr, "Runtime error: 2:9: unexpected end of input\n");
+    let (stdout, stderr) = run_toy_failure("print input();");
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "Runtime error: 1:7: unexpected end of input\n");
 }
 
 #[test]
-fn nested_parentheses() {
-    assert_eq!(run_toy("print ((2 + 3) * (4 + 1));"), "25\n");
+fn malformed_input_is_a_runtime_error() {
+    let src = "print input() + input();";
+    let (_, stderr) = run_toy_failure_with_input(src, "1 abc");
+    assert_eq!(
+        stderr,
+        "Runtime error: 1:17: expected an integer in the input\n"
+    );
+    // A sign must be followed by a digit
+    let (_, stderr) = run_toy_failure_with_input(src, "- 1 2");
+    assert_eq!(
+        stderr,
+        "Runtime error: 1:7: expected an integer in the input\n"
+    );
 }
 
 #[test]
-fn complex_expression() {
-    // 1 + (2*3) - (4/2) + 5 = 1 + 6 - 2 + 5 = 10
-    assert_eq!(run_toy("print 1 + 2 * 3 - 4 / 2 + 5;"), "10\n");
+fn input_is_a_builtin() {
+    assert_eq!(
+        compile_error("fn input() { return 1; }"),
+        "\
+error[E0202]: 'input' is a built-in function and cannot be redefined
+ --> test.toy:1:4
+  |
+1 | fn input() { return 1; }
+  |    ^^^^^
+"
+    );
+    assert_eq!(
+        compile_error("const N = input();"),
+        "\
+error[E0309]: cannot read input in a constant expression
+ --> test.toy:1:11
+  |
+1 | const N = input();
+  |           ^^^^^^^
+"
+    );
+    expect_compile_error("print input(1);");
 }
 
 // ==================== Unary minus tests ====================
@@ -307,6 +3342,31 @@ fn modulo_positive() {
     assert_eq!(run_toy("print 7 % -3;"), "1\n");
 }
 
+#[test]
+fn division_by_zero_is_runtime_error() {
+    // Compiled and interpreted programs stop with the same error
+    let src = "let x = 0;\nprint 1;\nprint 10 / x;\nprint 2;";
+    let (stdout, stderr) = run_toy_failure(src);
+    assert_eq!(stdout, "1\n");
+    assert_eq!(stderr, "Runtime error: line 3: division by zero\n");
+}
+
+#[test]
+fn modulo_by_zero_is_runtime_error() {
+    let (_, stderr) = run_toy_failure("let x: u8 = 0;\nprint 7 % x;");
+    assert_eq!(stderr, "Runtime error: line 2: modulo by zero\n");
+    let (_, stderr) = run_toy_failure("print 7 % 0;");
+    assert_eq!(stderr, "Runtime error: line 1: modulo by zero\n");
+}
+
+#[test]
+fn compound_division_by_zero_is_runtime_error() {
+    let (_, stderr) = run_toy_failure("let x = 5;\nlet z = 0;\nx /= z;");
+    assert_eq!(stderr, "Runtime error: line 3: division by zero\n");
+    let (_, stderr) = run_toy_failure("let a = [5; 2];\nlet z = 0;\nwhile 1 {\n  a[1] %= z;\n}");
+    assert_eq!(stderr, "Runtime error: line 4: modulo by zero\n");
+}
+
 // ==================== Comment tests ====================
 
 #[test]
@@ -437,8 +3497,8 @@ fn no_whitespace_program() {
 
 #[test]
 fn digit_followed_by_identifier() {
-    // "3x" lexes as integer 3, identifier x — this is a syntax error
-    // because after "print 3", the parser expects an operator or semicolon
+    // A number runs to the end of the word, so "3x" is one malformed
+    // literal, not integer 3 followed by identifier x
     expect_compile_error("print 3x;");
 }
 
@@ -610,6 +3670,111 @@ fn deeply_chained_unary_minus_over_limit() {
     expect_compile_error(&src);
 }
 
+// ==================== Statement nesting limit ====================
+
+#[test]
+fn deeply_nested_ifs_at_limit() {
+    // 128 levels of blocks should work, even with the deepest expression
+    // inside
+    let expr = format!("{}1{}", "(".repeat(256), ")".repeat(256));
+    let src = format!(
+        "{}print {};{}",
+        "if 1 { ".repeat(128),
+        expr,
+        " }".repeat(128)
+    );
+    assert_eq!(run_toy(&src), "1\n");
+}
+
+#[test]
+fn deeply_nested_ifs_over_limit() {
+    // 1000 levels of blocks is one error, not a stack overflow
+    let src = format!(
+        "{}print 1;{}\nprint 2 +;",
+        "if 1 { ".repeat(1000),
+        " }".repeat(1000)
+    );
+    let stderr = compile_error(&src);
+    assert_eq!(stderr.matches("error[").count(), 2, "stderr: {stderr}");
+    assert!(
+        stderr.starts_with(
+            "error[E0214]: blocks are nested too deeply (limit is 128)\n --> test.toy:1:902\n"
+        ),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains(" --> test.toy:2:10\n"), "stderr: {stderr}");
+}
+
+#[test]
+fn deeply_nested_loops_over_limit() {
+    let src = format!(
+        "let i = 0;\n{}i += 1;{}\nprint 2 +;",
+        "while i < 1 { ".repeat(20000),
+        " }".repeat(20000)
+    );
+    let stderr = compile_error(&src);
+    assert_eq!(stderr.matches("error[").count(), 2, "stderr: {stderr}");
+    assert!(stderr.starts_with("error[E0214]: "), "stderr: {stderr}");
+    assert!(stderr.contains(" --> test.toy:3:10\n"), "stderr: {stderr}");
+}
+
+#[test]
+fn deeply_nested_loops_at_limit() {
+    let src = format!(
+        "let i = 0;\n{}i += 1;{}\nprint i;",
+        "while i < 1 { ".repeat(128),
+        " }".repeat(128)
+    );
+    assert_eq!(run_toy(&src), "1\n");
+}
+
+#[test]
+fn long_else_if_chain_over_limit() {
+    // Each `else if` nests the rest of the chain in an `else` block
+    let mut src = String::from("let x = 999;\nif x == 0 { print 0; }");
+    for i in 1..1000 {
+        src.push_str(&format!(" else if x == {i} {{ print {i}; }}"));
+    }
+    src.push_str("\nprint 2 +;");
+    let stderr = compile_error(&src);
+    assert_eq!(stderr.matches("error[").count(), 2, "stderr: {stderr}");
+    assert!(stderr.starts_with("error[E0214]: "), "stderr: {stderr}");
+    assert!(stderr.contains(" --> test.toy:3:10\n"), "stderr: {stderr}");
+}
+
+#[test]
+fn else_if_chain_at_limit() {
+    let mut src = String::from("let x = 127;\nif x == 0 { print 0; }");
+    for i in 1..128 {
+        src.push_str(&format!(" else if x == {i} {{ print {i}; }}"));
+    }
+    assert_eq!(run_toy(&src), "127\n");
+}
+
+#[test]
+fn deeply_nested_blocks_over_limit() {
+    let src = format!("{}print 1;{}\nprint 2 +;", "{ ".repeat(1000), " }".repeat(1000));
+    let stderr = compile_error(&src);
+    assert_eq!(stderr.matches("error[").count(), 2, "stderr: {stderr}");
+    assert!(
+        stderr.starts_with(
+            "error[E0214]: blocks are nested too deeply (limit is 128)\n --> test.toy:1:257\n"
+        ),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains(" --> test.toy:2:10\n"), "stderr: {stderr}");
+}
+
+#[test]
+fn deeply_nested_blocks_at_limit() {
+    let src = format!(
+        "let x = 1;\n{}let x = x + 1; print x;{}\nprint x;",
+        "{ ".repeat(128),
+        " }".repeat(128)
+    );
+    assert_eq!(run_toy(&src), "2\n1\n");
+}
+
 // ==================== Error cases ====================
 
 #[test]
@@ -634,7 +3799,7 @@ fn error_unexpected_token() {
 
 #[test]
 fn error_literal_out_of_range() {
-    // 9223372036854775808 = i64::MAX + 1, cannot be parsed
+    // 9223372036854775808 = i64::MAX + 1 does not fit the literal's type, i64
     expect_compile_error("print 9223372036854775808;");
 }
 
@@ -642,3 +3807,439 @@ fn error_literal_out_of_range() {
 fn error_literal_way_out_of_range() {
     expect_compile_error("print 99999999999999999999;");
 }
+
+#[test]
+fn error_columns_count_characters() {
+    // 'é' and '→' take several bytes but one column each
+    assert_eq!(
+        compile_error("print \"é → \"; print 1 + true;"),
+        "\
+error[E0305]: cannot apply '+' to i64 and bool
+ --> test.toy:1:23
+  |
+1 | print \"é → \"; print 1 + true;
+  |                       ^
+"
+    );
+    let (_, stderr) = run_toy_failure("let a = [0; 2];\nprint \"→\"; print a[len(a)];");
+    assert_eq!(
+        stderr,
+        "Runtime error: 2:18: index 2 is out of bounds for array 'a' of length 2\n"
+    );
+}
+
+// ==================== Interpreter ====================
+
+#[test]
+fn interpreter_division_by_zero_is_runtime_error() {
+    let src = "\
+let x = 0;
+print 1;
+print 10 / x;
+print 2;
+";
+    let (ok, stdout, stderr) = interpret_toy(src);
+    assert!(!ok);
+    // Output before the error is still printed
+    assert_eq!(stdout, "1\n");
+    assert!(stderr.contains("line 3: division by zero"), "stderr: {stderr}");
+}
+
+#[test]
+fn interpreter_modulo_by_zero_is_runtime_error() {
+    let (ok, _, stderr) = interpret_toy("let x = 0;\nx = 7 % x;");
+    assert!(!ok);
+    assert!(stderr.contains("line 2: modulo by zero"), "stderr: {stderr}");
+}
+
+#[test]
+fn interpreter_undefined_variable_runs_nothing() {
+    // Name errors are reported before execution, like a compile error
+    let (ok, stdout, stderr) = interpret_toy("print 1;\nprint y;");
+    assert!(!ok);
+    assert_eq!(stdout, "");
+    assert!(stderr.contains("undefined variable 'y'"), "stderr: {stderr}");
+}
+
+#[test]
+fn interpreter_syntax_error() {
+    let (ok, _, _) = interpret_toy("print 42");
+    assert!(!ok);
+}
+
+#[test]
+fn interpreter_call_errors_run_nothing() {
+    let (ok, stdout, stderr) = interpret_toy("print 1;\nprint f(2);");
+    assert!(!ok);
+    assert_eq!(stdout, "");
+    assert!(stderr.contains("undefined function 'f'"), "stderr: {stderr}");
+
+    let (ok, stdout, stderr) = interpret_toy("fn f(a) { return a; }\nprint 1;\nprint f();");
+    assert!(!ok);
+    assert_eq!(stdout, "");
+    assert!(stderr.contains("takes 1 argument(s) but 0 were supplied"), "stderr: {stderr}");
+}
+
+#[test]
+fn interpreter_runaway_recursion_is_runtime_error() {
+    let (ok, _, stderr) = interpret_toy("fn f(n) { return f(n + 1); }\nprint f(0);");
+    assert!(!ok);
+    assert!(stderr.contains("line 1: stack overflow"), "stderr: {stderr}");
+}
+
+#[test]
+fn interpreter_compound_division_by_zero() {
+    let (ok, _, stderr) = interpret_toy("let x = 5;\nlet z = 0;\nx /= z;");
+    assert!(!ok);
+    assert!(stderr.contains("line 3: division by zero"), "stderr: {stderr}");
+}
+
+// ==================== Diagnostics ====================
+
+/// Compile a Toy program that must be rejected, with `--error-format json`,
+/// returning the error output with the path of the source file replaced by
+/// `test.toy`. Running it with `run` must fail with the same error.
+fn compile_error_json(source: &str) -> String {
+    let id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let tmp_dir = std::env::temp_dir().join(format!("toy_test_{}", id));
+    fs::create_dir_all(&tmp_dir).unwrap();
+
+    let src_path = tmp_dir.join("test.toy");
+    let exe_path = tmp_dir.join("test_exe");
+    fs::write(&src_path, source).unwrap();
+    let src = src_path.to_str().unwrap();
+
+    let compiler_path = PathBuf::from(env!("CARGO_BIN_EXE_toy-compiler"));
+    let compile_output = Command::new(&compiler_path)
+        .args([src, "-o", exe_path.to_str().unwrap(), "--error-format", "json"])
+        .output()
+        .expect("failed to run toy-compiler");
+    let run_output = Command::new(&compiler_path)
+        .args(["run", src, "--error-format", "json"])
+        .output()
+        .expect("failed to run toy-compiler run");
+    let _ = fs::remove_dir_all(&tmp_dir);
+
+    assert!(!compile_output.status.success());
+    assert!(!run_output.status.success());
+    assert_eq!(run_output.stdout, b"");
+    assert_eq!(run_output.stderr, compile_output.stderr);
+    String::from_utf8(compile_output.stderr)
+        .unwrap()
+        .replace(src, "test.toy")
+}
+
+#[test]
+fn duplicate_function_points_at_first_definition() {
+    assert_eq!(
+        compile_error("fn f() { return 1; }\nfn g() { return 2; }\nfn f() { return 3; }"),
+        "\
+error[E0302]: function 'f' is defined more than once
+ --> test.toy:3:4
+  |
+1 | fn f() { return 1; }
+  |    - first defined here
+...
+3 | fn f() { return 3; }
+  |    ^
+"
+    );
+}
+
+#[test]
+fn duplicate_parameter_points_at_first_declaration() {
+    assert_eq!(
+        compile_error("fn f(a, b, a) { return a; }"),
+        "\
+error[E0203]: duplicate parameter 'a'
+ --> test.toy:1:12
+  |
+1 | fn f(a, b, a) { return a; }
+  |      -     ^
+  |      |
+  |      first declared here
+"
+    );
+}
+
+#[test]
+fn json_error_has_code_and_span() {
+    assert_eq!(
+        compile_error_json("let x = 1;\nx = true;"),
+        concat!(
+            r#"{"severity":"error","phase":"semantic","code":"E0304","#,
+            r#""message":"mismatched types: expected i64, found bool","file":"test.toy","#,
+            r#""labels":[{"start":15,"end":19,"line":2,"column":5,"primary":true,"message":""}],"#,
+            r#""notes":[],"help":[]}"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn json_error_has_secondary_labels_notes_and_help() {
+    assert_eq!(
+        compile_error_json("fn f(a, a) { return a; }"),
+        concat!(
+            r#"{"severity":"error","phase":"parser","code":"E0203","#,
+            r#""message":"duplicate parameter 'a'","file":"test.toy","labels":["#,
+            r#"{"start":8,"end":9,"line":1,"column":9,"primary":true,"message":""},"#,
+            r#"{"start":5,"end":6,"line":1,"column":6,"primary":false,"message":"first declared here"}],"#,
+            r#""notes":[],"help":[]}"#,
+            "\n"
+        )
+    );
+    assert_eq!(
+        compile_error_json("let count = 1;\nprint cuont;"),
+        concat!(
+            r#"{"severity":"error","phase":"semantic","code":"E0313","#,
+            r#""message":"undefined variable 'cuont'","file":"test.toy","#,
+            r#""labels":[{"start":21,"end":26,"line":2,"column":7,"primary":true,"message":""}],"#,
+            r#""notes":[],"help":["did you mean 'count'?"]}"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn json_error_escapes_strings() {
+    assert_eq!(
+        compile_error_json("print \"\\q\";"),
+        concat!(
+            r#"{"severity":"error","phase":"lexer","code":"E0105","#,
+            r#""message":"unknown escape sequence '\\q'","file":"test.toy","#,
+            r#""labels":[{"start":7,"end":9,"line":1,"column":8,"primary":true,"message":""}],"#,
+            r#""notes":[],"help":[]}"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn unknown_error_format_is_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_toy-compiler"))
+        .args(["test.toy", "--error-format", "xml"])
+        .output()
+        .expect("failed to run toy-compiler");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown error format 'xml'"), "stderr: {stderr}");
+}
+
+#[test]
+fn unknown_color_choice_is_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_toy-compiler"))
+        .args(["run", "test.toy", "--color", "sometimes"])
+        .output()
+        .expect("failed to run toy-compiler");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown color choice 'sometimes'"), "stderr: {stderr}");
+}
+
+// ==================== Error recovery ====================
+
+#[test]
+fn every_syntax_error_is_reported() {
+    assert_eq!(
+        compile_error("let x = 1 +;\nprint x;\nprint (2;\nx = 3;\nprint x * ;"),
+        "\
+error[E0201]: expected expression, found ';'
+ --> test.toy:1:12
+  |
+1 | let x = 1 +;
+  |            ^
+
+error[E0201]: expected ')', found ';'
+ --> test.toy:3:9
+  |
+3 | print (2;
+  |         ^
+
+error[E0201]: expected expression, found ';'
+ --> test.toy:5:11
+  |
+5 | print x * ;
+  |           ^
+"
+    );
+}
+
+#[test]
+fn missing_semicolon_keeps_the_statement() {
+    // The `let` still declares `x`, and the undefined `y` is found too
+    assert_eq!(
+        compile_error("let x = 1\nprint x;\nprint y;"),
+        "\
+error[E0201]: expected ';', found 'print'
+ --> test.toy:1:10
+  |
+1 | let x = 1
+  |          ^
+
+error[E0313]: undefined variable 'y'
+ --> test.toy:3:7
+  |
+3 | print y;
+  |       ^
+"
+    );
+}
+
+#[test]
+fn recovery_skips_blocks_of_broken_statements() {
+    // The whole `if`, with its `else`, is skipped, not parsed as statements
+    assert_eq!(
+        compile_error("if 1 < { print 1; } else { print 2; }\nprint true + 1;"),
+        "\
+error[E0201]: expected expression, found '{'
+ --> test.toy:1:8
+  |
+1 | if 1 < { print 1; } else { print 2; }
+  |        ^
+
+error[E0305]: cannot apply '+' to bool and i64
+ --> test.toy:2:12
+  |
+2 | print true + 1;
+  |            ^
+"
+    );
+}
+
+#[test]
+fn recovery_inside_functions_and_blocks() {
+    let src = "\
+fn f(a) {
+    let b = a * ;
+    return a;
+}
+while true {
+    print (1;
+    break;
+}
+fn g() { return 1 }
+print f(1) + g();";
+    let stderr = compile_error(src);
+    assert_eq!(stderr.matches("error[").count(), 3, "stderr: {stderr}");
+    assert!(stderr.contains(" --> test.toy:2:17\n"), "stderr: {stderr}");
+    assert!(stderr.contains(" --> test.toy:6:13\n"), "stderr: {stderr}");
+    assert!(stderr.contains(" --> test.toy:9:18\n"), "stderr: {stderr}");
+}
+
+#[test]
+fn lexical_error_hides_other_errors() {
+    // The lexer stops at its first error, before anything is parsed
+    assert_eq!(
+        compile_error("let x = ;\nprint \"\\q\";\nlet y = 1 $ 2;\nprint +;"),
+        "\
+error[E0105]: unknown escape sequence '\\q'
+ --> test.toy:2:8
+  |
+2 | print \"\\q\";
+  |        ^^
+"
+    );
+}
+
+#[test]
+fn uses_of_broken_declarations_are_not_reported() {
+    assert_eq!(
+        compile_error("let x = ;\nfn f( { return 1; }\nprint x + f();"),
+        "\
+error[E0201]: expected expression, found ';'
+ --> test.toy:1:9
+  |
+1 | let x = ;
+  |         ^
+
+error[E0201]: expected identifier after '(' or ','
+ --> test.toy:2:7
+  |
+2 | fn f( { return 1; }
+  |       ^
+"
+    );
+}
+
+#[test]
+fn errors_that_do_not_break_parsing_are_all_reported() {
+    let stderr = compile_error("fn len(a, a) { return a; }\nbreak;\nreturn 1;");
+    assert!(stderr.contains("error[E0202]"), "stderr: {stderr}");
+    assert!(stderr.contains("error[E0203]"), "stderr: {stderr}");
+    assert!(stderr.contains("error[E0209]"), "stderr: {stderr}");
+    assert!(stderr.contains("error[E0210]"), "stderr: {stderr}");
+}
+
+#[test]
+fn every_semantic_error_is_reported() {
+    // The `let` with an error does not declare `x`, but its use is not
+    // reported again
+    assert_eq!(
+        compile_error("let x = 1 + true;\nprint x;\nprint y;\nx = 2;\nprint !1.5;"),
+        "\
+error[E0305]: cannot apply '+' to i64 and bool
+ --> test.toy:1:11
+  |
+1 | let x = 1 + true;
+  |           ^
+
+error[E0313]: undefined variable 'y'
+ --> test.toy:3:7
+  |
+3 | print y;
+  |       ^
+
+error[E0304]: mismatched types: expected bool or an integer, found f64
+ --> test.toy:5:8
+  |
+5 | print !1.5;
+  |        ^^^
+"
+    );
+}
+
+#[test]
+fn max_errors_limits_the_errors_shown() {
+    let src = "print +;\nprint *;\nprint /;\nprint %;";
+    assert_eq!(compile_error(src).matches("error[").count(), 4);
+    assert_eq!(
+        compile_error_with_args(src, &["--max-errors", "2"]),
+        "\
+error[E0201]: expected expression, found '+'
+ --> test.toy:1:7
+  |
+1 | print +;
+  |       ^
+
+error[E0201]: expected expression, found '*'
+ --> test.toy:2:7
+  |
+2 | print *;
+  |       ^
+  |
+  = note: 2 more error(s) not shown
+"
+    );
+    // Errors found by the type checker count too
+    let stderr = compile_error_with_args("print +;\nprint y;\nprint z;", &["--max-errors", "2"]);
+    assert_eq!(stderr.matches("error[").count(), 2, "stderr: {stderr}");
+    assert!(stderr.contains("undefined variable 'y'"), "stderr: {stderr}");
+    assert!(stderr.ends_with("= note: 1 more error(s) not shown\n"), "stderr: {stderr}");
+    let json = compile_error_json("print +;\nprint *;");
+    assert_eq!(json.lines().count(), 2, "stderr: {json}");
+}
+
+#[test]
+fn invalid_max_errors_is_rejected() {
+    for value in ["0", "-1", "many"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_toy-compiler"))
+            .args(["run", "test.toy", "--max-errors", value])
+            .output()
+            .expect("failed to run toy-compiler");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let expected = format!("invalid number of errors '{}'", value);
+        assert!(stderr.contains(&expected), "stderr: {stderr}");
+    }
+}
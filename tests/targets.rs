@@ -0,0 +1,150 @@
+//! Golden-assembly tests: every program in `tests/golden` is compiled with
+//! `-S` for each supported target and compared with the checked-in
+//! `<name>.<triple>.s`. Run with `UPDATE_GOLDEN=1` to regenerate the files
+//! after an intentional change to code generation.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const TRIPLES: &[&str] = &[
+    "aarch64-apple-darwin",
+    "aarch64-unknown-linux-gnu",
+    "x86_64-unknown-linux-gnu",
+];
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// Compile `src_path` for `triple` and return the generated assembly.
+fn emit_asm(src_path: &Path, triple: &str) -> String {
+    let out_path = std::env::temp_dir().join(format!(
+        "toy_golden_{}_{}_{}.s",
+        std::process::id(),
+        src_path.file_stem().unwrap().to_str().unwrap(),
+        triple
+    ));
+    let output = Command::new(env!("CARGO_BIN_EXE_toy-compiler"))
+        .arg(src_path)
+        .args(["--target", triple, "-S", "-o"])
+        .arg(&out_path)
+        .output()
+        .expect("failed to run toy-compiler");
+    assert!(
+        output.status.success(),
+        "compiling {} for {} failed: {}",
+        src_path.display(),
+        triple,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let asm = fs::read_to_string(&out_path).unwrap();
+    let _ = fs::remove_file(&out_path);
+    asm
+}
+
+fn check_golden(name: &str) {
+    let src_path = golden_dir().join(format!("{name}.toy"));
+    for triple in TRIPLES {
+        let asm = emit_asm(&src_path, triple);
+        let golden_path = golden_dir().join(format!("{name}.{triple}.s"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&golden_path, &asm).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden_path)
+            .unwrap_or_else(|e| panic!("reading {}: {}", golden_path.display(), e));
+        assert_eq!(
+            asm, expected,
+            "assembly for {name}.toy on {triple} differs from {}",
+            golden_path.display()
+        );
+    }
+}
+
+#[test]
+fn golden_arith() {
+    check_golden("arith");
+}
+
+#[test]
+fn golden_compare() {
+    check_golden("compare");
+}
+
+#[test]
+fn golden_if_else() {
+    check_golden("if_else");
+}
+
+#[test]
+fn golden_while_loop() {
+    check_golden("while_loop");
+}
+
+#[test]
+fn golden_functions() {
+    check_golden("functions");
+}
+
+#[test]
+fn golden_logical() {
+    check_golden("logical");
+}
+
+#[test]
+fn golden_bitwise() {
+    check_golden("bitwise");
+}
+
+#[test]
+fn golden_compound_assign() {
+    check_golden("compound_assign");
+}
+
+#[test]
+fn golden_strings() {
+    check_golden("strings");
+}
+
+#[test]
+fn golden_format() {
+    check_golden("format");
+}
+
+#[test]
+fn golden_arrays() {
+    check_golden("arrays");
+}
+
+#[test]
+fn golden_int_types() {
+    check_golden("int_types");
+}
+
+#[test]
+fn golden_floats() {
+    check_golden("floats");
+}
+
+#[test]
+fn golden_consts() {
+    check_golden("consts");
+}
+
+#[test]
+fn golden_input() {
+    check_golden("input");
+}
+
+#[test]
+fn unknown_target_is_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_toy-compiler"))
+        .arg(golden_dir().join("arith.toy"))
+        .args(["--target", "riscv64-unknown-linux-gnu", "-S", "-o", "/dev/null"])
+        .output()
+        .expect("failed to run toy-compiler");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unsupported target"), "stderr: {stderr}");
+}